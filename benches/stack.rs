@@ -0,0 +1,133 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+use yaslapi::{
+    aux::{HashableObject, Object},
+    State,
+};
+
+/// Number of items in the converted lists and tables.
+const LEN: i64 = 10_000;
+
+/// Benchmark pushing and popping scalars.
+fn push_pop(c: &mut Criterion) {
+    let mut state = State::from_source("");
+    c.bench_function("push_pop/int", |b| {
+        b.iter(|| {
+            state.push_int(black_box(42));
+            black_box(state.pop_int())
+        });
+    });
+    c.bench_function("push_pop/str", |b| {
+        b.iter(|| {
+            state.push_str(black_box("forty-two"));
+            black_box(state.pop_str())
+        });
+    });
+    c.bench_function("push_pop/interned", |b| {
+        b.iter(|| {
+            state.push_interned(black_box("forty-two"));
+            black_box(state.pop_str())
+        });
+    });
+}
+
+/// Benchmark popping lists and tables as `Object`s. The value is pushed again for each iteration,
+/// so the `push_*` benchmarks give the baseline cost of building it.
+fn pop_object(c: &mut Criterion) {
+    let mut state = State::from_source("");
+    let ints = (0..LEN).collect::<Vec<_>>();
+    let strs = (0..LEN).map(|i| i.to_string()).collect::<Vec<_>>();
+    let table = (0..LEN)
+        .map(|i| (i.to_string(), i))
+        .collect::<HashMap<_, _>>();
+
+    c.bench_function("push_vec/int", |b| {
+        b.iter(|| {
+            state.push_vec(&ints).unwrap();
+            state.pop();
+        });
+    });
+    c.bench_function("pop_object/list_int", |b| {
+        b.iter(|| {
+            state.push_vec(&ints).unwrap();
+            black_box(state.pop_object(None).unwrap())
+        });
+    });
+    c.bench_function("pop_object/list_str", |b| {
+        b.iter(|| {
+            state.push_vec(&strs).unwrap();
+            black_box(state.pop_object(None).unwrap())
+        });
+    });
+    c.bench_function("push_map/str_int", |b| {
+        b.iter(|| {
+            state.push_map(&table).unwrap();
+            state.pop();
+        });
+    });
+    c.bench_function("pop_object/table_str_int", |b| {
+        b.iter(|| {
+            state.push_map(&table).unwrap();
+            black_box(state.pop_object(None).unwrap())
+        });
+    });
+}
+
+/// Benchmark converting a nested `Object` to and from the stack.
+fn table_conversion(c: &mut Criterion) {
+    let mut state = State::from_source("");
+    let rows = (0..LEN / 10)
+        .map(|i| {
+            let mut row = HashMap::new();
+            row.insert(HashableObject::Str("id".to_owned()), Object::Int(i));
+            row.insert(
+                HashableObject::Str("name".to_owned()),
+                Object::Str(i.to_string()),
+            );
+            row.insert(
+                HashableObject::Str("tags".to_owned()),
+                Object::List(vec![Object::Bool(true); 3]),
+            );
+            Object::Table(row)
+        })
+        .collect::<Vec<_>>();
+    let object = Object::List(rows);
+
+    c.bench_function("push_object/rows", |b| {
+        b.iter(|| {
+            state.push_object(black_box(&object)).unwrap();
+            state.pop();
+        });
+    });
+    c.bench_function("round_trip/rows", |b| {
+        b.iter(|| {
+            state.push_object(black_box(&object)).unwrap();
+            black_box(state.pop_object(None).unwrap())
+        });
+    });
+}
+
+criterion_group!(benches, push_pop, pop_object, table_conversion);
+criterion_main!(benches);
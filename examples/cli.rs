@@ -1,12 +1,53 @@
 use clap::{arg, command, Parser};
-use rustyline::{error::ReadlineError, DefaultEditor};
-use yaslapi::State;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, Helper,
+};
+use std::{cell::RefCell, rc::Rc};
+use yaslapi::{
+    aux::Object,
+    repl::{ReplResult, ReplSession},
+    State,
+};
 
 // C-style function to quit from the REPL.
 unsafe extern "C" fn repl_quit(_: *mut yaslapi_sys::YASL_State) -> i32 {
     std::process::exit(0);
 }
 
+// Line editor helper which completes identifiers against the REPL's state.
+struct YaslHelper {
+    session: Rc<RefCell<ReplSession>>,
+}
+
+impl Completer for YaslHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        // Complete the dotted identifier ending at the cursor.
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '.')))
+            .map_or(0, |i| i + 1);
+        let candidates = self
+            .session
+            .borrow_mut()
+            .state()
+            .completions(&line[start..pos]);
+        Ok((start, candidates))
+    }
+}
+impl Hinter for YaslHelper {
+    type Hint = String;
+}
+impl Highlighter for YaslHelper {}
+impl Validator for YaslHelper {}
+impl Helper for YaslHelper {}
+
 // Constants that are better defined manually.
 const ABOUT: &str =
     "A reference implementation command line interface for Yet Another Scripting Language (YASL).";
@@ -32,9 +73,10 @@ struct Arguments {
     #[arg(short = 'E', long, default_value_t = false)]
     execute: bool,
 
-    /// Path to an optional script (or literal source with -e or -E) to execute.
+    /// Path to an optional script (or literal source with -e or -E) to execute,
+    /// followed by arguments for the script, which are available as the list `args`.
     #[arg(trailing_var_arg = true)]
-    input: Option<String>,
+    input: Vec<String>,
 }
 
 fn main() {
@@ -42,9 +84,10 @@ fn main() {
     let args = Arguments::parse();
 
     // Helper function to execute source code.
-    let execute_helper = |src: &str, args_compile, args_execute_print| {
+    let execute_helper = |src: &str, script_args: &[String], args_compile, args_execute_print| {
         let mut state = State::from_source(&src);
         state.declare_libs();
+        state.set_args(script_args);
 
         let _ = if args_compile {
             state.compile()
@@ -59,16 +102,17 @@ fn main() {
 
     // Check if we were given source expressions from the arguments.
     if args.execute_print || args.execute {
-        if let Some(input) = args.input {
-            execute_helper(&input, args.compile, args.execute_print);
+        if let Some(input) = args.input.first() {
+            execute_helper(input, &args.input, args.compile, args.execute_print);
         }
         return;
     }
 
     // Check if we were given a script location from the arguments.
-    if let Some(input) = args.input {
-        let mut state = State::from_path(&input).expect("Could not read file.");
+    if let Some(input) = args.input.first() {
+        let mut state = State::from_path(input).expect("Could not read file.");
         state.declare_libs();
+        state.set_args(&args.input);
 
         let _ = if args.compile {
             state.compile()
@@ -78,40 +122,52 @@ fn main() {
         return;
     }
 
-    // Create a new state.
-    let mut state = State::default();
-
-    // Declare the standard library.
-    state.declare_libs();
+    // Create a new REPL session, which declares the standard library.
+    let session = Rc::new(RefCell::new(ReplSession::new()));
 
     // Add a global `quit` function.
-    state.push_cfunction(repl_quit, 0);
-    state.init_global_slice("quit").unwrap();
-
-    // Create a new single line editor.
-    let mut reader = DefaultEditor::new().expect("Could not allocate a default line editor.");
+    session.borrow_mut().state().push_cfunction(repl_quit, 0);
+    session
+        .borrow_mut()
+        .state()
+        .init_global_slice("quit")
+        .unwrap();
+
+    // Create a new single line editor with tab-completion.
+    let mut reader: Editor<YaslHelper, DefaultHistory> =
+        Editor::new().expect("Could not allocate a default line editor.");
+    reader.set_helper(Some(YaslHelper {
+        session: Rc::clone(&session),
+    }));
 
     // Run the REPL.
     loop {
-        // Console prompt.
-        match reader.readline("yasl> ") {
-            Ok(mut line) => {
+        // Console prompt, which changes while a multi-line input is unfinished.
+        let prompt = if session.borrow().is_pending() {
+            "...   "
+        } else {
+            "yasl> "
+        };
+        match reader.readline(prompt) {
+            Ok(line) => {
                 // Append to the history.
                 let _ = reader.add_history_entry(line.as_str());
 
-                // Append a newline character.
-                line.push('\n');
-
-                // Recreate the execution state from the input.
-                state.reset_from_source(&line);
-
-                let _ = if args.compile {
-                    // Compile the source.
-                    state.compile()
-                } else {
-                    // Execute the REPL.
-                    state.execute_repl()
-                };
+                let mut session = session.borrow_mut();
+                if args.compile {
+                    // Only compile the source.
+                    let state = session.state();
+                    state.reset_from_source(&format!("{line}\n"));
+                    let _ = state.compile();
+                    continue;
+                }
+
+                // Print the value of expressions the same way YASL would.
+                if let Ok(ReplResult::Value(value)) = session.feed(&line) {
+                    if !matches!(value, Object::Undef) {
+                        println!("{}", value.to_yasl_string());
+                    }
+                }
             }
             Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => {
                 println!("Quit signal received.");
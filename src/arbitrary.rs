@@ -0,0 +1,98 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::aux::{HashableF64, HashableObject, Object};
+
+/// The deepest nesting of lists and tables in a generated `Object`.
+const MAX_DEPTH: usize = 4;
+
+/// Generates plain data with at most `MAX_DEPTH` levels of nested lists and tables, and never user data or pointers.
+/// Table keys are bools, ints, or strings, which YASL never considers equal to each other, and table values
+/// are never `undef`, which YASL would remove. Strings never contain zero bytes, since popping a string stops at
+/// the first one. So, pushing and popping a generated object gives an equal object.
+impl<'a> Arbitrary<'a> for Object {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_object(u, MAX_DEPTH)
+    }
+}
+
+/// Generates keys of any type except user pointers. Strings never contain zero bytes.
+impl<'a> Arbitrary<'a> for HashableObject {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=4)? {
+            0 => Self::Undef,
+            1 => Self::Float(HashableF64(u.arbitrary()?)),
+            _ => arbitrary_key(u)?,
+        })
+    }
+}
+
+/// Generate an object with at most `depth` levels of nested lists and tables.
+fn arbitrary_object(u: &mut Unstructured<'_>, depth: usize) -> Result<Object> {
+    let variants = if depth == 0 { 4 } else { 6 };
+    Ok(match u.int_in_range(0..=variants)? {
+        0 => Object::Undef,
+        1 => Object::Bool(u.arbitrary()?),
+        2 => Object::Int(u.arbitrary()?),
+        3 => Object::Float(u.arbitrary()?),
+        4 => Object::Str(arbitrary_str(u)?),
+        5 => {
+            let len = u.arbitrary_len::<Object>()?;
+            Object::List(
+                (0..len)
+                    .map(|_| arbitrary_object(u, depth - 1))
+                    .collect::<Result<_>>()?,
+            )
+        }
+        _ => {
+            let len = u.arbitrary_len::<(HashableObject, Object)>()?;
+            let mut table = HashMap::with_capacity(len);
+            for _ in 0..len {
+                let value = match arbitrary_object(u, depth - 1)? {
+                    Object::Undef => Object::Bool(false),
+                    value => value,
+                };
+                table.insert(arbitrary_key(u)?, value);
+            }
+            Object::Table(table)
+        }
+    })
+}
+
+/// Generate a bool, int, or string key.
+fn arbitrary_key(u: &mut Unstructured<'_>) -> Result<HashableObject> {
+    Ok(match u.int_in_range(0..=2)? {
+        0 => HashableObject::Bool(u.arbitrary()?),
+        1 => HashableObject::Int(u.arbitrary()?),
+        _ => HashableObject::Str(arbitrary_str(u)?),
+    })
+}
+
+/// Generate a string without zero bytes.
+fn arbitrary_str(u: &mut Unstructured<'_>) -> Result<String> {
+    let s: String = u.arbitrary()?;
+    Ok(s.replace('\0', ""))
+}
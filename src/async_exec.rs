@@ -0,0 +1,92 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use tokio::task::JoinHandle;
+
+use crate::{aux::Object, host::call_global, send_cell::SendCell, State, StateError, StateSuccess};
+
+/// Aborts the wrapped blocking task when dropped, so that a dropped future
+/// prevents work which has not yet started from ever running.
+struct AbortOnDrop<T>(JoinHandle<T>);
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Run `f` with the given `State` on tokio's blocking thread pool and hand the state back.
+/// The YASL VM cannot be interrupted, so dropping the future only cancels the work
+/// if it has not started yet. Otherwise, the state is dropped once the VM finishes.
+async fn run_blocking<R: Send + 'static>(
+    state: State,
+    f: impl FnOnce(&mut State) -> R + Send + 'static,
+) -> (State, R) {
+    // The state is only used by the blocking task until it is handed back, and the user data it owns is `Send`.
+    let state = SendCell(state);
+    let mut handle = AbortOnDrop(tokio::task::spawn_blocking(move || {
+        let mut state = state.into_inner();
+        let r = f(&mut state);
+        SendCell((state, r))
+    }));
+
+    match (&mut handle.0).await {
+        Ok(result) => result.into_inner(),
+        // The task is only aborted when this future is dropped, so any error is a panic.
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+impl State {
+    /// Execute the state's bytecode on tokio's blocking thread pool.
+    /// The state is moved into the future and returned alongside the result once execution completes.
+    /// It runs on another thread, which is sound because `State::push_userdata_box` only accepts `Send` data.
+    /// Dropping the future before the blocking task starts cancels the execution.
+    /// # Errors
+    /// The returned result has the same errors as `execute`.
+    pub async fn execute_async(self) -> (Self, Result<StateSuccess, StateError>) {
+        run_blocking(self, Self::execute).await
+    }
+
+    /// Call the global function `name` with the given arguments on tokio's blocking thread pool.
+    /// Returns the state alongside the function's return values, in the order they were returned.
+    /// Dropping the future before the blocking task starts cancels the call.
+    /// # Errors
+    /// The returned result is `StateError::Generic` if the global does not exist, `StateError::TypeError` if it is
+    /// not a function, or an error from converting the arguments or return values.
+    /// # Safety
+    /// The function must not raise a runtime error. The call isn't made inside `execute`,
+    /// which is the only place YASL can recover from one.
+    pub async unsafe fn call_global_async(
+        self,
+        name: impl Into<String>,
+        args: Vec<Object>,
+    ) -> (Self, Result<Vec<Object>, StateError>) {
+        let name = name.into();
+        let args = SendCell(args);
+        let (state, results) = run_blocking(self, move |state| {
+            // SAFETY: The caller guarantees that the function doesn't raise a runtime error.
+            SendCell(unsafe { call_global(state, &name, &args.into_inner()) })
+        })
+        .await;
+        (state, results.into_inner())
+    }
+}
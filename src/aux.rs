@@ -21,24 +21,32 @@
 // SOFTWARE.
 
 use std::{
+    cmp::Ordering,
     collections::HashMap,
     ffi::{CStr, CString},
+    fmt::{self, Display},
+    os::raw::c_int,
     ptr::NonNull,
 };
 
 use yaslapi_sys::YASL_State;
 
-use crate::{CFunction, InvalidIdentifier, State, StateError, Type, LIFETIME_CSTRINGS};
+use crate::{modules::run_lib_fn, CFunction, Error, InvalidIdentifier, State, StateError, Type};
 
 /// Helper type for wrapping a C-style function pointer.
 pub struct YaslCFn {
     pub cfn: unsafe extern "C" fn(*mut YASL_State) -> i32,
     pub args: isize,
+    /// The doc comment of a function with typed parameters, one line per line of the comment.
+    pub doc: &'static str,
+    /// The name and YASL type of each typed parameter, which is empty for other functions.
+    pub params: &'static [(&'static str, &'static str)],
 }
 
 #[macro_export]
 /// A helper macro for defining a function that can act as a callback for the YASL runtime.
 /// The macro will define an `unsafe extern "C" fn` and a `YaslCFn` struct with a reference to it.
+/// The body receives the state as a `StateRef`, which can't outlive the call.
 /// # Examples
 /// ```
 /// yaslapi::new_cfn! {
@@ -53,16 +61,49 @@ pub struct YaslCFn {
 /// }
 /// assert_eq!(PRINT_HELLO_GET_42.args, 0);
 /// ```
+/// Functions can also declare typed parameters instead of an argument count. The arguments are checked and popped
+/// before the body runs, raising a `TypeError` for an argument of the wrong type, and the body returns a `Result`
+/// of the values to push, e.g., a tuple for several values. Parameters can have any type which implements
+/// `convert::FromYasl`, such as `String` for strings, and `Option<T>` for parameters which may be `undef`.
+/// Their doc comments, names, and parameters are recorded by `State::init_function` for `State::api_docs`.
+/// A parameter can be given a default, e.g., `n: i64 = 1`, which is used when the argument is `undef`,
+/// including when a script omits it, since YASL fills in missing arguments with `undef`.
+/// ```
+/// yaslapi::new_cfn! {
+///     /// Repeats a string `n` times, or once if `n` is omitted.
+///     REPEAT(state, (s: String, n: i64 = 1)) => {
+///         let n = usize::try_from(n).map_err(|_| {
+///             yaslapi::aux::ScriptError::new(yaslapi::StateError::ValueError, "ValueError: repeat expected a non-negative int.")
+///         })?;
+///         Ok(s.repeat(n))
+///     }
+/// }
+/// assert_eq!(REPEAT.args, 2);
+/// ```
 macro_rules! new_cfn {
+    // The type a typed parameter is popped as, which is an `Option` if the parameter has a default.
+    (@param_type $t:ty = $default:expr) => {
+        Option<$t>
+    };
+    (@param_type $t:ty) => {
+        $t
+    };
+
+    // Replace a missing or `undef` argument with the parameter's default, if it has one.
+    (@param_default $arg:ident = $default:expr) => {
+        let $arg = $arg.unwrap_or_else(|| $default);
+    };
+    (@param_default $arg:ident) => {};
+
     // Primary variant for functions to use.
     ($(#[$attr:meta])* $name:ident($state:ident) $args:expr => $func:expr) => {
         $(#[$attr])*
         paste::paste! {
-            unsafe extern "C" fn [<$name:lower _impl>](state: *mut yaslapi_sys::YASL_State) -> i32 {
-                let mut $state: yaslapi::State = state.try_into().expect("State is null");
+            unsafe extern "C" fn [<$name:lower _impl>](mut state: *mut yaslapi_sys::YASL_State) -> i32 {
+                let mut $state = yaslapi::StateRef::new(&mut state).expect("State is null");
                 $func
             }
-            const $name: yaslapi::aux::YaslCFn = yaslapi::aux::YaslCFn { cfn: [<$name:lower _impl>], args: $args };
+            const $name: yaslapi::aux::YaslCFn = yaslapi::aux::YaslCFn { cfn: [<$name:lower _impl>], args: $args, doc: "", params: &[] };
         }
     };
 
@@ -73,12 +114,208 @@ macro_rules! new_cfn {
             unsafe extern "C" fn [<$name:lower _impl>](_: *mut yaslapi_sys::YASL_State) -> i32 {
                 $func
             }
-            const $name: yaslapi::aux::YaslCFn = yaslapi::aux::YaslCFn { cfn: [<$name:lower _impl>], args: $args };
+            const $name: yaslapi::aux::YaslCFn = yaslapi::aux::YaslCFn { cfn: [<$name:lower _impl>], args: $args, doc: "", params: &[] };
         }
-    }
+    };
+
+    // This variant is used for functions with typed parameters, whose body returns a `Result` of the values to push.
+    // Its doc comment is kept for `State::api_docs`, so it only accepts doc comments as attributes.
+    ($(#[doc = $doc:literal])* $name:ident($state:ident, ($($arg:ident: $t:ty $(= $default:expr)?),* $(,)?)) => $func:expr) => {
+        $(#[doc = $doc])*
+        paste::paste! {
+            unsafe extern "C" fn [<$name:lower _impl>](state: *mut yaslapi_sys::YASL_State) -> i32 {
+                $crate::aux::run_host_fn(state, |$state| {
+                    let fn_name = stringify!([<$name:lower>]);
+                    $crate::aux::check_args($state, fn_name, &[$((
+                        <$crate::new_cfn!(@param_type $t $(= $default)?) as $crate::convert::FromYasl>::is_n
+                            as $crate::aux::ArgCheck,
+                        <$t as $crate::convert::FromYasl>::TYPE_NAME,
+                    )),*])?;
+                    let ($($arg,)*): ($($crate::new_cfn!(@param_type $t $(= $default)?),)*) =
+                        $crate::convert::FromYasl::pop_from(&mut *$state)
+                        .map_err(|e| $crate::aux::ScriptError::new(e, format!("{e:?}: {fn_name} received invalid arguments.")))?;
+                    $($crate::new_cfn!(@param_default $arg $(= $default)?);)*
+
+                    let value = (|| -> Result<_, $crate::aux::ScriptError> { $func })()?;
+                    let base = $state.stack_len();
+                    $state
+                        .push(value)
+                        .map_err(|e| $crate::aux::ScriptError::new(e, format!("{e:?}: {fn_name} could not return its value.")))?;
+                    Ok($state.stack_len() - base)
+                })
+            }
+            const $name: $crate::aux::YaslCFn = $crate::aux::YaslCFn {
+                cfn: [<$name:lower _impl>],
+                args: <[&str]>::len(&[$(stringify!($arg)),*]) as isize,
+                doc: concat!($($doc, "\n"),*),
+                params: &[$((stringify!($arg), <$t as $crate::convert::FromYasl>::TYPE_NAME)),*],
+            };
+        }
+    };
 }
 pub use new_cfn;
 
+#[macro_export]
+/// Check the types of the arguments of a host function in one place, before popping them, e.g., at the top of a
+/// function run with `run_host_fn`. Each type is the name of a `Type` variant, followed by `?` if the argument
+/// may also be `undef`. The name of the function can be given before the types for error messages.
+/// YASL fills in missing arguments of functions with a fixed number of arguments with `undef`,
+/// so missing arguments are reported as having the wrong type unless they are optional.
+/// Expands to a `Result<(), ScriptError>` with a `TypeError` in the same format as YASL's functions.
+/// # Examples
+/// ```
+/// use yaslapi::aux::run_host_fn;
+///
+/// unsafe extern "C" fn get_or(state: *mut yaslapi_sys::YASL_State) -> i32 {
+///     run_host_fn(state, |state| {
+///         yaslapi::expect_args!(state, "get_or", [Table, Str, Int?])?;
+///         let default = state.pop_checked::<Option<i64>>().unwrap_or_default();
+///         let key = state.pop_str().unwrap_or_default();
+///         state.pop();
+///         state.push_int(default.unwrap_or(key.len() as i64));
+///         Ok(1)
+///     })
+/// }
+/// ```
+macro_rules! expect_args {
+    // Collect the expected types into an array, one type at a time.
+    (@types [$($out:tt)*]) => {
+        [$($out)*]
+    };
+    (@types [$($out:tt)*] $t:ident ? $(, $($rest:tt)*)?) => {
+        $crate::expect_args!(@types [$($out)* ($crate::Type::$t, true),] $($($rest)*)?)
+    };
+    (@types [$($out:tt)*] $t:ident $(, $($rest:tt)*)?) => {
+        $crate::expect_args!(@types [$($out)* ($crate::Type::$t, false),] $($($rest)*)?)
+    };
+
+    ($state:expr, [$($types:tt)*]) => {
+        $crate::expect_args!($state, "function", [$($types)*])
+    };
+    ($state:expr, $name:expr, [$($types:tt)*]) => {
+        $crate::aux::expect_arg_types(&$state, $name, &$crate::expect_args!(@types [] $($types)*))
+    };
+}
+pub use expect_args;
+
+/// An error raised by a host function or one of the optional libraries, which is thrown to the YASL VM.
+#[derive(Debug, PartialEq)]
+pub struct ScriptError {
+    /// The error which the script receives.
+    pub error: StateError,
+    /// The message printed to the state's error output, e.g., `ValueError: expected a positive int.`.
+    pub message: String,
+}
+
+impl ScriptError {
+    /// Create an error with the given message, which should follow YASL's format, e.g., `ValueError: ...`.
+    #[must_use]
+    pub fn new(error: StateError, message: impl Into<String>) -> Self {
+        Self {
+            error,
+            message: message.into(),
+        }
+    }
+
+    /// Create the same error as YASL's functions raise when argument `n`, counting from zero, has the wrong type.
+    pub(crate) fn bad_arg_type(state: &State, fn_name: &str, n: usize, expected: &str) -> Self {
+        let actual = state
+            .peek_n_typename(n)
+            .map_or("undef".into(), CStr::to_string_lossy);
+        Self::new(
+            StateError::TypeError,
+            format!("TypeError: {fn_name} expected arg in position {n} to be of type {expected}, got arg of type {actual}."),
+        )
+    }
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Run the body of a host function with a `State` wrapping the raw pointer, and return its number of return values,
+/// so that the function can use `?` instead of YASL's return codes. An error is printed and then thrown to the YASL VM,
+/// after everything owned by the body, including the error's message, has been dropped.
+/// # Examples
+/// ```
+/// use yaslapi::{aux::{run_host_fn, ScriptError}, StateError};
+///
+/// unsafe extern "C" fn sqrt(state: *mut yaslapi_sys::YASL_State) -> i32 {
+///     run_host_fn(state, |state| {
+///         let x = state.pop_float();
+///         if x < 0.0 {
+///             return Err(ScriptError::new(StateError::ValueError, "ValueError: sqrt expected a non-negative float."));
+///         }
+///         state.push_float(x.sqrt());
+///         Ok(1)
+///     })
+/// }
+/// ```
+/// # Panics
+/// The state pointer must not be null.
+pub fn run_host_fn(
+    state: *mut YASL_State,
+    f: impl FnOnce(&mut State) -> Result<usize, ScriptError>,
+) -> c_int {
+    run_lib_fn(state, |state| {
+        let returns = f(state)?;
+        c_int::try_from(returns).map_err(|_| {
+            ScriptError::new(
+                StateError::ValueError,
+                "ValueError: too many return values.",
+            )
+        })
+    })
+}
+
+/// Checks whether the value at the given index of the arguments has the expected type, e.g., `FromYasl::is_n`.
+pub type ArgCheck = fn(&State, usize) -> bool;
+
+/// Check the types of the arguments of the host function `fn_name` without popping them, as `new_cfn!` does for typed
+/// parameters. Each entry checks the argument at the same index, counting from the first argument, and names its type.
+/// # Errors
+/// Returns a `TypeError` in the same format as YASL's functions for the first argument with the wrong type.
+pub fn check_args(
+    state: &State,
+    fn_name: &str,
+    expected: &[(ArgCheck, &str)],
+) -> Result<(), ScriptError> {
+    for (n, (is_n, type_name)) in expected.iter().enumerate() {
+        if !is_n(state, n) {
+            return Err(ScriptError::bad_arg_type(state, fn_name, n, type_name));
+        }
+    }
+    Ok(())
+}
+
+/// Check the types of the arguments of the host function `fn_name` without popping them, as `expect_args!` does.
+/// Each entry gives the type of the argument at the same index, counting from the first argument,
+/// and whether the argument may also be `undef`. All kinds of functions match `Type::Fn`.
+/// # Errors
+/// Returns a `TypeError` in the same format as YASL's functions for the first argument with the wrong type.
+pub fn expect_arg_types(
+    state: &State,
+    fn_name: &str,
+    expected: &[(Type, bool)],
+) -> Result<(), ScriptError> {
+    for (n, (expected, optional)) in expected.iter().enumerate() {
+        let actual = state.peek_n_type(n);
+        if actual.name() != expected.name() && !(*optional && actual == Type::Undef) {
+            return Err(ScriptError::bad_arg_type(
+                state,
+                fn_name,
+                n,
+                expected.name(),
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// Helper for specifying the functions for a metatable.
 /// Each function will need an identifier, a C-style function, and the number of arguments.
 /// The number of arguments is signed to allow for variadic C functions when negative.
@@ -88,63 +325,73 @@ pub struct MetatableFunction<'a> {
     pub args: isize,
 }
 
+/// Names of the globals declared by `State::declare_libs`.
+const LIB_GLOBALS: [&str; 8] = [
+    "collections",
+    "error",
+    "io",
+    "math",
+    "require",
+    "__require_c__",
+    "mt",
+    "os",
+];
+
 impl State {
     /// Loads all standard libraries into the state and declares them with their default names.
     pub fn declare_libs(&mut self) {
         unsafe {
             yaslapi_sys::YASLX_decllibs(self.state.as_ptr());
         }
+        for name in LIB_GLOBALS {
+            self.record_global(name);
+        }
+        self.lazy_libraries.clear();
+        self.apply_require_overrides();
     }
 
     /// Declares a global variable with the given name and initializes it with the top of the stack.
     /// The top of the stack is popped after the global is initialized.
+    /// YASL keeps a pointer to the name of each global, so the name is copied into the state's interned names,
+    /// which live as long as the state. Borrowed states keep their names for the lifetime of the program instead.
     /// # Errors
     /// Will return an `InvalidIdentifier` if the given name is not a valid YASL identifier.
-    pub fn init_global(&mut self, name: &'static CStr) -> Result<(), InvalidIdentifier> {
-        // Ensure that the name is a valid YASL identifier.
-        if !name.to_str().map_or(false, crate::is_valid_identifier) {
-            return Err(InvalidIdentifier);
-        }
+    pub fn init_global(&mut self, name: &CStr) -> Result<(), InvalidIdentifier> {
+        // Ensure that the name is a valid YASL identifier. Non-UTF-8 bytes are reported as a replacement character.
+        let name_str = name.to_string_lossy();
+        crate::validate_identifier(&name_str)?;
+        self.record_global(&name_str);
 
         // Initialize the global variable.
-        unsafe {
-            yaslapi_sys::YASLX_initglobal(self.state.as_ptr(), name.as_ptr());
-        }
-
+        let var_name = self.lifetime_cstr(name.to_owned());
+        unsafe { yaslapi_sys::YASLX_initglobal(self.state.as_ptr(), var_name) };
+        self.shrink_stack(1);
         Ok(())
     }
     /// Declares a global variable with the given name and initializes it with the top of the stack.
     /// The top of the stack is popped after the global is initialized.
-    /// The string `name` is copied as a new `CString` to a static `HashSet<_>` to provide
-    /// a valid C-string pointer for the lifetime of the program, as YASL requires.
+    /// The name is kept alive in the same way as for `init_global`.
     /// # Errors
     /// Will return an `InvalidIdentifier` if the given name is not a valid YASL identifier.
-    #[allow(clippy::missing_panics_doc)] // Unwrapping mutex lock should never fail.
+    #[allow(clippy::missing_panics_doc)] // Valid identifiers don't contain zero bytes.
     pub fn init_global_slice(&mut self, name: &str) -> Result<(), InvalidIdentifier> {
-        // Ensure that the name is a valid YASL identifier.
-        if !crate::is_valid_identifier(name) {
-            return Err(InvalidIdentifier);
-        }
-
-        let var_name = CString::new(name).map_err(|_| InvalidIdentifier)?;
-        let mut lifetime_strings = LIFETIME_CSTRINGS.lock().unwrap();
-
-        // Ensure that if the C-string is already in our map that we use the original pointer.
-        let existing_cstr = lifetime_strings.get(&var_name);
-
-        // Initialize the global variable.
-        unsafe {
-            yaslapi_sys::YASLX_initglobal(
-                self.state.as_ptr(),
-                existing_cstr.unwrap_or(&var_name).as_ptr(),
-            );
-        }
+        // Ensure that the name is a valid YASL identifier, which also rules out internal zero bytes.
+        crate::validate_identifier(name)?;
+        self.init_global(&CString::new(name).unwrap())
+    }
 
-        if existing_cstr.is_none() {
-            // Prevent the C-string from being dropped.
-            lifetime_strings.insert(var_name);
+    /// Declares the global list `args` containing the given strings, as the reference YASL
+    /// interpreter does for command-line arguments. By convention, the first argument is the script.
+    #[allow(clippy::missing_panics_doc)] // Pushing to a new list and initializing `args` can't fail.
+    pub fn set_args<S: AsRef<str>>(&mut self, args: &[S]) {
+        self.push_list();
+        for arg in args {
+            self.push_str(arg.as_ref());
+            self.list_push()
+                .expect("Internal Error: Unable to push to a new list.");
         }
-        Ok(())
+        self.init_global_slice("args")
+            .expect("Internal Error: `args` is a valid identifier.");
     }
 
     /// Inserts all functions in the array into a new table on top of the stack.
@@ -163,8 +410,7 @@ impl State {
 
         // Create a YASL function for each function in the array.
         for f in functions {
-            let name = CString::new(f.name).unwrap();
-            let name_pointer = name.as_ptr();
+            let name_pointer = self.lifetime_cstr(CString::new(f.name).unwrap());
 
             // Create a YASL function from the given data.
             yasl_fns.push(yaslapi_sys::YASLX_function {
@@ -172,9 +418,6 @@ impl State {
                 fn_: Some(f.cfn),
                 args: f.args as std::os::raw::c_int,
             });
-
-            // Prevent the C-string from being dropped.
-            LIFETIME_CSTRINGS.lock().unwrap().insert(name);
         }
         // Every list must end with this entry.
         yasl_fns.push(SENTINEL_FUNCTION);
@@ -182,6 +425,56 @@ impl State {
         unsafe { yaslapi_sys::YASLX_tablesetfunctions(self.state.as_ptr(), yasl_fns.as_mut_ptr()) }
     }
 
+    /// Push the value stored under `key` in the table on top of the stack, or `undef` if there is none.
+    /// The table stays on the stack below the value. YASL's API can't index a table directly, so
+    /// the entries are searched in order, which takes time linear in the size of the table.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is not a table.
+    pub fn table_get_key(&mut self, key: &HashableObject) -> Result<(), StateError> {
+        if !self.is_table() {
+            return Err(StateError::TypeError);
+        }
+
+        let mut previous = HashableObject::Undef;
+        self.push_undef();
+        while self.table_next() {
+            self.pop();
+            let current = self.peek_key();
+            if current == *key {
+                // Step back to the previous key so that `table_next` pushes the entry again, then drop its key.
+                self.pop();
+                self.push_object(&previous.into())?;
+                self.table_next();
+                return self.remove(1);
+            }
+            previous = current;
+        }
+        self.push_undef();
+        Ok(())
+    }
+
+    /// Store `value` under `key` in the table on top of the stack, which stays on the stack.
+    /// Unlike `table_set`, the key and value don't have to be pushed in order first.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is not a table,
+    /// or an error if the value could not be pushed.
+    pub fn table_set_key(
+        &mut self,
+        key: &HashableObject,
+        value: &Object,
+    ) -> Result<(), StateError> {
+        if !self.is_table() {
+            return Err(StateError::TypeError);
+        }
+
+        self.push_object(&key.clone().into())?;
+        if let Err(e) = self.push_object(value) {
+            self.pop();
+            return Err(e);
+        }
+        self.table_set().map(|_| ())
+    }
+
     /* Crate-Specific Helpers */
     /* ********************** */
 
@@ -202,81 +495,218 @@ impl State {
         self.pop_object(expected_type)
     }
 
+    /// Push the object and store it in the existing global `name`, the counterpart of `pop_global_slice`.
+    /// # Errors
+    /// Will return an error if the object could not be pushed, or `StateError::Generic` if the global
+    /// does not exist or is `const`, in which case the pushed object is popped again.
+    /// # Panics
+    /// The string slice `name` must not contain internal zero bytes.
+    pub fn set_global_value(&mut self, name: &str, value: &Object) -> Result<(), StateError> {
+        self.push_object(value)?;
+        if let Err(e) = self.set_global_slice(name) {
+            self.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Declare the global `name` and initialize it with the object.
+    /// # Errors
+    /// Will return `Error::InvalidIdentifier` if the given name is not a valid YASL identifier,
+    /// as `init_global_slice` does, or `Error::State` if the object could not be pushed.
+    #[allow(clippy::missing_panics_doc)] // The name was validated before the object was pushed.
+    pub fn init_global_value(&mut self, name: &str, value: &Object) -> Result<(), Error> {
+        crate::validate_identifier(name)?;
+        self.push_object(value)?;
+        self.init_global_slice(name)
+            .expect("Internal Error: The global's name was validated.");
+        Ok(())
+    }
+
+    /// Return the underlying value of the top stack object without removing it from the stack.
+    /// # Errors
+    /// Has the same errors as `pop_object(None)`.
+    pub fn peek_object(&mut self) -> Result<Object, StateError> {
+        // Convert a copy of the top so that the original stays on the stack.
+        self.clone_top();
+        self.pop_object(None)
+    }
+
+    /// Return the underlying value of the object at index `n`, counting from the bottom of the current frame,
+    /// without modifying the stack. E.g., in a C function, `0` is its first argument.
+    /// # Errors
+    /// Will return a `StateError::TypeError` if the object is a string, list, table, or user pointer,
+    /// since YASL can only read these from the top of the stack. Use `peek_object` for those instead.
+    /// # Panics
+    /// The argument count `n` must be able to safely convert into a C unsigned integer.
+    pub fn peek_n_object(&mut self, n: usize) -> Result<Object, StateError> {
+        match self.peek_n_type(n) {
+            Type::Bool => Ok(Object::Bool(self.peek_n_bool(n))),
+            Type::Int => Ok(Object::Int(self.peek_n_int(n))),
+            Type::Float => Ok(Object::Float(self.peek_n_float(n))),
+            Type::UserData => Ok(Object::UserData {
+                data: self.peek_n_userdata(n).and_then(NonNull::new),
+                tag: self.peek_n_typename(n),
+            }),
+            Type::Undef => Ok(Object::Undef),
+            _ => Err(StateError::TypeError),
+        }
+    }
+
+    /// Pop the variadic arguments of the running C function, and return them in the order they were passed.
+    /// The function must have been pushed with a negative number of arguments, e.g., `-1` for only variadic arguments,
+    /// and must not have popped any of them yet. Its fixed arguments stay on the stack, below the count of variadic arguments.
+    pub fn vargs(&mut self) -> std::vec::IntoIter<Object> {
+        let count = usize::try_from(self.peek_vargs_count()).unwrap_or_default();
+
+        // The last argument is on top of the stack.
+        let mut args = (0..count)
+            .map(|_| self.pop_object(None).unwrap_or(Object::Undef))
+            .collect::<Vec<_>>();
+        args.reverse();
+        args.into_iter()
+    }
+
     /// Return the underlying value of the top stack object, optionally ensuring a type, or return an error.
+    /// Lists and tables which are referenced more than once are copied for each reference.
     /// # Errors
     /// Will return a `StateError::TypeError` if the object is of a different type than what was expected.
-    #[allow(clippy::missing_panics_doc)] // Getting a `HashableObject` from a `Table` key can't fail.
+    /// Will return a `StateError::ValueError` if a list or table contains itself, e.g., after `x.push(x)`,
+    /// since it can't be converted to a finite `Object`. The whole value is still popped.
+    #[allow(clippy::missing_panics_doc)] // A value is always converted before the outermost container is finished.
     pub fn pop_object(&mut self, expected_type: Option<Type>) -> Result<Object, StateError> {
-        // Check the type on the stack.
-        let stack_type = self.peek_type();
-        if let Some(object_type) = expected_type {
-            // If the caller expected a certain type which wasn't found, return an error.
-            if stack_type != object_type {
-                return Err(StateError::TypeError);
-            }
+        // If the caller expected a certain type which wasn't found, return an error.
+        // NOTE: The expected type isn't forwarded to the items of lists and tables.
+        if expected_type.is_some_and(|t| t != self.peek_type()) {
+            return Err(StateError::TypeError);
         }
 
-        // Get the underlying value.
-        match stack_type {
-            Type::Bool => Ok(Object::Bool(self.pop_bool())),
-            Type::Int => Ok(Object::Int(self.pop_int())),
-            Type::Float => Ok(Object::Float(self.pop_float())),
-            Type::Str => Ok(Object::Str(self.pop_str().unwrap_or_default())),
-            Type::List => {
-                // Clone the top of the stack so it isn't consumed by `len`.
-                self.clone_top();
+        // Lists and tables are converted iteratively, with the partially converted containers kept
+        // in a vector instead of on the native stack, so that deeply nested values can't overflow it.
+        let mut containers = Vec::new();
+        // The address of each container being converted, to detect containers which contain themselves.
+        let mut open = Vec::new();
+        loop {
+            if matches!(self.peek_type(), Type::List | Type::Table) {
+                let address = self.peek_container_address();
+                if open.contains(&address) {
+                    self.pop_partial(&containers);
+                    return Err(StateError::ValueError);
+                }
+                open.push(address);
+            }
 
-                // Get the length of the list.
-                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-                let n = {
+            // Start converting the value on top of the stack. Scalars are converted immediately.
+            let mut value = match self.peek_type() {
+                Type::List => {
+                    // Clone the top of the stack so it isn't consumed by `len`.
+                    self.clone_top();
                     self.len();
-                    self.pop_int() as usize
-                };
-
-                // Create a vector to hold the list.
-                let mut list = Vec::with_capacity(n);
-
-                // Iterate over the list and push each object onto the vector.
-                for i in 0..n {
-                    // Get the object at index `i` and push it onto the stack.
-                    #[allow(clippy::cast_possible_wrap)]
-                    self.list_get(i as isize)?;
-
-                    // Pop the object off of the stack and push it onto the vector.
-                    // NOTE: We don't forward the expected type since if the original
-                    // caller expected a list, they didn't expect a list of lists.
-                    list.push(self.pop_object(None)?);
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let n = self.pop_int() as usize;
+                    containers.push(PartialContainer::List(Vec::with_capacity(n), n));
+                    None
                 }
-                Ok(Object::List(list))
-            }
-            Type::Table => {
-                let mut table = HashMap::new();
-
-                // Give an empty start index to `table_next` to get the first key.
-                self.push_undef();
-
-                // Iterate over the table and insert each key-value pair into the map.
-                while self.table_next() {
-                    // Pop the key and value off of the stack.
-                    // Similat to the note above, we don't forward the expected type
-                    // to the key or value.
-                    let k: HashableObject = self
-                        .pop_object(None)?
-                        .try_into()
-                        .expect("Internal Error: Invalid key type.");
-                    let v = self.pop_object(None)?;
-                    table.insert(k, v);
+                Type::Table => {
+                    // Allocate space for all of the entries up front.
+                    self.clone_top();
+                    self.len();
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let n = self.pop_int() as usize;
+                    containers.push(PartialContainer::Table(HashMap::with_capacity(n)));
+
+                    // Give an empty start index to `table_next` to get the first key.
+                    self.push_undef();
+                    None
                 }
-                Ok(Object::Table(table))
+                t => Some(self.pop_scalar(t)),
+            };
+
+            // Add each finished value to its container, and finish each container which has no items left,
+            // until there is another item on top of the stack to convert.
+            loop {
+                match containers.last_mut() {
+                    None => return Ok(value.expect("Internal Error: No value was converted.")),
+                    Some(PartialContainer::List(items, n)) => {
+                        if let Some(v) = value.take() {
+                            items.push(v);
+                        }
+                        if items.len() < *n {
+                            #[allow(clippy::cast_possible_wrap)]
+                            self.list_get(items.len() as isize)?;
+                            break;
+                        }
+                    }
+                    Some(PartialContainer::Table(table)) => {
+                        if let Some(v) = value.take() {
+                            // Read the key below the value, which is left on the stack as the
+                            // previous index for `table_next`.
+                            table.insert(self.peek_key(), v);
+                        }
+                        if self.table_next() {
+                            break;
+                        }
+                    }
+                }
+
+                // Pop the container itself now that its items have been read.
+                self.pop();
+                open.pop();
+                value = containers.pop().map(|container| match container {
+                    PartialContainer::List(items, _) => Object::List(items),
+                    PartialContainer::Table(table) => Object::Table(table),
+                });
+            }
+        }
+    }
+
+    /// Get the address of the list or table on top of the stack, which identifies it while it is on the stack.
+    fn peek_container_address(&mut self) -> usize {
+        // Lists and tables are stored as user-data internally, and popping doesn't release a reference.
+        self.clone_top();
+        self.shrink_stack(1);
+        unsafe { yaslapi_sys::YASL_popuserdata(self.state.as_ptr()) as usize }
+    }
+
+    /// Pop the item on top of the stack, then each of the partially converted containers below it.
+    fn pop_partial(&mut self, containers: &[PartialContainer]) {
+        self.pop();
+        for container in containers.iter().rev() {
+            // Tables also have the key of the current item on the stack.
+            if let PartialContainer::Table(_) = container {
+                self.pop();
             }
+            self.pop();
+        }
+    }
+
+    /// Read the table key on top of the stack without popping it.
+    fn peek_key(&self) -> HashableObject {
+        match self.peek_type() {
+            Type::Bool => HashableObject::Bool(self.peek_bool()),
+            Type::Int => HashableObject::Int(self.peek_int()),
+            Type::Float => HashableObject::Float(HashableF64(self.peek_float())),
+            Type::Str => HashableObject::Str(self.peek_str().unwrap_or_default()),
+            Type::UserPtr => HashableObject::UserPtr(self.peek_userptr()),
+            _ => HashableObject::Undef,
+        }
+    }
+
+    /// Pop the top of the stack, which has type `t` and is not a list or table.
+    fn pop_scalar(&mut self, t: Type) -> Object {
+        match t {
+            Type::Bool => Object::Bool(self.pop_bool()),
+            Type::Int => Object::Int(self.pop_int()),
+            Type::Float => Object::Float(self.pop_float()),
+            Type::Str => Object::Str(self.pop_str().unwrap_or_default()),
             Type::UserData => {
                 let tag = self.peek_type_name();
-                Ok(Object::UserData {
+                Object::UserData {
                     data: self.pop_userdata(),
                     tag,
-                })
+                }
             }
-            Type::UserPtr => Ok(Object::UserPtr(self.pop_userptr())),
+            Type::UserPtr => Object::UserPtr(self.pop_userptr()),
             t => {
                 // Temporary warning for unhandled types.
                 if !matches!(t, Type::Undef) {
@@ -285,10 +715,54 @@ impl State {
 
                 // Pop the object off of the stack and return `Undef`.
                 self.pop();
-                Ok(Object::Undef)
+                Object::Undef
             }
         }
     }
+
+    /// Push a copy of the given `Object` onto the stack, recursively creating any lists and tables.
+    /// A `UserData` object is pushed without a destructor, so YASL will not free the data
+    /// when the new reference is collected; the original owner remains responsible for it.
+    /// # Errors
+    /// Will return a `StateError::TypeError` if a table key could not be inserted.
+    pub fn push_object(&mut self, object: &Object) -> Result<(), StateError> {
+        match object {
+            Object::Bool(b) => self.push_bool(*b),
+            Object::Int(i) => self.push_int(*i),
+            Object::Float(f) => self.push_float(*f),
+            Object::Str(s) => self.push_str(s),
+            Object::List(list) => {
+                self.push_list();
+                for o in list {
+                    self.push_object(o)?;
+                    self.list_push()?;
+                }
+            }
+            Object::Table(table) => {
+                self.push_table();
+                for (k, v) in table {
+                    self.push_object(&k.clone().into())?;
+                    self.push_object(v)?;
+                    self.table_set()?;
+                }
+            }
+            Object::UserData { data, tag } => match tag {
+                // Push the same tagged pointer, but leave ownership with the original object.
+                Some(tag) => unsafe { self.push_userdata(*data, tag, None) },
+                None => self.push_undef(),
+            },
+            Object::UserPtr(p) => unsafe { self.push_userptr(*p) },
+            Object::Undef => self.push_undef(),
+        }
+        Ok(())
+    }
+}
+
+/// A list or table whose items are being popped by `pop_object`.
+enum PartialContainer {
+    /// The items popped so far, and the length of the list.
+    List(Vec<Object>, usize),
+    Table(HashMap<HashableObject, Object>),
 }
 
 /// Helper enum for wrapping a YASL `Object`.
@@ -321,7 +795,7 @@ pub enum HashableObject {
 
 /// Helper struct for making the `Object` type usable for indexing tables.
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct HashableF64(f64);
+pub struct HashableF64(pub(crate) f64);
 /// Ensure that this type is hashable.
 impl std::hash::Hash for HashableF64 {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
@@ -366,6 +840,100 @@ impl From<HashableObject> for Object {
     }
 }
 
+/// Order table keys first by type, as `undef < bool < int/float < str < userptr`, then by value.
+/// Ints and floats are ordered together by their value as a float, with an int before an equal float.
+/// `NaN`s are ordered after positive infinity, or before negative infinity when their sign bit is set.
+/// User pointers are ordered by address.
+impl Ord for HashableObject {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn rank(key: &HashableObject) -> u8 {
+            match key {
+                HashableObject::Undef => 0,
+                HashableObject::Bool(_) => 1,
+                HashableObject::Int(_) | HashableObject::Float(_) => 2,
+                HashableObject::Str(_) => 3,
+                HashableObject::UserPtr(_) => 4,
+            }
+        }
+        fn compare_floats(a: f64, b: f64) -> Ordering {
+            a.partial_cmp(&b).unwrap_or_else(|| a.total_cmp(&b))
+        }
+        #[allow(clippy::cast_precision_loss)] // YASL compares mixed ints and floats as floats.
+        fn int_as_float(i: i64) -> f64 {
+            i as f64
+        }
+
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Int(a), Self::Int(b)) => a.cmp(b),
+            (Self::Float(a), Self::Float(b)) => compare_floats(a.0, b.0),
+            (Self::Int(a), Self::Float(b)) => {
+                compare_floats(int_as_float(*a), b.0).then(Ordering::Less)
+            }
+            (Self::Float(a), Self::Int(b)) => {
+                compare_floats(a.0, int_as_float(*b)).then(Ordering::Greater)
+            }
+            (Self::Str(a), Self::Str(b)) => a.cmp(b),
+            (Self::UserPtr(a), Self::UserPtr(b)) => a
+                .map_or(0, |p| p.as_ptr() as usize)
+                .cmp(&b.map_or(0, |p| p.as_ptr() as usize)),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+impl PartialOrd for HashableObject {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Object {
+    /// Iterate over the entries of a table in the order of their keys, so that the output is deterministic.
+    /// Returns `None` if the object is not a table.
+    #[must_use]
+    pub fn table_sorted_iter(&self) -> Option<impl Iterator<Item = (&HashableObject, &Object)>> {
+        let Self::Table(table) = self else {
+            return None;
+        };
+        let mut entries = table.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|&(k, _)| k);
+        Some(entries.into_iter())
+    }
+}
+
+/// Compare objects the same way as YASL's `==`. Ints and floats compare by numeric value, so `NaN` is never equal,
+/// while lists and tables compare their items recursively. Table keys must match exactly, including their type.
+/// User data compares equal when both the pointer and the tag are the same.
+impl PartialEq for Object {
+    #[allow(clippy::cast_precision_loss)] // YASL compares mixed ints and floats as floats.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a == b,
+            (Self::Int(a), Self::Float(b)) | (Self::Float(b), Self::Int(a)) => *a as f64 == *b,
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::List(a), Self::List(b)) => a == b,
+            (Self::Table(a), Self::Table(b)) => {
+                a.len() == b.len() && a.iter().all(|(k, v)| b.get(k) == Some(v))
+            }
+            (
+                Self::UserData {
+                    data: a,
+                    tag: a_tag,
+                },
+                Self::UserData {
+                    data: b,
+                    tag: b_tag,
+                },
+            ) => a == b && a_tag.map(CStr::as_ptr) == b_tag.map(CStr::as_ptr),
+            (Self::UserPtr(a), Self::UserPtr(b)) => a == b,
+            (Self::Undef, Self::Undef) => true,
+            _ => false,
+        }
+    }
+}
+
 /// Get the type of a YASL `Object` enum.
 impl From<&Object> for Type {
     fn from(value: &Object) -> Self {
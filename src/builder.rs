@@ -0,0 +1,265 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A builder for creating a configured `State` in a single expression.
+//!
+//! ```no_run
+//! use yaslapi::{builder::Library, State};
+//!
+//! let mut state = State::builder()
+//!     .source("echo math.sqrt(16)")
+//!     .library(Library::Math)
+//!     .capture_printout()
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use std::{collections::BTreeSet, path::PathBuf};
+
+use crate::{validate_identifier, CFunction, Error, State};
+
+/// A standard library which can be declared by a `StateBuilder`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Library {
+    Collections,
+    Error,
+    Io,
+    Math,
+    Mt,
+    Os,
+    Require,
+    RequireC,
+}
+
+impl Library {
+    /// Every standard library, as declared by `State::declare_libs`.
+    pub const ALL: [Self; 8] = [
+        Self::Collections,
+        Self::Error,
+        Self::Io,
+        Self::Math,
+        Self::Mt,
+        Self::Os,
+        Self::Require,
+        Self::RequireC,
+    ];
+
+    /// The name of the global which the library is declared as.
+    #[must_use]
+    pub fn global_name(self) -> &'static str {
+        match self {
+            Self::Collections => "collections",
+            Self::Error => "error",
+            Self::Io => "io",
+            Self::Math => "math",
+            Self::Mt => "mt",
+            Self::Os => "os",
+            Self::Require => "require",
+            Self::RequireC => "__require_c__",
+        }
+    }
+
+    /// Declare the library in the given state.
+    pub(crate) fn declare(self, state: &mut State) {
+        match self {
+            Self::Collections => state.declare_lib_collections(),
+            Self::Error => state.declare_lib_error(),
+            Self::Io => state.declare_lib_io(),
+            Self::Math => state.declare_lib_math(),
+            Self::Mt => state.declare_lib_mt(),
+            Self::Os => state.declare_lib_os(),
+            Self::Require => state.declare_lib_require(),
+            Self::RequireC => state.declare_lib_require_c(),
+        };
+    }
+}
+
+/// Where the builder's state loads its script from.
+enum Script {
+    Source(String),
+    Path(PathBuf),
+}
+
+/// Configures a new `State`, created with `State::builder`. By default, the state has empty source code,
+/// no standard libraries, and prints to the standard output and error streams.
+// TODO: Limits on the instructions or time a script may use need YASL's VM to call back into the host while
+// it runs, which its API doesn't support yet.
+pub struct StateBuilder {
+    script: Script,
+    libraries: Vec<Library>,
+    capture_printout: bool,
+    capture_printerr: bool,
+    lazy_libraries: bool,
+    functions: Vec<(String, CFunction, i32)>,
+}
+
+impl Default for StateBuilder {
+    fn default() -> Self {
+        Self {
+            script: Script::Source(String::new()),
+            libraries: Vec::new(),
+            capture_printout: false,
+            capture_printerr: false,
+            lazy_libraries: false,
+            functions: Vec::new(),
+        }
+    }
+}
+
+impl StateBuilder {
+    /// Create a builder with the default configuration.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the script from the given source code, replacing any previous source or path.
+    #[must_use]
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.script = Script::Source(source.into());
+        self
+    }
+
+    /// Load the script from the file at the given path, replacing any previous source or path.
+    #[must_use]
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.script = Script::Path(path.into());
+        self
+    }
+
+    /// Declare a standard library. Libraries are declared in the order they were added.
+    #[must_use]
+    pub fn library(mut self, library: Library) -> Self {
+        if !self.libraries.contains(&library) {
+            self.libraries.push(library);
+        }
+        self
+    }
+
+    /// Declare every standard library.
+    #[must_use]
+    pub fn all_libraries(self) -> Self {
+        Library::ALL
+            .into_iter()
+            .fold(self, |builder, library| builder.library(library))
+    }
+
+    /// Capture the state's standard output in a string, which can be pushed with `State::load_printout`.
+    #[must_use]
+    pub fn capture_printout(mut self) -> Self {
+        self.capture_printout = true;
+        self
+    }
+
+    /// Capture the state's error output in a string, which can be pushed with `State::load_printerr`.
+    #[must_use]
+    pub fn capture_printerr(mut self) -> Self {
+        self.capture_printerr = true;
+        self
+    }
+
+    /// Declare the libraries lazily, as `State::declare_libs_lazily` does, so that each library is only
+    /// initialized if a script which the state compiles reads it.
+    #[must_use]
+    pub fn lazy_libraries(mut self) -> Self {
+        self.lazy_libraries = true;
+        self
+    }
+
+    /// Declare the global `name` as a host function which takes `args` arguments.
+    #[must_use]
+    pub fn function(mut self, name: impl Into<String>, function: CFunction, args: i32) -> Self {
+        self.functions.push((name.into(), function, args));
+        self
+    }
+
+    /// Create the configured state.
+    /// # Errors
+    /// Will return `Error::InvalidIdentifier` if the name of a function is not a valid YASL identifier,
+    /// or `Error::Load` with the same errors as `State::from_file` if the script is loaded from a path.
+    #[allow(clippy::missing_panics_doc)] // The functions' names were validated first.
+    pub fn build(self) -> Result<State, Error> {
+        for (name, _, _) in &self.functions {
+            validate_identifier(name)?;
+        }
+        let mut state = match &self.script {
+            Script::Source(source) => State::from_source(source),
+            Script::Path(path) => State::from_file(path)?,
+        };
+        if self.capture_printout {
+            state.set_printout_tostr();
+        }
+        if self.capture_printerr {
+            state.set_printerr_tostr();
+        }
+        if self.lazy_libraries {
+            state.declare_lazily(&self.libraries);
+        } else {
+            for library in self.libraries {
+                library.declare(&mut state);
+            }
+        }
+        for (name, function, args) in self.functions {
+            state.push_cfunction(function, args);
+            state
+                .init_global_slice(&name)
+                .expect("Internal Error: The function's name was validated.");
+        }
+        Ok(state)
+    }
+}
+
+impl State {
+    /// Register every standard library without initializing it. Before compiling a script, each library which the
+    /// script reads, as found by `analyze`, is declared as `declare_libs` would, so short-lived states only pay for
+    /// the libraries they use. Libraries which are only reached some other way, e.g., by `eval`, stay undeclared
+    /// until source which reads them is compiled.
+    pub fn declare_libs_lazily(&mut self) {
+        self.declare_lazily(&Library::ALL);
+    }
+
+    /// Register the libraries to be declared once source which reads them is compiled.
+    fn declare_lazily(&mut self, libraries: &[Library]) {
+        for &library in libraries {
+            if !self.lazy_libraries.contains(&library) {
+                self.lazy_libraries.push(library);
+            }
+        }
+    }
+
+    /// Declare the lazy libraries which are read, out of the globals `reads`.
+    pub(crate) fn declare_lazy_libraries(&mut self, reads: &BTreeSet<String>) {
+        let (read, unread) = std::mem::take(&mut self.lazy_libraries)
+            .into_iter()
+            .partition::<Vec<_>, _>(|library| reads.contains(library.global_name()));
+        self.lazy_libraries = unread;
+        for library in read {
+            library.declare(self);
+        }
+    }
+
+    /// Create a builder for configuring a new state in a single expression.
+    #[must_use]
+    pub fn builder() -> StateBuilder {
+        StateBuilder::new()
+    }
+}
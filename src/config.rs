@@ -0,0 +1,213 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! YASL as a programmable configuration language. A configuration script assigns globals,
+//! which are deserialized into a Rust type with serde after the script has run.
+//!
+//! ```no_run
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Window {
+//!     width: i64,
+//!     height: i64,
+//!     title: Option<String>,
+//! }
+//!
+//! // window.yasl:
+//! // const scale = 2;
+//! // width = 640 * scale;
+//! // height = 360 * scale;
+//! let window: Window = yaslapi::config::load("window.yasl").unwrap();
+//! ```
+//!
+//! Scripts run without any standard libraries, so they can't read files or run commands.
+//! `aux::Object` also implements `serde::Deserializer`, to deserialize values read from any state.
+
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+use serde::{
+    de::{
+        value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+        DeserializeOwned, Deserializer, IntoDeserializer, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+use crate::{
+    aux::{HashableObject, Object},
+    State, StateError,
+};
+
+/// Error returned when a configuration can't be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The script could not be read.
+    Io(io::Error),
+    /// The script failed to compile or run.
+    Script(StateError),
+    /// The script's values don't match the type they are deserialized into.
+    Invalid(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to read the configuration: {e}"),
+            Self::Script(e) => write!(f, "the configuration script failed: {e}"),
+            Self::Invalid(message) => write!(f, "invalid configuration: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Script(e) => Some(e),
+            Self::Invalid(_) => None,
+        }
+    }
+}
+
+impl serde::de::Error for ConfigError {
+    fn custom<T: Display>(message: T) -> Self {
+        Self::Invalid(message.to_string())
+    }
+}
+
+/// Run the configuration script at `path`, and deserialize the globals it assigns into `T`,
+/// as if they were the fields of a table.
+/// # Errors
+/// Will return `ConfigError::Io` if the file can't be read, `ConfigError::Script` if the script fails,
+/// or `ConfigError::Invalid` if its globals can't be deserialized into `T`.
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, ConfigError> {
+    let source = fs::read_to_string(path).map_err(ConfigError::Io)?;
+    load_source(&source)
+}
+
+/// Run the configuration script `source`, and deserialize the globals it assigns into `T`, as `load` does.
+/// # Errors
+/// Will return `ConfigError::Script` if the script fails, or `ConfigError::Invalid` if its globals
+/// can't be deserialized into `T`.
+// TODO: Also accept a table given to `export`, once YASL's API gives the host the value a script exports.
+pub fn load_source<T: DeserializeOwned>(source: &str) -> Result<T, ConfigError> {
+    let names = State::analyze(source).assigns;
+    let mut state = State::from_source(source);
+    for name in &names {
+        state
+            .declare_global(name)
+            .map_err(|e| ConfigError::Invalid(format!("invalid global name {name:?}: {e}")))?;
+    }
+    state.execute().map_err(ConfigError::Script)?;
+
+    let globals = names
+        .into_iter()
+        .map(|name| {
+            let value = state
+                .pop_global_slice(&name, None)
+                .map_err(ConfigError::Script)?;
+            Ok((HashableObject::Str(name), value))
+        })
+        .collect::<Result<_, ConfigError>>()?;
+    T::deserialize(Object::Table(globals))
+}
+
+/// Deserialize an `Object` into any type which matches its structure. `undef` is a missing optional value or unit,
+/// a string is a unit enum variant, and a table with one entry is an enum variant holding the entry's value.
+impl<'de> Deserializer<'de> for Object {
+    type Error = ConfigError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::Undef => visitor.visit_unit(),
+            Self::Bool(b) => visitor.visit_bool(b),
+            Self::Int(i) => visitor.visit_i64(i),
+            Self::Float(f) => visitor.visit_f64(f),
+            Self::Str(s) => visitor.visit_string(s),
+            Self::List(list) => {
+                let mut seq = SeqDeserializer::new(list.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Self::Table(table) => {
+                let mut map =
+                    MapDeserializer::new(table.into_iter().map(|(k, v)| (Self::from(k), v)));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            Self::UserData { .. } | Self::UserPtr(_) => Err(ConfigError::Invalid(
+                "user data and pointers can't be deserialized".to_owned(),
+            )),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::Undef => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::Str(s) => visitor.visit_enum(s.into_deserializer()),
+            Self::Table(table) if table.len() == 1 => {
+                let map = MapDeserializer::new(table.into_iter().map(|(k, v)| (Self::from(k), v)));
+                visitor.visit_enum(MapAccessDeserializer::new(map))
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ConfigError> for Object {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
@@ -0,0 +1,225 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A console for debug commands, e.g., an in-game console. Each line names a registered global and its arguments:
+//! a function is called with the arguments, and a variable is read without arguments or assigned with one.
+//!
+//! Arguments are separated by whitespace, and may be quoted with `'` or `"` to include whitespace,
+//! with `\` escaping the next character. Unquoted arguments are converted to the first type they match
+//! of `undef`, `bool`, `int`, and `float`, and are strings otherwise. Quoted arguments are always strings.
+//!
+//! ```
+//! use yaslapi::{aux::Object, console::CommandConsole, State};
+//!
+//! let mut state = State::from_source("gravity = 9.8; spawn = fn(kind, count) -> kind, count;");
+//! state.declare_global("gravity").unwrap();
+//! state.declare_global("spawn").unwrap();
+//! state.execute().unwrap();
+//!
+//! let console = CommandConsole::new().command("gravity").command("spawn");
+//! // SAFETY: `spawn` only returns its arguments, so it can't raise a runtime error.
+//! unsafe {
+//!     assert_eq!(
+//!         console.run(&mut state, "spawn 'red slime' 3"),
+//!         Ok(vec![Object::Str("red slime".to_owned()), Object::Int(3)])
+//!     );
+//!     assert_eq!(console.run(&mut state, "gravity 1.6"), Ok(Vec::new()));
+//!     assert_eq!(console.run(&mut state, "gravity"), Ok(vec![Object::Float(1.6)]));
+//! }
+//! ```
+
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display},
+};
+
+use crate::{aux::Object, host::call_global, State, StateError, Type};
+
+/// Parses lines of input into calls of the globals registered with it.
+#[derive(Clone, Debug, Default)]
+pub struct CommandConsole {
+    commands: BTreeSet<String>,
+}
+
+/// Error returned when a line can't be run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConsoleError {
+    /// The line has no command.
+    Empty,
+    /// The command is not registered with the console.
+    UnknownCommand(String),
+    /// A quote starting at the given byte offset is never closed.
+    UnclosedQuote(usize),
+    /// A variable was given more than one argument.
+    TooManyArgs(String),
+    /// Loading, calling, or assigning the global failed.
+    State(StateError),
+}
+
+impl Display for ConsoleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("no command given"),
+            Self::UnknownCommand(name) => write!(f, "unknown command: {name}"),
+            Self::UnclosedQuote(offset) => write!(f, "unclosed quote at byte {offset}"),
+            Self::TooManyArgs(name) => write!(f, "{name} takes at most one value"),
+            Self::State(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ConsoleError {}
+
+impl From<StateError> for ConsoleError {
+    fn from(e: StateError) -> Self {
+        Self::State(e)
+    }
+}
+
+impl CommandConsole {
+    /// Create a console without any commands.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the global `name` as a command. Globals which are not registered can't be used from the console.
+    #[must_use]
+    pub fn command(mut self, name: impl Into<String>) -> Self {
+        self.commands.insert(name.into());
+        self
+    }
+
+    /// Get the names of the registered commands in sorted order, e.g., for a help listing or completion.
+    pub fn commands(&self) -> impl Iterator<Item = &str> {
+        self.commands.iter().map(String::as_str)
+    }
+
+    /// Run one line of input in the state. Returns the values returned by a function, the value of a variable
+    /// which was given no arguments, or nothing if a variable was assigned.
+    /// # Errors
+    /// Will return a `ConsoleError` if the line can't be parsed, the command isn't registered,
+    /// or YASL fails to load, call, or assign the global.
+    /// # Safety
+    /// A function named by the line must not raise a runtime error for the arguments it is given, e.g., because
+    /// a mistyped argument has the wrong type. YASL can't recover from runtime errors outside of `State::execute`,
+    /// so only register functions which check their arguments, or variables.
+    pub unsafe fn run(&self, state: &mut State, line: &str) -> Result<Vec<Object>, ConsoleError> {
+        let mut tokens = tokenize(line)?.into_iter();
+        let name = match tokens.next() {
+            Some(Token::Word(name) | Token::Quoted(name)) => name,
+            None => return Err(ConsoleError::Empty),
+        };
+        if !self.commands.contains(&name) {
+            return Err(ConsoleError::UnknownCommand(name));
+        }
+        let args = tokens.map(Token::into_object).collect::<Vec<_>>();
+
+        state.load_global_slice(&name)?;
+        let is_function = matches!(state.peek_type(), Type::Fn | Type::Closure | Type::CFn);
+        if is_function {
+            state.pop();
+            // SAFETY: The caller guarantees that the command doesn't raise a runtime error.
+            return Ok(unsafe { call_global(state, &name, &args) }?);
+        }
+        match args.as_slice() {
+            [] => Ok(vec![state.pop_object(None)?]),
+            [value] => {
+                state.pop();
+                state.push_object(value)?;
+                state.set_global_slice(&name)?;
+                Ok(Vec::new())
+            }
+            _ => {
+                state.pop();
+                Err(ConsoleError::TooManyArgs(name))
+            }
+        }
+    }
+}
+
+/// An argument of a command line.
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+impl Token {
+    /// Convert the argument to the value it is passed as.
+    fn into_object(self) -> Object {
+        match self {
+            Self::Quoted(s) => Object::Str(s),
+            Self::Word(s) => match s.as_str() {
+                "undef" => Object::Undef,
+                "true" => Object::Bool(true),
+                "false" => Object::Bool(false),
+                // Words like `inf` and `nan` are strings, since they don't look like numbers.
+                _ if s.contains(|c: char| c.is_ascii_digit()) => s
+                    .parse()
+                    .map(Object::Int)
+                    .or_else(|_| s.parse().map(Object::Float))
+                    .unwrap_or(Object::Str(s)),
+                _ => Object::Str(s),
+            },
+        }
+    }
+}
+
+/// Split a line into whitespace-separated arguments, which may be quoted.
+fn tokenize(line: &str) -> Result<Vec<Token>, ConsoleError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut token = String::new();
+        if c == '\'' || c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => token.extend(chars.next().map(|(_, c)| c)),
+                    Some((_, q)) if q == c => break,
+                    Some((_, c)) => token.push(c),
+                    None => return Err(ConsoleError::UnclosedQuote(start)),
+                }
+            }
+            tokens.push(Token::Quoted(token));
+        } else {
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                chars.next();
+                if c == '\\' {
+                    token.extend(chars.next().map(|(_, c)| c));
+                } else {
+                    token.push(c);
+                }
+            }
+            tokens.push(Token::Word(token));
+        }
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,487 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Conversions between Rust values and values on the YASL stack.
+
+use crate::{aux::Object, State, StateError, Type};
+use std::{
+    collections::HashMap,
+    ffi::CStr,
+    path::{Path, PathBuf},
+};
+
+/// Types which can be pushed to the YASL stack.
+pub trait IntoYasl {
+    /// Push this value to the top of the stack.
+    /// # Errors
+    /// Will return an error if the value could not be represented in YASL.
+    fn push_to(self, state: &mut State) -> Result<(), StateError>;
+}
+
+/// Implement `IntoYasl` for types with an infallible push method, including references to them.
+macro_rules! impl_into_yasl {
+    ($($t:ty => $push:ident),* $(,)?) => {$(
+        impl IntoYasl for $t {
+            fn push_to(self, state: &mut State) -> Result<(), StateError> {
+                state.$push(self.into());
+                Ok(())
+            }
+        }
+        impl IntoYasl for &$t {
+            fn push_to(self, state: &mut State) -> Result<(), StateError> {
+                (*self).push_to(state)
+            }
+        }
+    )*};
+}
+impl_into_yasl!(
+    bool => push_bool,
+    i8 => push_int,
+    i16 => push_int,
+    i32 => push_int,
+    i64 => push_int,
+    u8 => push_int,
+    u16 => push_int,
+    u32 => push_int,
+    f32 => push_float,
+    f64 => push_float,
+);
+
+impl IntoYasl for &str {
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        state.push_str(self);
+        Ok(())
+    }
+}
+impl IntoYasl for &&str {
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        state.push_str(self);
+        Ok(())
+    }
+}
+impl IntoYasl for String {
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        state.push_str(&self);
+        Ok(())
+    }
+}
+impl IntoYasl for &String {
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        state.push_str(self);
+        Ok(())
+    }
+}
+
+/// `None` is pushed as `undef`.
+impl<T: IntoYasl> IntoYasl for Option<T> {
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        match self {
+            Some(value) => value.push_to(state),
+            None => {
+                state.push_undef();
+                Ok(())
+            }
+        }
+    }
+}
+
+impl IntoYasl for Object {
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        state.push_object(&self)
+    }
+}
+impl IntoYasl for &Object {
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        state.push_object(self)
+    }
+}
+
+/// Tuples push each of their values in order, so that the last value ends up on top of the stack.
+macro_rules! impl_into_yasl_tuple {
+    ($($t:ident),*) => {
+        impl<$($t: IntoYasl),*> IntoYasl for ($($t,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn push_to(self, state: &mut State) -> Result<(), StateError> {
+                let ($($t,)*) = self;
+                $($t.push_to(state)?;)*
+                Ok(())
+            }
+        }
+    };
+}
+impl_into_yasl_tuple!();
+impl_into_yasl_tuple!(A);
+impl_into_yasl_tuple!(A, B);
+impl_into_yasl_tuple!(A, B, C);
+impl_into_yasl_tuple!(A, B, C, D);
+impl_into_yasl_tuple!(A, B, C, D, E);
+impl_into_yasl_tuple!(A, B, C, D, E, F);
+impl_into_yasl_tuple!(A, B, C, D, E, F, G);
+impl_into_yasl_tuple!(A, B, C, D, E, F, G, H);
+
+/// Types which can be taken from the top of the YASL stack.
+pub trait FromYasl: Sized {
+    /// The number of stack values this type is popped from.
+    const COUNT: usize = 1;
+    /// The name of the YASL type this type is popped from, for error messages, e.g., `int`.
+    const TYPE_NAME: &'static str = "any";
+
+    /// Whether the value at index `n`, counting from the bottom of the current frame, can be popped as this type.
+    /// Types which only find out when popping should return `true`.
+    fn is_n(_state: &State, _n: usize) -> bool {
+        true
+    }
+
+    /// Pop the top of the stack as this type.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack has a different type,
+    /// in which case the value is left on the stack.
+    fn pop_from(state: &mut State) -> Result<Self, StateError>;
+}
+
+impl FromYasl for bool {
+    const TYPE_NAME: &'static str = "bool";
+
+    fn is_n(state: &State, n: usize) -> bool {
+        state.peek_n_type(n) == Type::Bool
+    }
+
+    fn pop_from(state: &mut State) -> Result<Self, StateError> {
+        if state.is_bool() {
+            Ok(state.pop_bool())
+        } else {
+            Err(StateError::TypeError)
+        }
+    }
+}
+
+impl FromYasl for i64 {
+    const TYPE_NAME: &'static str = "int";
+
+    fn is_n(state: &State, n: usize) -> bool {
+        state.peek_n_type(n) == Type::Int
+    }
+
+    fn pop_from(state: &mut State) -> Result<Self, StateError> {
+        if state.is_int() {
+            Ok(state.pop_int())
+        } else {
+            Err(StateError::TypeError)
+        }
+    }
+}
+
+impl FromYasl for f64 {
+    const TYPE_NAME: &'static str = "float";
+
+    fn is_n(state: &State, n: usize) -> bool {
+        state.peek_n_type(n) == Type::Float
+    }
+
+    fn pop_from(state: &mut State) -> Result<Self, StateError> {
+        if state.is_float() {
+            Ok(state.pop_float())
+        } else {
+            Err(StateError::TypeError)
+        }
+    }
+}
+
+impl FromYasl for String {
+    const TYPE_NAME: &'static str = "str";
+
+    fn is_n(state: &State, n: usize) -> bool {
+        state.peek_n_type(n) == Type::Str
+    }
+
+    fn pop_from(state: &mut State) -> Result<Self, StateError> {
+        if state.is_str() {
+            state.pop_str().ok_or(StateError::TypeError)
+        } else {
+            Err(StateError::TypeError)
+        }
+    }
+}
+
+/// Any value can be popped as an `Object`.
+impl FromYasl for Object {
+    fn pop_from(state: &mut State) -> Result<Self, StateError> {
+        state.pop_object(None)
+    }
+}
+
+/// `undef` is popped as `None`, any other value must be convertible to `T`.
+impl<T: FromYasl> FromYasl for Option<T> {
+    const TYPE_NAME: &'static str = T::TYPE_NAME;
+
+    fn is_n(state: &State, n: usize) -> bool {
+        state.peek_n_type(n) == Type::Undef || T::is_n(state, n)
+    }
+
+    fn pop_from(state: &mut State) -> Result<Self, StateError> {
+        if state.is_undef() {
+            state.pop();
+            Ok(None)
+        } else {
+            T::pop_from(state).map(Some)
+        }
+    }
+}
+
+/// The empty tuple pops nothing, e.g., to call a function without using its return values.
+impl FromYasl for () {
+    const COUNT: usize = 0;
+
+    fn pop_from(_: &mut State) -> Result<Self, StateError> {
+        Ok(())
+    }
+}
+
+/// Tuples pop their values with the topmost value last, i.e., in the order they were pushed.
+/// If a value has the wrong type then the values above it have already been popped.
+macro_rules! impl_from_yasl_tuple {
+    (($($init:ident),*), $last:ident) => {
+        impl<$($init: FromYasl,)* $last: FromYasl> FromYasl for ($($init,)* $last,) {
+            const COUNT: usize = $($init::COUNT +)* $last::COUNT;
+
+            #[allow(non_snake_case)]
+            fn pop_from(state: &mut State) -> Result<Self, StateError> {
+                let $last = $last::pop_from(state)?;
+                let ($($init,)*) = <($($init,)*)>::pop_from(state)?;
+                Ok(($($init,)* $last,))
+            }
+        }
+    };
+}
+impl_from_yasl_tuple!((), A);
+impl_from_yasl_tuple!((A), B);
+impl_from_yasl_tuple!((A, B), C);
+impl_from_yasl_tuple!((A, B, C), D);
+impl_from_yasl_tuple!((A, B, C, D), E);
+impl_from_yasl_tuple!((A, B, C, D, E), F);
+impl_from_yasl_tuple!((A, B, C, D, E, F), G);
+impl_from_yasl_tuple!((A, B, C, D, E, F, G), H);
+
+impl State {
+    /// Call the global function `name` with the given arguments, which may be a tuple to pass
+    /// several values, and pop its return values as `R`, which may be a tuple for several values.
+    /// # Errors
+    /// Will return `StateError::Generic` if the global does not exist, an error if an argument
+    /// could not be pushed, `StateError::ValueError` if the function returned a different number of
+    /// values than `R` expects, or `StateError::TypeError` if a return value has the wrong type.
+    pub fn call_global<R: FromYasl>(
+        &mut self,
+        name: &CStr,
+        args: impl IntoYasl,
+    ) -> Result<R, StateError> {
+        self.load_global(name)?;
+        self.call_loaded(args)
+    }
+
+    /// Call the global function `name` with the given arguments, and pop its return values as `R`.
+    /// # Errors
+    /// Has the same errors as `call_global`.
+    pub fn call_global_slice<R: FromYasl>(
+        &mut self,
+        name: &str,
+        args: impl IntoYasl,
+    ) -> Result<R, StateError> {
+        self.load_global_slice(name)?;
+        self.call_loaded(args)
+    }
+
+    /// Load the global `name` and pop it as `T`, e.g., `state.get_global::<i64>("count")`.
+    /// # Errors
+    /// Will return `StateError::Generic` if the global does not exist, `StateError::ValueError` if `T` is a tuple
+    /// which doesn't hold exactly one value, or `StateError::TypeError` if the global has the wrong type.
+    /// The stack is left unchanged when an error is returned.
+    pub fn get_global<T: FromYasl>(&mut self, name: &str) -> Result<T, StateError> {
+        if T::COUNT != 1 {
+            return Err(StateError::ValueError);
+        }
+        self.load_global_slice(name)?;
+        let base = self.stack_len() - 1;
+        let result = T::pop_from(self);
+        if result.is_err() && self.stack_len() > base {
+            self.pop();
+        }
+        result
+    }
+
+    /// Call the function on top of the stack with the given arguments, and pop its return values.
+    fn call_loaded<R: FromYasl>(&mut self, args: impl IntoYasl) -> Result<R, StateError> {
+        let base = self.stack_len();
+        if let Err(e) = args.push_to(self) {
+            // Remove the function and any arguments pushed before the error.
+            for _ in 0..=self.stack_len() - base {
+                self.pop();
+            }
+            return Err(e);
+        }
+
+        let results = self.function_call(self.stack_len() - base);
+        if results != R::COUNT {
+            for _ in 0..results {
+                self.pop();
+            }
+            return Err(StateError::ValueError);
+        }
+        R::pop_from(self)
+    }
+
+    /// Push any value which can be represented in YASL, e.g., `state.push(42)` or `state.push(Some("text"))`.
+    /// Tuples push each of their values in order, so the last value ends up on top of the stack.
+    /// # Errors
+    /// Will return an error if the value could not be pushed.
+    pub fn push(&mut self, value: impl IntoYasl) -> Result<(), StateError> {
+        value.push_to(self)
+    }
+
+    /// Push each of the values in order, so that the last value ends up on top of the stack,
+    /// e.g., to push the arguments for `function_call`. Returns the number of values pushed.
+    /// # Errors
+    /// Will return an error if a value could not be pushed. Values before it remain on the stack.
+    pub fn push_all<T: IntoYasl>(
+        &mut self,
+        values: impl IntoIterator<Item = T>,
+    ) -> Result<usize, StateError> {
+        let mut count = 0;
+        for value in values {
+            value.push_to(self)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Push a new list containing each of the values in order.
+    /// # Errors
+    /// Will return an error if a value could not be pushed. The partial list is popped.
+    pub fn push_vec<T>(&mut self, values: &[T]) -> Result<(), StateError>
+    where
+        for<'a> &'a T: IntoYasl,
+    {
+        self.push_list();
+        for value in values {
+            if let Err(e) = value
+                .push_to(self)
+                .and_then(|()| self.list_push().map(|_| ()))
+            {
+                self.pop();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Push a new table containing each of the key-value pairs.
+    /// # Errors
+    /// Will return an error if a key or value could not be pushed, or if a key could not be
+    /// inserted into the table (e.g., `undef`). The partial table is popped.
+    pub fn push_map<K, V, S>(&mut self, map: &HashMap<K, V, S>) -> Result<(), StateError>
+    where
+        for<'a> &'a K: IntoYasl,
+        for<'a> &'a V: IntoYasl,
+    {
+        self.push_table();
+        for (key, value) in map {
+            if let Err(e) = self.push_pair(key, value) {
+                self.pop();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Push a key-value pair and insert it into the table below them.
+    fn push_pair(&mut self, key: impl IntoYasl, value: impl IntoYasl) -> Result<(), StateError> {
+        key.push_to(self)?;
+        if let Err(e) = value.push_to(self) {
+            self.pop();
+            return Err(e);
+        }
+        self.table_set().map(|_| ())
+    }
+
+    /// Pop the top of the stack as the requested type.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack has a different type,
+    /// in which case the value is left on the stack.
+    pub fn pop_checked<T: FromYasl>(&mut self) -> Result<T, StateError> {
+        T::pop_from(self)
+    }
+
+    /// Pop the top of the stack if it is a bool.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is not a bool, which is left on the stack.
+    pub fn pop_bool_checked(&mut self) -> Result<bool, StateError> {
+        self.pop_checked()
+    }
+
+    /// Pop the top of the stack if it is an int.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is not an int, which is left on the stack.
+    pub fn pop_int_checked(&mut self) -> Result<i64, StateError> {
+        self.pop_checked()
+    }
+
+    /// Pop the top of the stack if it is a float.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is not a float, which is left on the stack.
+    pub fn pop_float_checked(&mut self) -> Result<f64, StateError> {
+        self.pop_checked()
+    }
+
+    /// Pop the top of the stack if it is a string.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is not a string, which is left on the stack.
+    pub fn pop_str_checked(&mut self) -> Result<String, StateError> {
+        self.pop_checked()
+    }
+
+    /// Push a path as a string. On Unix the bytes of the path are pushed unchanged, even if they aren't valid UTF-8,
+    /// so that `pop_path` returns the same path. Elsewhere, paths which aren't valid Unicode are converted lossily,
+    /// with invalid parts replaced by `U+FFFD`.
+    pub fn push_path(&mut self, path: &Path) {
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+        #[cfg(not(unix))]
+        let lossy = path.to_string_lossy();
+        #[cfg(not(unix))]
+        let bytes = lossy.as_bytes();
+        crate::modules::push_bytes(self, bytes);
+    }
+
+    /// Pop the top of the stack as a path if it is a string. On Unix the bytes of the string are used unchanged,
+    /// including bytes which aren't valid UTF-8. Elsewhere, invalid UTF-8 is replaced by `U+FFFD`.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is not a string, which is left on the stack.
+    pub fn pop_path(&mut self) -> Result<PathBuf, StateError> {
+        let bytes =
+            crate::modules::pop_bytes_arg(self, "path", 0).map_err(|_| StateError::TypeError)?;
+        #[cfg(unix)]
+        let path =
+            PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes));
+        #[cfg(not(unix))]
+        let path = PathBuf::from(String::from_utf8_lossy(&bytes).into_owned());
+        Ok(path)
+    }
+}
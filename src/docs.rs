@@ -0,0 +1,146 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Documentation of the host functions which a state exposes to scripts, e.g., to publish a scripting API.
+
+use std::{ffi::CStr, fmt::Write};
+
+use crate::{aux::YaslCFn, modules::with_hidden_userdata, InvalidIdentifier, State};
+
+/// Name of the hidden global which stores the documentation of registered functions.
+const DOCS_GLOBAL: &str = "__yaslapi_api_docs";
+static DOCS_TAG: &CStr = c"yaslapi.api_docs";
+
+/// The documentation of a host function registered with `State::init_function`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionDoc {
+    /// The name of the global which holds the function.
+    pub name: String,
+    /// The number of arguments the function takes, which is negative for variadic functions.
+    pub args: isize,
+    /// The name and YASL type of each parameter, if the function declared typed parameters.
+    pub params: Vec<(String, String)>,
+    /// The function's doc comment, without the leading space of each line.
+    pub doc: String,
+}
+
+impl FunctionDoc {
+    /// The function's signature as scripts call it, e.g., `repeat(s: str, n: int)`.
+    /// Parameters without types are numbered, e.g., `log(arg0, ...)`.
+    #[must_use]
+    pub fn signature(&self) -> String {
+        let params = if self.params.is_empty() {
+            let fixed = self.args.unsigned_abs() - usize::from(self.args < 0);
+            let mut params = (0..fixed).map(|n| format!("arg{n}")).collect::<Vec<_>>();
+            if self.args < 0 {
+                params.push("...".to_owned());
+            }
+            params
+        } else {
+            self.params
+                .iter()
+                .map(|(name, type_name)| format!("{name}: {type_name}"))
+                .collect()
+        };
+        format!("{}({})", self.name, params.join(", "))
+    }
+}
+
+/// Render the documentation of functions as markdown, with a heading for each function's signature
+/// followed by its doc comment, in the order given.
+#[must_use]
+pub fn render_markdown(docs: &[FunctionDoc]) -> String {
+    let mut markdown = String::new();
+    for doc in docs {
+        if !markdown.is_empty() {
+            markdown.push('\n');
+        }
+        let _ = writeln!(markdown, "### `{}`", doc.signature());
+        if !doc.doc.is_empty() {
+            let _ = writeln!(markdown, "\n{}", doc.doc);
+        }
+    }
+    markdown
+}
+
+impl State {
+    /// Declare the global `name` as the given host function, e.g., one defined with `new_cfn!`,
+    /// and record its documentation for `api_docs`.
+    /// # Errors
+    /// Will return an `InvalidIdentifier` if the given name is not a valid YASL identifier.
+    pub fn init_function(
+        &mut self,
+        name: &str,
+        function: &YaslCFn,
+    ) -> Result<(), InvalidIdentifier> {
+        crate::validate_identifier(name)?;
+        #[allow(clippy::cast_possible_truncation)]
+        self.push_cfunction(function.cfn, function.args as i32);
+        self.init_global_slice(name)?;
+
+        let doc = FunctionDoc {
+            name: name.to_owned(),
+            args: function.args,
+            params: function
+                .params
+                .iter()
+                .map(|&(name, type_name)| (name.to_owned(), type_name.to_owned()))
+                .collect(),
+            doc: function
+                .doc
+                .lines()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        with_docs(self, |docs| {
+            // Registering a name again replaces its documentation.
+            docs.retain(|existing| existing.name != doc.name);
+            docs.push(doc);
+        });
+        Ok(())
+    }
+
+    /// Get the documentation of the functions declared with `init_function`, in the order they were declared.
+    #[must_use]
+    pub fn api_docs(&mut self) -> Vec<FunctionDoc> {
+        with_docs(self, |docs| docs.clone())
+    }
+}
+
+/// Run `f` with the state's recorded documentation, creating it if needed.
+fn with_docs<R>(state: &mut State, f: impl FnOnce(&mut Vec<FunctionDoc>) -> R) -> R {
+    let exists = state.load_global_slice(DOCS_GLOBAL).is_ok() && {
+        let is_docs = state.is_userdata(DOCS_TAG);
+        state.pop();
+        is_docs
+    };
+    if !exists {
+        state.push_userdata_box(Vec::<FunctionDoc>::new(), DOCS_TAG);
+        state
+            .init_global_slice(DOCS_GLOBAL)
+            .expect("Internal Error: The documentation's global is a valid identifier.");
+    }
+    with_hidden_userdata(state, DOCS_GLOBAL, DOCS_TAG, f).unwrap_or_else(|_| {
+        unreachable!("Internal Error: The documentation's global was just created.")
+    })
+}
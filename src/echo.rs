@@ -0,0 +1,89 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{ffi::CStr, os::raw::c_int};
+
+use yaslapi_sys::YASL_State;
+
+use crate::{
+    aux::Object,
+    modules::{run_lib_fn, with_hidden_userdata},
+    State,
+};
+
+/// Name of the hidden global which `echo` statements are rewritten to call.
+pub(crate) const ECHO_GLOBAL: &str = "__yaslapi_echo";
+
+/// Name of the hidden global which stores the echo handler.
+const HANDLER_GLOBAL: &str = "__yaslapi_echo_handler";
+static HANDLER_TAG: &CStr = c"yaslapi.echo_handler";
+
+/// Callback which receives the values of each `echo` statement.
+type EchoHandler = Box<dyn FnMut(&[Object]) + Send>;
+
+impl State {
+    /// Intercept `echo` statements, so that the handler receives the values they echo instead of them being printed,
+    /// e.g., to render structured values in a game console or notebook.
+    /// YASL prints `echo` statements itself, so the source of each script and chunk is rewritten before compiling,
+    /// turning each `echo a, b;` into a call of a hidden function with the same values. Source which isn't valid UTF-8
+    /// is not rewritten, and neither is the value which `execute_repl` prints.
+    /// Functions can't be converted to `Object`s, so they are received as `Object::Undef`.
+    #[allow(clippy::missing_panics_doc)] // The globals' names are valid identifiers.
+    pub fn set_echo_handler(&mut self, handler: impl FnMut(&[Object]) + Send + 'static) {
+        let handler: EchoHandler = Box::new(handler);
+        self.push_userdata_box(handler, HANDLER_TAG);
+        self.init_global_slice(HANDLER_GLOBAL)
+            .expect("Internal Error: The handler's global is a valid identifier.");
+        self.push_cfunction(echo, -1);
+        self.init_global_slice(ECHO_GLOBAL)
+            .expect("Internal Error: The echo global is a valid identifier.");
+
+        // The main program may already contain `echo` statements.
+        self.restore_origin();
+    }
+
+    /// Whether `set_echo_handler` was called for this state.
+    pub(crate) fn has_echo_handler(&mut self) -> bool {
+        if self.load_global_slice(HANDLER_GLOBAL).is_err() {
+            return false;
+        }
+        let is_handler = self.is_userdata(HANDLER_TAG);
+        self.pop();
+        is_handler
+    }
+}
+
+/// Implements the function which rewritten `echo` statements call.
+unsafe extern "C" fn echo(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let values = state.vargs().collect::<Vec<_>>();
+        with_hidden_userdata(
+            state,
+            HANDLER_GLOBAL,
+            HANDLER_TAG,
+            |handler: &mut EchoHandler| {
+                handler(&values);
+            },
+        )?;
+        Ok(0)
+    })
+}
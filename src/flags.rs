@@ -0,0 +1,182 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bit flags shared between Rust and YASL, for types declared with the `bitflags!` macro.
+//! Scripts see a set of flags as an int of its bits, so the values can be combined with `|` or with the
+//! `combine` function of the global declared by `State::declare_flags`, which also checks for unknown bits.
+//!
+//! ```
+//! use bitflags::bitflags;
+//! use yaslapi::{convert::FromYasl, flags::FlagSet, State};
+//!
+//! bitflags! {
+//!     #[derive(Clone, Copy, Debug, PartialEq)]
+//!     struct Access: u32 {
+//!         const READ = 1;
+//!         const WRITE = 2;
+//!     }
+//! }
+//!
+//! let mut state = State::from_source("access = Access.combine(Access.READ, Access.WRITE);");
+//! state.declare_flags::<Access>("Access").unwrap();
+//! state.declare_global("access").unwrap();
+//! state.execute().unwrap();
+//!
+//! state.load_global_slice("access").unwrap();
+//! let access = FlagSet::<Access>::pop_from(&mut state).unwrap();
+//! assert_eq!(access.0, Access::READ | Access::WRITE);
+//! ```
+
+use std::os::raw::c_int;
+
+use bitflags::Flags;
+use yaslapi_sys::YASL_State;
+
+use crate::{
+    aux::{run_host_fn, HashableObject, MetatableFunction, Object, ScriptError},
+    convert::{FromYasl, IntoYasl},
+    State, StateError, Type,
+};
+
+/// A set of flags of the `bitflags!` type `F`, which YASL sees as an int of its bits.
+/// Popping a set fails with `StateError::ValueError` if the int has bits which are not named flags of `F`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct FlagSet<F>(pub F);
+
+/// Convert the bits of a set of flags to a YASL int.
+fn to_int<F: Flags>(flags: &F) -> Result<i64, StateError>
+where
+    F::Bits: TryInto<i64>,
+{
+    flags.bits().try_into().map_err(|_| StateError::ValueError)
+}
+
+/// Convert a YASL int to a set of flags, if every bit is a known flag.
+fn from_int<F: Flags>(n: i64) -> Option<F>
+where
+    F::Bits: TryFrom<i64>,
+{
+    F::Bits::try_from(n).ok().and_then(F::from_bits)
+}
+
+impl<F: Flags> FromYasl for FlagSet<F>
+where
+    F::Bits: TryFrom<i64>,
+{
+    const TYPE_NAME: &'static str = "int";
+
+    fn is_n(state: &State, n: usize) -> bool {
+        state.peek_n_type(n) == Type::Int
+    }
+
+    fn pop_from(state: &mut State) -> Result<Self, StateError> {
+        let n = state.peek_int_strict().ok_or(StateError::TypeError)?;
+        let flags = from_int(n).ok_or(StateError::ValueError)?;
+        state.pop();
+        Ok(Self(flags))
+    }
+}
+
+impl<F: Flags> IntoYasl for FlagSet<F>
+where
+    F::Bits: TryInto<i64>,
+{
+    fn push_to(self, state: &mut State) -> Result<(), StateError> {
+        state.push_int(to_int(&self.0)?);
+        Ok(())
+    }
+}
+
+impl State {
+    /// Declare a global table named `name` with an int for each named flag of `F`, e.g., `Access.READ`,
+    /// and a variadic function `combine` which returns the union of its arguments, e.g., `Access.combine(Access.READ, Access.WRITE)`.
+    /// `combine` throws a `ValueError` for any bits which are not named flags of `F`.
+    /// # Errors
+    /// Will return an error if `name` is not a valid identifier, or if a flag doesn't fit in a YASL int.
+    pub fn declare_flags<F: Flags>(&mut self, name: &str) -> Result<(), crate::Error>
+    where
+        F::Bits: TryFrom<i64> + TryInto<i64>,
+    {
+        crate::validate_identifier(name)?;
+
+        self.push_table();
+        self.table_set_functions(&[MetatableFunction {
+            name: "combine",
+            cfn: combine_flags::<F>,
+            args: -1,
+        }]);
+        for flag in F::FLAGS.iter().filter(|flag| flag.is_named()) {
+            let value = to_int(flag.value()).and_then(|n| {
+                self.table_set_key(
+                    &HashableObject::Str(flag.name().to_owned()),
+                    &Object::Int(n),
+                )
+            });
+            if let Err(e) = value {
+                self.pop();
+                return Err(e.into());
+            }
+        }
+        self.init_global_slice(name)?;
+        Ok(())
+    }
+}
+
+/// Implements the `combine` function of a global declared by `State::declare_flags`.
+unsafe extern "C" fn combine_flags<F: Flags>(state: *mut YASL_State) -> c_int
+where
+    F::Bits: TryFrom<i64> + TryInto<i64>,
+{
+    run_host_fn(state, |state| {
+        // The last argument is on top of the stack.
+        let count = usize::try_from(state.peek_vargs_count()).unwrap_or_default();
+        let mut flags = F::empty();
+        for n in (0..count).rev() {
+            let Some(bits) = state.peek_int_strict() else {
+                return Err(ScriptError::new(
+                    StateError::TypeError,
+                    format!(
+                        "TypeError: combine expected arg in position {n} to be of type int, got arg of type {}.",
+                        state.peek_type_name_slice().unwrap_or("undef")
+                    ),
+                ));
+            };
+            let flag = from_int::<F>(bits).ok_or_else(|| {
+                ScriptError::new(
+                    StateError::ValueError,
+                    format!("ValueError: combine expected known flags, got {bits}."),
+                )
+            })?;
+            state.pop();
+            flags.insert(flag);
+        }
+
+        let n = to_int(&flags).map_err(|e| {
+            ScriptError::new(
+                e,
+                "ValueError: combine got flags which don't fit in an int.",
+            )
+        })?;
+        state.push_int(n);
+        Ok(1)
+    })
+}
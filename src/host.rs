@@ -0,0 +1,282 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{mpsc, Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+};
+
+use crate::{aux::Object, send_cell::SendCell, State, StateError, Type};
+
+/// Owns a `State` on a dedicated thread, so that the rest of an application can interact with
+/// its scripts through `ScriptHandle`s from any thread or async task.
+/// Requests are handled one at a time, in the order they were sent.
+pub struct ScriptHost {
+    handle: ScriptHandle,
+    thread: Option<JoinHandle<SendCell<State>>>,
+}
+
+/// A cloneable handle for sending requests to a `ScriptHost`'s state.
+#[derive(Clone)]
+pub struct ScriptHandle {
+    sender: mpsc::Sender<Message>,
+}
+
+/// The pending result of a request to a `ScriptHost`. Either block on it with `wait`, or `.await` it.
+#[must_use = "The request is still handled, but its result is lost if the reply is dropped."]
+pub struct Reply<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// A request for the host thread.
+enum Message {
+    Run(Box<dyn FnOnce(&mut State) + Send>),
+    Stop,
+}
+
+/// The result slot shared by a `Reply` and the request which completes it.
+struct Shared<T> {
+    slot: Mutex<Slot<T>>,
+    completed: Condvar,
+}
+struct Slot<T> {
+    value: Option<SendCell<Result<T, StateError>>>,
+    waker: Option<Waker>,
+}
+
+/// Completes a `Reply`. If dropped without sending, e.g., because the host stopped
+/// or panicked before handling the request, the reply completes with `StateError::Generic`.
+struct Completer<T> {
+    shared: Option<Arc<Shared<T>>>,
+}
+
+impl ScriptHost {
+    /// Move the state to a new thread which handles requests until the host is stopped or dropped.
+    /// The state should already be executed, so that its globals are defined.
+    /// Its user data moves to the new thread too, which `State::push_userdata_box` allows by only accepting `Send` data.
+    #[must_use]
+    pub fn new(state: State) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        // The state is only used by the host thread until it is handed back by `stop`, and its user data is `Send`.
+        let state = SendCell(state);
+        let thread = thread::spawn(move || {
+            let mut state = state.into_inner();
+            for message in receiver {
+                match message {
+                    Message::Run(f) => f(&mut state),
+                    Message::Stop => break,
+                }
+            }
+            SendCell(state)
+        });
+
+        Self {
+            handle: ScriptHandle { sender },
+            thread: Some(thread),
+        }
+    }
+
+    /// Get a new handle to the host's state, which can be sent to other threads.
+    #[must_use]
+    pub fn handle(&self) -> ScriptHandle {
+        self.handle.clone()
+    }
+
+    /// Stop the host once the requests already sent have been handled, and return its state.
+    /// Requests sent afterwards, from any remaining handles, fail with `StateError::Generic`.
+    /// # Panics
+    /// Resumes the panic if the host thread panicked while handling a request.
+    pub fn stop(mut self) -> State {
+        let thread = self.stop_thread();
+        match thread.join() {
+            Ok(state) => state.into_inner(),
+            Err(e) => std::panic::resume_unwind(e),
+        }
+    }
+
+    /// Send the stop message, and take the host thread's handle.
+    fn stop_thread(&mut self) -> JoinHandle<SendCell<State>> {
+        // The host thread may have already exited from a panic.
+        let _ = self.handle.sender.send(Message::Stop);
+        self.thread
+            .take()
+            .expect("Internal Error: Script host was already stopped.")
+    }
+}
+
+impl Drop for ScriptHost {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            let _ = self.stop_thread().join();
+        }
+    }
+}
+
+impl ScriptHandle {
+    // TODO: Call a global function with `Object` arguments (e.g., `call(name, args) -> Reply<Vec<Object>>`) without
+    // building an expression for `eval`. This needs `call_global` to return the function's runtime errors first.
+
+    /// Compile and run a single expression against the host state's globals.
+    /// # Errors
+    /// The reply is `StateError::Generic` if the host has stopped, or has the same errors as `State::eval`.
+    pub fn eval(&self, expr: impl Into<String>) -> Reply<Object> {
+        let expr = expr.into();
+        self.request(move |state| state.eval(&expr))
+    }
+
+    /// Run `f` with exclusive access to the host's state, e.g., to set globals or register functions.
+    /// # Errors
+    /// The reply is `StateError::Generic` if the host has stopped before running `f`.
+    pub fn with<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut State) -> R + Send + 'static,
+    ) -> Reply<R> {
+        self.request(move |state| Ok(f(state)))
+    }
+
+    /// Send a request whose result completes the returned reply.
+    fn request<T: 'static>(
+        &self,
+        f: impl FnOnce(&mut State) -> Result<T, StateError> + Send + 'static,
+    ) -> Reply<T> {
+        let shared = Arc::new(Shared {
+            slot: Mutex::new(Slot {
+                value: None,
+                waker: None,
+            }),
+            completed: Condvar::new(),
+        });
+        let completer = Completer {
+            shared: Some(shared.clone()),
+        };
+
+        // If the host has stopped, dropping the message drops the completer, which completes the reply.
+        let _ = self.sender.send(Message::Run(Box::new(move |state| {
+            completer.send(f(state))
+        })));
+        Reply { shared }
+    }
+}
+
+impl<T> Reply<T> {
+    /// Block the current thread until the request has been handled, and return its result.
+    /// # Errors
+    /// Returns the request's error, or `StateError::Generic` if the host stopped before handling it.
+    #[allow(clippy::missing_panics_doc)] // Unwrapping mutex lock should never fail.
+    pub fn wait(self) -> Result<T, StateError> {
+        let mut slot = self
+            .shared
+            .completed
+            .wait_while(self.shared.slot.lock().unwrap(), |slot| {
+                slot.value.is_none()
+            })
+            .unwrap();
+        slot.value.take().unwrap().into_inner()
+    }
+}
+
+impl<T> Future for Reply<T> {
+    type Output = Result<T, StateError>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.shared.slot.lock().unwrap();
+        if let Some(value) = slot.value.take() {
+            Poll::Ready(value.into_inner())
+        } else {
+            slot.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Completer<T> {
+    /// Complete the reply with the given result.
+    fn send(mut self, result: Result<T, StateError>) {
+        if let Some(shared) = self.shared.take() {
+            let mut slot = shared.slot.lock().unwrap();
+            slot.value = Some(SendCell(result));
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+            shared.completed.notify_all();
+        }
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        if self.shared.is_some() {
+            Completer {
+                shared: self.shared.take(),
+            }
+            .send(Err(StateError::Generic));
+        }
+    }
+}
+
+/// Call the global function `name` with the given arguments and pop all of its return values.
+/// Returns `StateError::TypeError` without calling anything if the global is not a function.
+/// # Safety
+/// The function must not raise a runtime error, since this call isn't made inside `State::execute`.
+// TODO: Make the public callers of this function safe. YASL only recovers from errors raised inside
+// `YASL_execute`, and `YASL_functioncall` jumps to the handler of the last execution, which has already returned.
+// Calling the function from a compiled chunk doesn't help either, since YASL then looks up the error's line in the
+// chunk's line table with a position in the function's own bytecode, and reads past the table. Errors can only be
+// returned once YASL supports protected calls, or reports lines relative to the failing function's bytecode.
+pub(crate) unsafe fn call_global(
+    state: &mut State,
+    name: &str,
+    args: &[Object],
+) -> Result<Vec<Object>, StateError> {
+    // Drop the function and any arguments already pushed if an argument fails to convert.
+    let mut state = state.stack_guard();
+    state.load_global_slice(name)?;
+    unsafe { call_top(&mut state, args) }
+}
+
+/// Call the function on top of the stack with the given arguments and pop all of its return values.
+/// Returns `StateError::TypeError` without calling anything if the top of the stack is not a function.
+/// If this fails, the function and any arguments already pushed are left for the caller's `StackGuard` to pop.
+/// # Safety
+/// The function must not raise a runtime error, as for `call_global`.
+pub(crate) unsafe fn call_top(
+    state: &mut State,
+    args: &[Object],
+) -> Result<Vec<Object>, StateError> {
+    if !matches!(state.peek_type(), Type::Fn | Type::Closure | Type::CFn) {
+        return Err(StateError::TypeError);
+    }
+    for arg in args {
+        state.push_object(arg)?;
+    }
+
+    // Pop the return values, which are pushed with the last value on top.
+    let n = state.function_call(args.len());
+    let mut results = (0..n)
+        .map(|_| state.pop_object(None))
+        .collect::<Result<Vec<_>, _>>()?;
+    results.reverse();
+    Ok(results)
+}
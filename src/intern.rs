@@ -0,0 +1,59 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Interning of strings which are pushed many times, e.g., the field names of exported records.
+
+use std::ffi::CString;
+
+use crate::State;
+
+impl State {
+    /// Push a string which is kept by the state, so that pushing the same string again reuses its memory
+    /// instead of allocating a copy each time, e.g., for the keys of thousands of records with the same fields.
+    /// After the first push, the string is found with a single lookup and YASL borrows it like `push_literal`.
+    /// YASL may refer to the pushed strings until the state is deleted, so an owned state keeps them until it is
+    /// dropped, while a borrowed state keeps them for the lifetime of the program. Only strings which are pushed
+    /// repeatedly should be interned.
+    /// YASL can only borrow zero-terminated strings, so a string with a zero byte is copied like `push_str`.
+    pub fn push_interned(&mut self, string: &str) {
+        let literal = match self.interned.get(string) {
+            Some(&literal) => literal,
+            None => {
+                let Ok(cstring) = CString::new(string) else {
+                    self.push_str(string);
+                    return;
+                };
+                let literal = self.lifetime_cstr(cstring);
+                self.interned.insert(string.into(), literal);
+                literal
+            }
+        };
+        unsafe { yaslapi_sys::YASL_pushlit(self.state.as_ptr(), literal) }
+        self.grow_stack(1);
+    }
+
+    /// Forget the strings looked up by `push_interned`, e.g., once a batch of records was exported.
+    /// Values which were already pushed are unaffected, since the strings' memory is kept as described there.
+    pub fn clear_interned(&mut self) {
+        self.interned.clear();
+    }
+}
@@ -0,0 +1,103 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    aux::{HashableObject, Object},
+    StateError,
+};
+
+impl TryFrom<Value> for Object {
+    type Error = StateError;
+    /// Convert a JSON value into a YASL `Object`. `null` becomes `undef`, and objects become tables with string keys.
+    /// # Errors
+    /// Will return `StateError::ValueError` if a number fits neither an `i64` nor an `f64`.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Null => Self::Undef,
+            Value::Bool(b) => Self::Bool(b),
+            Value::Number(n) => {
+                // Unsigned integers larger than `i64::MAX` are rejected rather than losing precision.
+                if let Some(i) = n.as_i64() {
+                    Self::Int(i)
+                } else if n.is_f64() {
+                    Self::Float(n.as_f64().ok_or(StateError::ValueError)?)
+                } else {
+                    return Err(StateError::ValueError);
+                }
+            }
+            Value::String(s) => Self::Str(s),
+            Value::Array(array) => Self::List(
+                array
+                    .into_iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(map) => Self::Table(
+                map.into_iter()
+                    .map(|(k, v)| Ok((HashableObject::Str(k), Self::try_from(v)?)))
+                    .collect::<Result<_, StateError>>()?,
+            ),
+        })
+    }
+}
+
+impl TryFrom<&Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a JSON value. `undef` becomes `null`.
+    /// # Errors
+    /// Will return `StateError::ValueError` for floats which are not finite, and `StateError::TypeError`
+    /// for user data, user pointers, and tables with keys which are not strings.
+    fn try_from(value: &Object) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Object::Undef => Self::Null,
+            Object::Bool(b) => Self::Bool(*b),
+            Object::Int(i) => Self::Number((*i).into()),
+            Object::Float(f) => Self::Number(Number::from_f64(*f).ok_or(StateError::ValueError)?),
+            Object::Str(s) => Self::String(s.clone()),
+            Object::List(list) => {
+                Self::Array(list.iter().map(Self::try_from).collect::<Result<_, _>>()?)
+            }
+            Object::Table(table) => Self::Object(
+                table
+                    .iter()
+                    .map(|(k, v)| match k {
+                        HashableObject::Str(k) => Ok((k.clone(), Self::try_from(v)?)),
+                        _ => Err(StateError::TypeError),
+                    })
+                    .collect::<Result<Map<_, _>, _>>()?,
+            ),
+            Object::UserData { .. } | Object::UserPtr(_) => return Err(StateError::TypeError),
+        })
+    }
+}
+
+impl TryFrom<Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a JSON value. `undef` becomes `null`.
+    /// # Errors
+    /// Has the same errors as converting from `&Object`.
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
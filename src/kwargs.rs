@@ -0,0 +1,170 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A convention for named arguments, which scripts pass to host functions as a trailing table,
+//! e.g., `fetch(url, { 'timeout': 5 })`.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    aux::{HashableObject, Object, ScriptError},
+    convert::FromYasl,
+    State, StateError, Type,
+};
+
+/// The keyword arguments of a host function, popped from a trailing table argument.
+/// Each argument which is read is remembered, so that `deny_unknown` can reject the rest.
+///
+/// ```
+/// use yaslapi::{aux::run_host_fn, kwargs::KwArgs};
+///
+/// unsafe extern "C" fn fetch(state: *mut yaslapi_sys::YASL_State) -> i32 {
+///     run_host_fn(state, |state| {
+///         let mut kwargs = KwArgs::pop(state, "fetch")?;
+///         let timeout = kwargs.get_or::<i64>("timeout", 30)?;
+///         let retry = kwargs.get::<bool>("retry")?.unwrap_or_default();
+///         kwargs.deny_unknown()?;
+///         let url = state.pop_str().unwrap_or_default();
+///         state.push_str(&format!("{url} {timeout} {retry}"));
+///         Ok(1)
+///     })
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct KwArgs {
+    fn_name: String,
+    args: HashMap<HashableObject, Object>,
+    used: HashSet<String>,
+}
+
+impl KwArgs {
+    /// Pop the table of keyword arguments of the host function `fn_name` from the top of the stack.
+    /// An `undef` argument, e.g., when a script omits the table, gives no keyword arguments.
+    /// # Errors
+    /// Returns a `TypeError` if the top of the stack is neither a table nor `undef`, which is left on the stack,
+    /// or a `ValueError` if the table contains itself.
+    pub fn pop(state: &mut State, fn_name: &str) -> Result<Self, ScriptError> {
+        let args = match state.peek_type() {
+            Type::Undef => {
+                state.pop();
+                HashMap::new()
+            }
+            Type::Table => match state.pop_object(Some(Type::Table)) {
+                Ok(Object::Table(args)) => args,
+                _ => {
+                    return Err(ScriptError::new(
+                        StateError::ValueError,
+                        format!("ValueError: {fn_name} expected keyword arguments which don't contain themselves."),
+                    ))
+                }
+            },
+            actual => {
+                return Err(ScriptError::new(
+                    StateError::TypeError,
+                    format!("TypeError: {fn_name} expected keyword arguments of type table, got arg of type {actual}."),
+                ))
+            }
+        };
+        Ok(Self {
+            fn_name: fn_name.to_owned(),
+            args,
+            used: HashSet::new(),
+        })
+    }
+
+    /// Get the keyword argument `name`, or `None` if it wasn't given or is `undef`.
+    /// # Errors
+    /// Returns a `TypeError` if the argument can't be converted to `T`.
+    pub fn get<T>(&mut self, name: &str) -> Result<Option<T>, ScriptError>
+    where
+        T: FromYasl + TryFrom<Object>,
+    {
+        self.used.insert(name.to_owned());
+        let value = match self.args.get(&HashableObject::Str(name.to_owned())) {
+            None | Some(Object::Undef) => return Ok(None),
+            Some(value) => value.clone(),
+        };
+        let actual = Type::from(&value);
+        T::try_from(value).map(Some).map_err(|_| {
+            ScriptError::new(
+                StateError::TypeError,
+                format!(
+                    "TypeError: {} expected keyword argument {name} to be of type {}, got arg of type {actual}.",
+                    self.fn_name,
+                    T::TYPE_NAME
+                ),
+            )
+        })
+    }
+
+    /// Get the keyword argument `name`, or `default` if it wasn't given or is `undef`.
+    /// # Errors
+    /// Returns a `TypeError` if the argument can't be converted to `T`.
+    pub fn get_or<T>(&mut self, name: &str, default: T) -> Result<T, ScriptError>
+    where
+        T: FromYasl + TryFrom<Object>,
+    {
+        Ok(self.get(name)?.unwrap_or(default))
+    }
+
+    /// Get the keyword argument `name`, which must be given.
+    /// # Errors
+    /// Returns a `ValueError` if the argument wasn't given or is `undef`,
+    /// or a `TypeError` if it can't be converted to `T`.
+    pub fn require<T>(&mut self, name: &str) -> Result<T, ScriptError>
+    where
+        T: FromYasl + TryFrom<Object>,
+    {
+        self.get(name)?.ok_or_else(|| {
+            ScriptError::new(
+                StateError::ValueError,
+                format!(
+                    "ValueError: {} expected keyword argument {name}.",
+                    self.fn_name
+                ),
+            )
+        })
+    }
+
+    /// Check that every keyword argument was read with `get`, `get_or`, or `require`,
+    /// e.g., to catch misspelled names. Call this after reading all the arguments a function accepts.
+    /// # Errors
+    /// Returns a `ValueError` naming the first unknown argument, in the order of `HashableObject`.
+    pub fn deny_unknown(&self) -> Result<(), ScriptError> {
+        let unknown = self
+            .args
+            .keys()
+            .filter(|key| !matches!(key, HashableObject::Str(name) if self.used.contains(name)))
+            .min();
+        match unknown {
+            Some(key) => Err(ScriptError::new(
+                StateError::ValueError,
+                format!(
+                    "ValueError: {} got unknown keyword argument {}.",
+                    self.fn_name,
+                    Object::from(key.clone()).to_yasl_string()
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+}
@@ -62,17 +62,72 @@
 //! ```
 
 use num_derive::FromPrimitive;
-use once_cell::sync::Lazy;
-use regex::Regex;
 use std::{
-    collections::HashSet,
-    ffi::{CStr, CString},
+    collections::{
+        hash_map::{DefaultHasher, Entry},
+        BTreeSet, HashMap,
+    },
+    ffi::{CStr, CString, NulError},
+    fmt::{self, Display},
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
     os::raw::c_void,
+    path::Path,
     ptr::{null_mut, NonNull},
-    sync::Mutex,
+    str::FromStr,
+    sync::{LazyLock, Mutex},
 };
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "async")]
+mod async_exec;
 pub mod aux;
+pub mod builder;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod console;
+pub mod convert;
+pub mod docs;
+mod echo;
+#[cfg(feature = "bitflags")]
+pub mod flags;
+pub mod host;
+mod intern;
+#[cfg(feature = "json")]
+mod json;
+pub mod kwargs;
+pub mod metrics;
+pub mod modules;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod pool;
+mod pretty;
+pub mod repl;
+mod require;
+pub mod roundtrip;
+mod send_cell;
+pub mod snapshot;
+pub mod stack;
+mod syntax;
+pub mod template;
+pub mod testing;
+#[cfg(feature = "toml")]
+mod toml;
+pub mod typestate;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+/// Embed a YASL script which is checked for syntax errors at compile time.
+#[cfg(feature = "macros")]
+pub use yaslapi_macros::yasl_source;
 
 use yaslapi_sys::YASL_State;
 
@@ -90,91 +145,426 @@ pub enum StateSuccess {
 }
 
 /// Defines the error results that a YASL operation may return from the state machine.
-#[derive(Debug, FromPrimitive, PartialEq)]
-#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum StateError {
     /// Generic error.
-    Generic = yaslapi_sys::YASL_Error_YASL_ERROR,
+    Generic,
     /// YASL_State has not been correctly initialised.
-    InitError = yaslapi_sys::YASL_Error_YASL_INIT_ERROR,
+    InitError,
     /// Syntax error during compilation.
-    SyntaxError = yaslapi_sys::YASL_Error_YASL_SYNTAX_ERROR,
+    SyntaxError,
     /// Type error (at runtime).
-    TypeError = yaslapi_sys::YASL_Error_YASL_TYPE_ERROR,
+    TypeError,
     /// Division by zero error (at runtime).
-    DivideByZeroError = yaslapi_sys::YASL_Error_YASL_DIVIDE_BY_ZERO_ERROR,
+    DivideByZeroError,
     /// Invalid items (at runtime).
-    ValueError = yaslapi_sys::YASL_Error_YASL_VALUE_ERROR,
+    ValueError,
     /// Too many variables in current scope.
-    TooManyVarError = yaslapi_sys::YASL_Error_YASL_TOO_MANY_VAR_ERROR,
+    TooManyVarError,
     /// Platform specific code not supported for this platform.
-    PlatformNotSupp = yaslapi_sys::YASL_Error_YASL_PLATFORM_NOT_SUPP,
+    PlatformNotSupp,
     /// Assertion failed.
-    AssertError = yaslapi_sys::YASL_Error_YASL_ASSERT_ERROR,
-    /// Stack overflow occurred.
-    StackOverflowError = yaslapi_sys::YASL_Error_YASL_STACK_OVERFLOW_ERROR,
+    AssertError,
+    /// Stack overflow occurred. YASL raises this when a script uses more than 1024 stack slots
+    /// or nests more than 1000 calls, which are fixed when YASL is compiled.
+    StackOverflowError,
+    /// A result code which this crate doesn't know, e.g., an error added by a newer version of YASL.
+    Unknown(i32),
 }
 
-/// Define the errors that a YASL operation may return.
+/// The YASL result code of each known `StateError`.
 #[allow(clippy::cast_possible_wrap)]
-#[derive(Debug, FromPrimitive, PartialEq)]
-#[repr(i32)]
+const STATE_ERROR_CODES: [(StateError, i32); 10] = [
+    (
+        StateError::Generic,
+        yaslapi_sys::YASL_Error_YASL_ERROR as i32,
+    ),
+    (
+        StateError::InitError,
+        yaslapi_sys::YASL_Error_YASL_INIT_ERROR as i32,
+    ),
+    (
+        StateError::SyntaxError,
+        yaslapi_sys::YASL_Error_YASL_SYNTAX_ERROR as i32,
+    ),
+    (
+        StateError::TypeError,
+        yaslapi_sys::YASL_Error_YASL_TYPE_ERROR as i32,
+    ),
+    (
+        StateError::DivideByZeroError,
+        yaslapi_sys::YASL_Error_YASL_DIVIDE_BY_ZERO_ERROR as i32,
+    ),
+    (
+        StateError::ValueError,
+        yaslapi_sys::YASL_Error_YASL_VALUE_ERROR as i32,
+    ),
+    (
+        StateError::TooManyVarError,
+        yaslapi_sys::YASL_Error_YASL_TOO_MANY_VAR_ERROR as i32,
+    ),
+    (
+        StateError::PlatformNotSupp,
+        yaslapi_sys::YASL_Error_YASL_PLATFORM_NOT_SUPP as i32,
+    ),
+    (
+        StateError::AssertError,
+        yaslapi_sys::YASL_Error_YASL_ASSERT_ERROR as i32,
+    ),
+    (
+        StateError::StackOverflowError,
+        yaslapi_sys::YASL_Error_YASL_STACK_OVERFLOW_ERROR as i32,
+    ),
+];
+
+/// Define the errors that a YASL operation may return.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Type {
-    Undef = yaslapi_sys::YASL_Types_Y_UNDEF,
-    Float = yaslapi_sys::YASL_Types_Y_FLOAT,
-    Int = yaslapi_sys::YASL_Types_Y_INT,
-    Bool = yaslapi_sys::YASL_Types_Y_BOOL,
-    Str = yaslapi_sys::YASL_Types_Y_STR,
-    List = yaslapi_sys::YASL_Types_Y_LIST,
-    Table = yaslapi_sys::YASL_Types_Y_TABLE,
-    Fn = yaslapi_sys::YASL_Types_Y_FN,
-    Closure = yaslapi_sys::YASL_Types_Y_CLOSURE,
-    CFn = yaslapi_sys::YASL_Types_Y_CFN,
-    UserPtr = yaslapi_sys::YASL_Types_Y_USERPTR,
-    UserData = yaslapi_sys::YASL_Types_Y_USERDATA,
+    Undef,
+    Float,
+    Int,
+    Bool,
+    Str,
+    List,
+    Table,
+    Fn,
+    Closure,
+    CFn,
+    UserPtr,
+    UserData,
+    /// A type code which this crate doesn't know, e.g., a type added by a newer version of YASL.
+    Unknown(i32),
 }
 
-/// Lazily-initialized set of `CString`s that are allocated for the lifetime of the program.
-static LIFETIME_CSTRINGS: Lazy<Mutex<HashSet<CString>>> = Lazy::new(Mutex::default);
+/// The YASL type code of each known `Type`.
+const TYPE_CODES: [(Type, i32); 12] = [
+    (Type::Undef, yaslapi_sys::YASL_Types_Y_UNDEF),
+    (Type::Float, yaslapi_sys::YASL_Types_Y_FLOAT),
+    (Type::Int, yaslapi_sys::YASL_Types_Y_INT),
+    (Type::Bool, yaslapi_sys::YASL_Types_Y_BOOL),
+    (Type::Str, yaslapi_sys::YASL_Types_Y_STR),
+    (Type::List, yaslapi_sys::YASL_Types_Y_LIST),
+    (Type::Table, yaslapi_sys::YASL_Types_Y_TABLE),
+    (Type::Fn, yaslapi_sys::YASL_Types_Y_FN),
+    (Type::Closure, yaslapi_sys::YASL_Types_Y_CLOSURE),
+    (Type::CFn, yaslapi_sys::YASL_Types_Y_CFN),
+    (Type::UserPtr, yaslapi_sys::YASL_Types_Y_USERPTR),
+    (Type::UserData, yaslapi_sys::YASL_Types_Y_USERDATA),
+];
+
+/// Number of shards of `LIFETIME_CSTRINGS`, so that threads interning different names rarely wait for each other.
+const LIFETIME_CSTRING_SHARDS: usize = 16;
+
+/// Sets of C-strings that are allocated for the lifetime of the program, sharded by the hash of each string.
+/// Only used by borrowed states, since their YASL state may outlive the `State` wrapper.
+static LIFETIME_CSTRINGS: LazyLock<[Mutex<InternedNames>; LIFETIME_CSTRING_SHARDS]> =
+    LazyLock::new(|| std::array::from_fn(|_| Mutex::default()));
 
 /// Wrapper for the YASL state.
 pub struct State {
     state: NonNull<YASL_State>,
     owns_state: bool,
+    origin: Option<Origin>,
+    globals: BTreeSet<String>,
+    stack_len: usize,
+    names: InternedNames,
+    interned: HashMap<Box<str>, *const std::os::raw::c_char>,
+    metrics_enabled: bool,
+    missing_global_handler: Option<Box<MissingGlobalHandler>>,
+    lazy_libraries: Vec<builder::Library>,
+}
+
+/// Callback which provides the values of globals that a script reads without them being declared.
+type MissingGlobalHandler = dyn FnMut(&str) -> Option<aux::Object> + Send;
+
+/// Where the main program of a `State` was loaded from, so that it can be restored
+/// after compiling other source code against the same globals.
+enum Origin {
+    Source(Vec<u8>),
+    Script(CString),
+}
+
+/// Prefix of globals used internally by this crate, which are not reported as known globals.
+const HIDDEN_GLOBAL_PREFIX: &str = "__yaslapi_";
+
+/// Name of the hidden global used to return the result of `State::eval`.
+const EVAL_GLOBAL: &str = "__yaslapi_eval";
+
+/// Name of the global which YASL declares in every new state.
+const VERSION_GLOBAL: &str = "__VERSION__";
+
+/// Interned C-strings, keyed by the strings themselves so that each name is found or added with a single lookup.
+type InternedNames = HashMap<Box<CStr>, ()>;
+
+/// Get a pointer to the given C-string from the set, adding it if the same string isn't already kept alive.
+fn intern_cstr(names: &mut InternedNames, name: CString) -> *const std::os::raw::c_char {
+    match names.entry(name.into_boxed_c_str()) {
+        Entry::Occupied(existing) => existing.key().as_ptr(),
+        Entry::Vacant(entry) => {
+            // The heap allocation of a boxed `CStr` doesn't move when the box itself is moved into the map.
+            let ptr = entry.key().as_ptr();
+            entry.insert(());
+            ptr
+        }
+    }
+}
+
+/// Get the path of a script from the location given to YASL, or `None` if it isn't a valid path on this platform.
+fn script_path(script_location: &CStr) -> Option<&Path> {
+    #[cfg(unix)]
+    let path = Path::new(
+        <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(script_location.to_bytes()),
+    );
+    #[cfg(not(unix))]
+    let path = Path::new(script_location.to_str().ok()?);
+    Some(path)
 }
 
 /// Error returned when trying to initialize a global variable with an invalid name.
+/// YASL identifiers start with one of `[A-Za-z_$]`, followed by any of `[A-Za-z0-9_$]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InvalidIdentifier {
+    /// The name is empty.
+    Empty,
+    /// The character starting at byte `index` of the name is not allowed in that position.
+    InvalidChar { index: usize, character: char },
+}
+
+/// The globals which a script uses, as found by `State::analyze`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScriptInfo {
+    /// Names of globals whose values the script reads, including those which it modifies, e.g., with `+=`.
+    pub reads: BTreeSet<String>,
+    /// Names of globals which the script assigns new values to.
+    pub assigns: BTreeSet<String>,
+}
+
+impl ScriptInfo {
+    /// Every global which the script reads or assigns, in sorted order.
+    pub fn globals(&self) -> impl Iterator<Item = &str> {
+        self.reads.union(&self.assigns).map(String::as_str)
+    }
+}
+
+/// Error returned when a state can't be created from a script file.
 #[derive(Debug)]
-pub struct InvalidIdentifier;
+pub enum LoadError {
+    /// The file could not be opened, e.g., because it does not exist.
+    Io(io::Error),
+    /// The path contains a zero byte, or isn't valid Unicode on platforms where YASL requires it.
+    InvalidPath,
+    /// The file was opened, but YASL failed to initialize a state from it.
+    Init,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to open the script: {e}"),
+            Self::InvalidPath => f.write_str("the script's path can't be given to YASL"),
+            Self::Init => f.write_str("YASL failed to initialize a state from the script"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Generic => "YASL operation failed",
+            Self::InitError => "YASL state was not initialized",
+            Self::SyntaxError => "syntax error",
+            Self::TypeError => "type error",
+            Self::DivideByZeroError => "division by zero",
+            Self::ValueError => "value error",
+            Self::TooManyVarError => "too many variables in scope",
+            Self::PlatformNotSupp => "not supported on this platform",
+            Self::AssertError => "assertion failed",
+            Self::StackOverflowError => "stack overflow",
+            Self::Unknown(code) => return write!(f, "unknown YASL error code {code}"),
+        })
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl Display for InvalidIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("identifier is empty"),
+            Self::InvalidChar { index, character } => {
+                write!(
+                    f,
+                    "identifier has invalid character {character:?} at byte {index}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidIdentifier {}
+
+/// Any error returned by this crate, so that downstream code can use a single `Result<T, yaslapi::Error>`.
+#[derive(Debug)]
+pub enum Error {
+    /// A YASL operation failed.
+    State(StateError),
+    /// A name is not a valid YASL identifier.
+    InvalidIdentifier(InvalidIdentifier),
+    /// An `Object` couldn't be converted, e.g., with `i64::try_from`, because it has this other type.
+    UnexpectedType(Type),
+    /// A string isn't the name of a YASL type.
+    ParseType(ParseTypeError),
+    /// A string given to YASL contains a zero byte.
+    Nul(NulError),
+    /// A state couldn't be created from a script file.
+    Load(LoadError),
+    /// Reading a script or other input failed.
+    Io(io::Error),
+    /// A host function raised an error for its script.
+    Script(aux::ScriptError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::State(e) => e.fmt(f),
+            Self::InvalidIdentifier(e) => e.fmt(f),
+            Self::UnexpectedType(t) => write!(f, "unexpected value of type {t}"),
+            Self::ParseType(e) => e.fmt(f),
+            Self::Nul(e) => e.fmt(f),
+            Self::Load(e) => e.fmt(f),
+            Self::Io(e) => e.fmt(f),
+            Self::Script(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::State(e) => Some(e),
+            Self::InvalidIdentifier(e) => Some(e),
+            Self::UnexpectedType(_) => None,
+            Self::ParseType(e) => Some(e),
+            Self::Nul(e) => Some(e),
+            Self::Load(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::Script(e) => Some(e),
+        }
+    }
+}
+
+/// Implement `From` for each error wrapped by `Error`, so that `?` converts them.
+macro_rules! impl_from_error {
+    ($($t:ty => $variant:ident),* $(,)?) => {$(
+        impl From<$t> for Error {
+            fn from(e: $t) -> Self {
+                Self::$variant(e)
+            }
+        }
+    )*};
+}
+impl_from_error!(
+    StateError => State,
+    InvalidIdentifier => InvalidIdentifier,
+    Type => UnexpectedType,
+    ParseTypeError => ParseType,
+    NulError => Nul,
+    LoadError => Load,
+    io::Error => Io,
+    aux::ScriptError => Script,
+);
 
 /// A helper function to determine if the given string is a valid YASL identifier.
 pub fn is_valid_identifier(name: &str) -> bool {
-    static IDENTIFIER_REGEX: Lazy<Regex> = Lazy::new(|| {
-        regex::Regex::new(r#"[A-z_$][A-z0-9_$]*"#)
-            .expect("Internal Error: Unable to compile IDENTIFIER_REGEX.")
-    });
-    IDENTIFIER_REGEX.is_match(name)
+    validate_identifier(name).is_ok()
+}
+
+/// Check that the given string is a valid YASL identifier.
+/// # Errors
+/// Will return an `InvalidIdentifier` describing the first character which is not allowed, or if the name is empty.
+pub fn validate_identifier(name: &str) -> Result<(), InvalidIdentifier> {
+    let mut chars = name.char_indices();
+    match chars.next() {
+        None => return Err(InvalidIdentifier::Empty),
+        Some((index, character))
+            if !(character.is_ascii_alphabetic() || matches!(character, '_' | '$')) =>
+        {
+            return Err(InvalidIdentifier::InvalidChar { index, character });
+        }
+        Some(_) => {}
+    }
+    match chars.find(|&(_, c)| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '$'))) {
+        Some((index, character)) => Err(InvalidIdentifier::InvalidChar { index, character }),
+        None => Ok(()),
+    }
 }
 
 impl State {
     /// Initialize a new YASL `State` from a script's filepath. Returns `None` if the file does not exist or cannot be read.
+    /// Use `from_file` to find out why the script couldn't be loaded.
     /// # Panics
     /// The string slice `script_location` must not contain internal zero bytes.
     #[must_use]
     pub fn from_path(script_location: &str) -> Option<Self> {
-        let script_location = CString::new(script_location).unwrap();
+        Self::from_script(CString::new(script_location).unwrap())
+    }
+
+    /// Initialize a new YASL `State` from the script at the given path.
+    /// # Errors
+    /// Will return `LoadError::Io` if the file can't be opened, e.g., because it does not exist,
+    /// `LoadError::InvalidPath` if the path can't be given to YASL, or `LoadError::Init` if YASL fails to load the script.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        File::open(path).map_err(LoadError::Io)?;
+
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+        #[cfg(not(unix))]
+        let bytes = path.to_str().ok_or(LoadError::InvalidPath)?.as_bytes();
+
+        let script_location = CString::new(bytes).map_err(|_| LoadError::InvalidPath)?;
+        Self::from_script(script_location).ok_or(LoadError::Init)
+    }
+
+    /// Create a state for the script at the given location, or `None` if YASL can't load it.
+    fn from_script(script_location: CString) -> Option<Self> {
         let ptr = unsafe { yaslapi_sys::YASL_newstate(script_location.as_ptr()) };
 
         // Ensure that the pointer is not null before returning the final `State`.
         NonNull::new(ptr).map(|state| Self {
             state,
             owns_state: true,
+            origin: Some(Origin::Script(script_location)),
+            globals: BTreeSet::from([VERSION_GLOBAL.to_owned()]),
+            stack_len: 0,
+            names: HashMap::new(),
+            interned: HashMap::new(),
+            metrics_enabled: false,
+            missing_global_handler: None,
+            lazy_libraries: Vec::new(),
         })
     }
 
     /// Initialize a new YASL `State` from a string containing the source code.
     #[must_use]
     pub fn from_source(source: &str) -> Self {
+        Self::from_bytes(source.as_bytes())
+    }
+
+    /// Initialize a new YASL `State` from the bytes of the source code, which don't need to be valid UTF-8,
+    /// e.g., for scripts embedded with `include_bytes!`.
+    #[must_use]
+    pub fn from_bytes(source: &[u8]) -> Self {
         Self {
             state: unsafe {
                 NonNull::new_unchecked(yaslapi_sys::YASL_newstate_bb(
@@ -183,9 +573,27 @@ impl State {
                 ))
             },
             owns_state: true,
+            origin: Some(Origin::Source(source.to_vec())),
+            globals: BTreeSet::from([VERSION_GLOBAL.to_owned()]),
+            stack_len: 0,
+            names: HashMap::new(),
+            interned: HashMap::new(),
+            metrics_enabled: false,
+            missing_global_handler: None,
+            lazy_libraries: Vec::new(),
         }
     }
 
+    /// Initialize a new YASL `State` from source code read until the end of the given reader,
+    /// e.g., a file inside an archive or a network stream.
+    /// # Errors
+    /// Will return any error from reading the source.
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut source = Vec::new();
+        reader.read_to_end(&mut source)?;
+        Ok(Self::from_bytes(&source))
+    }
+
     /// Safely convert from a raw pointer to a YASL `State`, or `None` if given a null pointer.
     /// A `State` created from a raw pointer **will not** be dropped when it goes out of scope.
     /// Useful for creating a `State` from within a YASL callback C-function.
@@ -194,6 +602,14 @@ impl State {
         NonNull::new(state).map(|state| Self {
             state,
             owns_state: false,
+            origin: None,
+            globals: BTreeSet::new(),
+            stack_len: 0,
+            names: HashMap::new(),
+            interned: HashMap::new(),
+            metrics_enabled: false,
+            missing_global_handler: None,
+            lazy_libraries: Vec::new(),
         })
     }
 
@@ -203,77 +619,126 @@ impl State {
     /// # Errors
     /// Will return `StateError::SyntaxError` if the source code contains invalid syntax.
     pub fn compile(&mut self) -> Result<StateSuccess, StateError> {
+        self.resolve_origin_globals();
         unsafe { state_result(yaslapi_sys::YASL_compile(self.state.as_ptr())) }
     }
 
+    // TODO: Expose compiling to a serializable bytecode buffer (e.g., `compile_to_bytes`) and
+    // creating or resetting a state from such a buffer. The YASL API keeps compiled bytecode
+    // private to the `YASL_State` and can only create states from source (`YASL_newstate_bb`),
+    // so this needs upstream support for exporting and loading bytecode first.
+    // TODO: Once bytecode can be exported, add a `BytecodeCache` which hashes source files and stores
+    // compiled artifacts in a cache directory, compiling from source on a cache miss.
+    // TODO: Likewise, an immutable `Program` which is compiled once and instantiates many states, e.g., one per
+    // request of a server, needs YASL to create a state from loaded bytecode without compiling its source again.
+
     /// Add a new global variable to the state with default value `undef`.
     /// The variable `name` must be a valid `YASL` identifier.
-    /// Adds `name` to the internal map of `CString`s that are kept alive for the lifetime of the program.
     /// # Errors
     /// Will return an `InvalidIdentifier` if the given name is not a valid YASL identifier.
-    /// # Panics
-    /// The argument `name` must not contain internal zero bytes.
+    #[allow(clippy::missing_panics_doc)] // Valid identifiers don't contain zero bytes.
     pub fn declare_global(&mut self, name: &str) -> Result<(), InvalidIdentifier> {
-        if !is_valid_identifier(name) {
-            return Err(InvalidIdentifier);
-        }
+        validate_identifier(name)?;
 
+        // YASL copies the name when declaring a global.
         let var_name = CString::new(name).unwrap();
-        let mut lifetime_strings = LIFETIME_CSTRINGS.lock().unwrap();
-
-        // Ensure that if the C-string is already in our map that we use the original pointer.
-        let existing_cstr = lifetime_strings.get(&var_name);
-
-        // Declare the global variable.
-        unsafe {
-            yaslapi_sys::YASL_declglobal(
-                self.state.as_ptr(),
-                existing_cstr.unwrap_or(&var_name).as_ptr(),
-            )
-        };
-
-        if existing_cstr.is_none() {
-            // Prevent the C-string from being dropped.
-            lifetime_strings.insert(var_name);
-        }
+        unsafe { yaslapi_sys::YASL_declglobal(self.state.as_ptr(), var_name.as_ptr()) };
+        self.record_global(name);
         Ok(())
     }
 
     /// Add std collections library to the global scope.
     pub fn declare_lib_collections(&mut self) -> i32 {
+        self.record_global("collections");
         unsafe { yaslapi_sys::YASL_decllib_collections(self.state.as_ptr()) }
     }
     /// Add std error-handling library to the global scope.
     pub fn declare_lib_error(&mut self) -> i32 {
+        self.record_global("error");
         unsafe { yaslapi_sys::YASL_decllib_error(self.state.as_ptr()) }
     }
     /// Add std io library to the global scope.
     pub fn declare_lib_io(&mut self) -> i32 {
+        self.record_global("io");
         unsafe { yaslapi_sys::YASL_decllib_io(self.state.as_ptr()) }
     }
     /// Add std math library to the global scope.
     pub fn declare_lib_math(&mut self) -> i32 {
+        self.record_global("math");
         unsafe { yaslapi_sys::YASL_decllib_math(self.state.as_ptr()) }
     }
     /// Add std library importing YASL code to the global scope.
     pub fn declare_lib_require(&mut self) -> i32 {
-        unsafe { yaslapi_sys::YASL_decllib_require(self.state.as_ptr()) }
+        self.record_global("require");
+        let result = unsafe { yaslapi_sys::YASL_decllib_require(self.state.as_ptr()) };
+        self.apply_require_overrides();
+        result
     }
     /// Add std library for importing C code to the global scope.
     pub fn declare_lib_require_c(&mut self) -> i32 {
-        unsafe { yaslapi_sys::YASL_decllib_require_c(self.state.as_ptr()) }
+        self.record_global("__require_c__");
+        let result = unsafe { yaslapi_sys::YASL_decllib_require_c(self.state.as_ptr()) };
+        self.apply_require_overrides();
+        result
     }
     /// Add std metatable library to the global scope.
     pub fn declare_lib_mt(&mut self) -> i32 {
+        self.record_global("mt");
         unsafe { yaslapi_sys::YASL_decllib_mt(self.state.as_ptr()) }
     }
+    /// Add std operating system library to the global scope.
+    pub fn declare_lib_os(&mut self) -> i32 {
+        self.record_global("os");
+        unsafe { yaslapi_sys::YASL_decllib_os(self.state.as_ptr()) }
+    }
+
+    /// Remember that a global with the given name exists, e.g., for completions.
+    pub(crate) fn record_global(&mut self, name: &str) {
+        if !name.starts_with(HIDDEN_GLOBAL_PREFIX) && !self.globals.contains(name) {
+            self.globals.insert(name.to_owned());
+        }
+    }
+
+    /// Get a pointer to the given C-string which stays valid as long as the YASL state, since YASL keeps
+    /// pointers to the names of globals, metatables, and table functions instead of copying them.
+    /// Owned states free their strings when dropped, while borrowed states keep them for the lifetime of the program.
+    #[allow(clippy::missing_panics_doc)] // Unwrapping mutex lock should never fail.
+    pub(crate) fn lifetime_cstr(&mut self, name: CString) -> *const std::os::raw::c_char {
+        if self.owns_state {
+            intern_cstr(&mut self.names, name)
+        } else {
+            let mut hasher = DefaultHasher::new();
+            name.hash(&mut hasher);
+            #[allow(clippy::cast_possible_truncation)] // Only the low bits select a shard.
+            let shard = &LIFETIME_CSTRINGS[hasher.finish() as usize % LIFETIME_CSTRING_SHARDS];
+            intern_cstr(&mut shard.lock().unwrap(), name)
+        }
+    }
 
     /// Duplicate the top item on the stack and push it to the stack.
     pub fn clone_top(&mut self) {
         unsafe {
             yaslapi_sys::YASL_duptop(self.state.as_ptr());
         }
-    }
+        self.grow_stack(1);
+    }
+
+    // TODO: A per-state limit on the depth of recursion (e.g., `set_max_call_depth`), so that deep recursion raises
+    // `StackOverflowError` at a depth chosen by the host. YASL's stack size and number of call frames are
+    // constants of its C sources (`STACK_SIZE` and `NUM_FRAMES`), and the VM isn't visible through its API,
+    // so this needs upstream support for configuring the limits of a `YASL_State` first.
+    // TODO: Line coverage of executed scripts (e.g., with an lcov report) needs a hook which YASL's VM calls
+    // when it reaches a new source line. The VM only reads its line table to report errors, and YASL's API
+    // has no debug hooks, so coverage can't be collected until one is added to YASL and `yaslapi-sys`.
+    // TODO: A frame-budgeted call for game loops (e.g., `run_budgeted(entry_fn, budget) -> RunOutcome`), which suspends
+    // a script function once it has used its budget of instructions or time and resumes it on the next frame.
+    // This needs the same hook from YASL's VM as a time limit, and also a way to save and restore a running call's
+    // frames, which YASL's API doesn't expose either, so it can't be built until both are added upstream.
+    // TODO: A plugin manager which loads each script in a directory into its own state and calls entry points such as
+    // `on_init`, `on_event`, and `on_shutdown`, reporting each plugin's errors without affecting the others. Calling an
+    // entry point after its script has executed goes through `YASL_functioncall`, which has no protected mode, so a
+    // runtime error jumps to the handler of an execution which has already returned. Plugins can't be isolated
+    // until YASL supports protected calls (see `host::call_global`).
 
     /// Execute the state's bytecode.
     /// Returns `StateSuccess::Generic` if successful.
@@ -281,7 +746,10 @@ impl State {
     /// Will return `StateError::SyntaxError` if the source code contains invalid syntax.
     /// May return runtime errors depending on the source code and execution state.
     pub fn execute(&mut self) -> Result<StateSuccess, StateError> {
-        unsafe { state_result(yaslapi_sys::YASL_execute(self.state.as_ptr())) }
+        self.resolve_origin_globals();
+        self.record_execution(|state| unsafe {
+            state_result(yaslapi_sys::YASL_execute(state.state.as_ptr()))
+        })
     }
 
     /// Execute the state's bytecode in REPL mode. The only difference
@@ -292,7 +760,148 @@ impl State {
     /// Will return `StateError::SyntaxError` if the source code contains invalid syntax.
     /// May return runtime errors depending on the source code and execution state.
     pub fn execute_repl(&mut self) -> Result<StateSuccess, StateError> {
-        unsafe { state_result(yaslapi_sys::YASL_execute_REPL(self.state.as_ptr())) }
+        self.resolve_origin_globals();
+        self.record_execution(|state| unsafe {
+            state_result(yaslapi_sys::YASL_execute_REPL(state.state.as_ptr()))
+        })
+    }
+
+    /// Determine whether the given source is unfinished rather than invalid, e.g., because a
+    /// block, bracket, raw string, or block comment is still open, or a line ends with an operator.
+    /// Interactive frontends can use this to show a continuation prompt instead of an error.
+    /// The source is only scanned, not compiled, so complete source may still contain syntax errors.
+    #[must_use]
+    pub fn is_input_incomplete(source: &str) -> bool {
+        syntax::scan(source).is_incomplete()
+    }
+
+    /// Find the globals which the given source reads or assigns, without compiling or running it.
+    /// Names which the script binds itself, with `let`, `const`, `fn`, `for`, function parameters,
+    /// or `match` patterns, are local and not reported. Hosts can use this to check that a script
+    /// only uses the globals they allow before running it.
+    /// The source is only scanned, not compiled, so source with syntax errors is analyzed on a best-effort basis.
+    #[must_use]
+    pub fn analyze(source: &str) -> ScriptInfo {
+        let uses = syntax::scan(source).globals();
+        ScriptInfo {
+            reads: uses.reads.into_iter().map(str::to_owned).collect(),
+            assigns: uses.assigns.into_iter().map(str::to_owned).collect(),
+        }
+    }
+
+    /// Set a function which provides the value of each global that a script reads without it being declared,
+    /// e.g., to create parts of an API only once they are used, or to report similar names to script authors.
+    /// YASL rejects undeclared globals when compiling, so the function is called before the source is compiled,
+    /// with the names found by `analyze`, and each value it returns is declared as a global.
+    /// Returning `None` leaves the global undeclared, so compiling fails as before.
+    pub fn set_missing_global_handler(
+        &mut self,
+        handler: impl FnMut(&str) -> Option<aux::Object> + Send + 'static,
+    ) {
+        self.missing_global_handler = Some(Box::new(handler));
+    }
+
+    /// Declare the lazy libraries which the source reads, then call the missing global handler, if there is one,
+    /// for the other globals which the source reads without them being declared.
+    fn resolve_missing_globals(&mut self, source: &str) {
+        if self.missing_global_handler.is_none() && self.lazy_libraries.is_empty() {
+            return;
+        }
+        let reads = Self::analyze(source).reads;
+        self.declare_lazy_libraries(&reads);
+        let Some(mut handler) = self.missing_global_handler.take() else {
+            return;
+        };
+        for name in reads {
+            if self.globals.contains(&name) {
+                continue;
+            }
+            // Globals declared by YASL itself aren't recorded by this `State`, but can be loaded.
+            if self.load_global_slice(&name).is_ok() {
+                self.pop();
+                continue;
+            }
+            if let Some(value) = handler(&name) {
+                let _ = self.init_global_value(&name, &value);
+            }
+        }
+        self.missing_global_handler = Some(handler);
+    }
+
+    /// Resolve the globals read by the state's main program, as `resolve_missing_globals` does.
+    fn resolve_origin_globals(&mut self) {
+        if self.missing_global_handler.is_none() && self.lazy_libraries.is_empty() {
+            return;
+        }
+        let source = match &self.origin {
+            Some(Origin::Source(source)) => String::from_utf8_lossy(source).into_owned(),
+            Some(Origin::Script(script_location)) => {
+                match script_path(script_location).map(std::fs::read) {
+                    Some(Ok(source)) => String::from_utf8_lossy(&source).into_owned(),
+                    _ => return,
+                }
+            }
+            None => return,
+        };
+        self.resolve_missing_globals(&source);
+    }
+
+    /// Compile and run a single expression against the state's globals and return its value.
+    /// The state's main program is restored afterwards, so `execute` behaves as before.
+    /// # Errors
+    /// Will return `StateError::Generic` if the state was created from a raw pointer, since
+    /// its main program may be running and cannot be restored.
+    /// Will return `StateError::SyntaxError` if the expression contains invalid syntax,
+    /// or is not a single expression.
+    /// May return runtime errors depending on the expression and execution state.
+    #[allow(clippy::missing_panics_doc)] // The name `EVAL_GLOBAL` is always a valid identifier.
+    pub fn eval(&mut self, expr: &str) -> Result<aux::Object, StateError> {
+        if self.origin.is_none() {
+            return Err(StateError::Generic);
+        }
+
+        // Cut off a trailing comment, which would hide the closing parenthesis around the expression.
+        // It can't be moved to a new line instead, since YASL ends the statement at a line break after an operand.
+        let expr = &expr[..syntax::scan(expr).code_end()];
+        let expr = expr.trim_end_matches(';');
+        if !syntax::scan(expr).is_self_contained() {
+            return Err(StateError::SyntaxError);
+        }
+
+        // Assign the expression to a hidden global so that it can be read back.
+        self.declare_global(EVAL_GLOBAL)
+            .expect("Internal Error: Invalid EVAL_GLOBAL identifier.");
+        let source = format!("{EVAL_GLOBAL} = ({expr});");
+        self.reset_source(&source);
+        let result = self.execute();
+        self.restore_origin();
+        result?;
+
+        // Take the value and clear the hidden global so it doesn't keep the value alive.
+        let value = self.pop_global_slice(EVAL_GLOBAL, None)?;
+        self.push_undef();
+        self.set_global_slice(EVAL_GLOBAL)?;
+        Ok(value)
+    }
+
+    /// Compile and execute an additional piece of source against the state's globals, e.g., to load a plugin
+    /// or run a line entered in a REPL. The state's main program is restored afterwards, so `execute` behaves as before.
+    /// Top-level `let`, `const`, and `fn` declarations are local to the chunk, so a chunk must assign
+    /// declared globals for its results to outlive it.
+    /// # Errors
+    /// Will return `StateError::Generic` if the state was created from a raw pointer, since
+    /// its main program may be running and cannot be restored.
+    /// Will return `StateError::SyntaxError` if the chunk contains invalid syntax.
+    /// May return runtime errors depending on the chunk and execution state.
+    pub fn run_chunk(&mut self, source: &str) -> Result<StateSuccess, StateError> {
+        if self.origin.is_none() {
+            return Err(StateError::Generic);
+        }
+
+        self.reset_source(source);
+        let result = self.execute();
+        self.restore_origin();
+        result
     }
 
     /// Calls a function with `n` parameters. The function must be located below all `n`
@@ -302,16 +911,19 @@ impl State {
     /// # Panics
     /// The argument count `n` must be able to safely convert into a non-negative C signed integer.
     pub fn function_call(&mut self, n: usize) -> usize {
-        // TODO: Remove this if YASL API is updated to use unsigned values here.
+        let args: i32 = n.try_into().expect(
+            "The input argument count cannout be safely converted to a non-negative C signed integer.",
+        );
+
+        // YASL reports the difference between the number of arguments and the number of return values.
+        let difference = unsafe { yaslapi_sys::YASL_functioncall(self.state.as_ptr(), args) };
         #[allow(clippy::cast_sign_loss)]
-        unsafe {
-            yaslapi_sys::YASL_functioncall(
-                self.state.as_ptr(),
-                n.try_into().expect(
-                    "The input argument count cannout be safely converted to a non-negative C signed integer.",
-                ),
-            ) as usize
-        }
+        let results = (args - difference) as usize;
+
+        // The function and its arguments are replaced by its return values.
+        self.shrink_stack(n + 1);
+        self.grow_stack(results);
+        results
     }
 
     /// Checks if the top of the stack is a bool.
@@ -477,6 +1089,12 @@ impl State {
         unsafe { yaslapi_sys::YASL_len(self.state.as_ptr()) }
     }
 
+    // TODO: Compare the top two items of the stack as YASL's `==` and `<` would (e.g., `equals_top` and
+    // `compare_top`), calling `__eq`, `__lt`, and `__le` from their metatables. The VM's comparisons aren't
+    // exposed by YASL's API, and a value's metatable can only be set, not loaded, so this needs upstream support first.
+    // TODO: Likewise, arithmetic on the top items of the stack (e.g., `add_top` and `sub_top`) which calls
+    // `__add`, `__sub`, and the other operator methods of userdata needs YASL to expose its operators.
+
     /// Indexes the list on top of the stack and pushes the result to the stack.
     /// If `n` is negative it indexes from the end of the list.
     /// Returns `StateSuccess::Generic` if successful.
@@ -485,13 +1103,17 @@ impl State {
     /// # Panics
     /// The argument count `n` must be able to safely convert into a 64-bit signed integer.
     pub fn list_get(&mut self, n: isize) -> Result<StateSuccess, StateError> {
-        unsafe {
+        let result = unsafe {
             state_result(yaslapi_sys::YASL_listget(
                 self.state.as_ptr(),
                 n.try_into()
                     .expect("Index must be able to safely convert into a 64-bit signed integer."),
             ))
+        };
+        if result.is_ok() {
+            self.grow_stack(1);
         }
+        result
     }
 
     /// Pops the top of the stack and appends it to a list (which should be located directly below the top of the stack).
@@ -499,6 +1121,8 @@ impl State {
     /// # Errors
     /// If the object on the stack is not a list then it will return `StateError::TypeError`.
     pub fn list_push(&mut self) -> Result<StateSuccess, StateError> {
+        // The value is popped even if the list is invalid.
+        self.shrink_stack(1);
         unsafe { state_result(yaslapi_sys::YASL_listpush(self.state.as_ptr())) }
     }
 
@@ -507,12 +1131,16 @@ impl State {
     /// # Errors
     /// If the global does not exist then it will return `StateError::Generic`.
     pub fn load_global(&mut self, name: &CStr) -> Result<StateSuccess, StateError> {
-        unsafe {
+        let result = unsafe {
             state_result(yaslapi_sys::YASL_loadglobal(
                 self.state.as_ptr(),
                 name.as_ptr(),
             ))
+        };
+        if result.is_ok() {
+            self.grow_stack(1);
         }
+        result
     }
     /// Loads the specified global from state and pushes it to the stack.
     /// Returns `StateSuccess::Generic` if successful.
@@ -523,12 +1151,7 @@ impl State {
     /// The string slice `name` must not contain internal zero bytes.
     pub fn load_global_slice(&mut self, name: &str) -> Result<StateSuccess, StateError> {
         let name = CString::new(name).unwrap();
-        unsafe {
-            state_result(yaslapi_sys::YASL_loadglobal(
-                self.state.as_ptr(),
-                name.as_ptr(),
-            ))
-        }
+        self.load_global(&name)
     }
 
     /// Loads a metatable by name. Returns error `StateError::Generic` if the metatable
@@ -536,7 +1159,12 @@ impl State {
     /// # Errors
     /// If the metatable `name` does not exist then it will return `StateError::Generic`.
     pub fn load_mt(&mut self, name: &CStr) -> Result<StateSuccess, StateError> {
-        unsafe { state_result(yaslapi_sys::YASL_loadmt(self.state.as_ptr(), name.as_ptr())) }
+        let result =
+            unsafe { state_result(yaslapi_sys::YASL_loadmt(self.state.as_ptr(), name.as_ptr())) };
+        if result.is_ok() {
+            self.grow_stack(1);
+        }
+        result
     }
     /// Loads a metatable by name. Returns error `StateError::Generic` if the metatable
     /// could not be found, else `StateSuccess::Generic`.
@@ -547,12 +1175,20 @@ impl State {
     /// If the metatable `name` does not exist then it will return `StateError::Generic`.
     pub fn load_mt_slice(&mut self, name: &str) -> Result<StateSuccess, StateError> {
         let name = CString::new(name).unwrap();
-        unsafe { state_result(yaslapi_sys::YASL_loadmt(self.state.as_ptr(), name.as_ptr())) }
+        self.load_mt(&name)
     }
 
-    // TODO: Determine if these should be added.
-    // YASL_loadprintout(struct YASL_State *S);
-    // YASL_loadprinterr(struct YASL_State *S);
+    /// Pushes the standard output captured since `set_printout_tostr` was called as a string.
+    pub fn load_printout(&mut self) {
+        unsafe { yaslapi_sys::YASL_loadprintout(self.state.as_ptr()) }
+        self.grow_stack(1);
+    }
+    /// Pushes the error output captured since `set_printerr_tostr` was called as a string.
+    /// If compilation failed, this is the compiler's output, otherwise it is the VM's.
+    pub fn load_printerr(&mut self) {
+        unsafe { yaslapi_sys::YASL_loadprinterr(self.state.as_ptr()) }
+        self.grow_stack(1);
+    }
 
     /// Returns the boolean value of the top of the stack, if it is a bool.
     /// Otherwise, returns false.
@@ -591,6 +1227,37 @@ impl State {
     pub fn peek_int(&self) -> i64 {
         unsafe { yaslapi_sys::YASL_peekint(self.state.as_ptr()) }
     }
+    /// Returns the boolean value of the top of the stack, or `None` if it is not a bool.
+    /// Unlike `peek_bool`, a value of another type can't be mistaken for `false`.
+    #[must_use]
+    pub fn peek_bool_strict(&self) -> Option<bool> {
+        self.is_bool().then(|| self.peek_bool())
+    }
+    /// Returns the int value of the top of the stack, or `None` if it is not an int.
+    /// Unlike `peek_int`, a value of another type can't be mistaken for `0`.
+    #[must_use]
+    pub fn peek_int_strict(&self) -> Option<i64> {
+        self.is_int().then(|| self.peek_int())
+    }
+    /// Returns the float value of the top of the stack, or `None` if it is not a float.
+    /// Unlike `peek_float`, a value of another type can't be mistaken for `0.0`.
+    #[must_use]
+    pub fn peek_float_strict(&self) -> Option<f64> {
+        self.is_float().then(|| self.peek_float())
+    }
+    /// Returns the numeric value of the top of the stack as a float, converting an int if necessary.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is neither an int nor a float.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn peek_number(&self) -> Result<f64, StateError> {
+        if self.is_float() {
+            Ok(self.peek_float())
+        } else if self.is_int() {
+            Ok(self.peek_int() as f64)
+        } else {
+            Err(StateError::TypeError)
+        }
+    }
     /// Returns the userdata value of the top of the stack, if the top of the stack is a userdata.
     /// TODO: At the time of this note, Sept. 24/2023, `YASL_peekuserdata(..)` is declared but not implemented. Use this API when it is implemented.
     #[must_use]
@@ -723,7 +1390,23 @@ impl State {
         })
     }
 
-    /// TODO: Document.
+    /// Returns the type of the object at index `n` from the top of the stack.
+    /// # Panics
+    /// The argument count `n` must be able to safely convert into a C unsigned integer.
+    #[must_use]
+    pub fn peek_n_type(&self, n: usize) -> Type {
+        unsafe {
+            yaslapi_sys::YASL_peekntype(
+                self.state.as_ptr(),
+                n.try_into()
+                    .expect("Index must be able to safely convert into a C unsigned integer."),
+            )
+        }
+        .into()
+    }
+
+    /// Returns the number of variadic arguments passed to the running C function,
+    /// which must have been pushed with a negative number of arguments. See `vargs`.
     #[must_use]
     pub fn peek_vargs_count(&self) -> i64 {
         unsafe { yaslapi_sys::YASL_peekvargscount(self.state.as_ptr()) }
@@ -732,15 +1415,21 @@ impl State {
     /// Removes the top of the stack.
     pub fn pop(&mut self) {
         unsafe { yaslapi_sys::YASL_pop(self.state.as_ptr()) }
+        self.shrink_stack(1);
     }
     /// Returns the bool value at the top of the stack, if the top of the stack is a boolean. Otherwise returns false. Removes the top element of the stack.
     pub fn pop_bool(&mut self) -> bool {
+        // YASL only pops the value if it has the expected type.
+        if self.is_bool() {
+            self.shrink_stack(1);
+        }
         unsafe { yaslapi_sys::YASL_popbool(self.state.as_ptr()) }
     }
     /// Returns the string value of the top of the stack, if the top of the stack is a string. Otherwise returns `None`. Removes the top of the stack.
     /// # Panics
     /// The popped string must contain valid UTF-8.
     pub fn pop_str(&mut self) -> Option<String> {
+        self.shrink_stack(1);
         unsafe {
             let ptr = yaslapi_sys::YASL_popcstr(self.state.as_ptr());
             if ptr.is_null() {
@@ -757,15 +1446,32 @@ impl State {
     }
     /// Returns the float value at the top of the stack, if the top of the stack is a float. Otherwise returns 0.0. Removes the top of the stack.
     pub fn pop_float(&mut self) -> f64 {
+        // YASL only pops the value if it has the expected type.
+        if self.is_float() {
+            self.shrink_stack(1);
+        }
         unsafe { yaslapi_sys::YASL_popfloat(self.state.as_ptr()) }
     }
     /// Returns the int value of the top of the stack, if the top of the stack is an int. Otherwise returns 0. Removes the top of the stack.
     pub fn pop_int(&mut self) -> i64 {
+        // YASL only pops the value if it has the expected type.
+        if self.is_int() {
+            self.shrink_stack(1);
+        }
         unsafe { yaslapi_sys::YASL_popint(self.state.as_ptr()) }
     }
+    /// Returns the numeric value of the top of the stack as a float, converting an int if necessary. Removes the top of the stack.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the top of the stack is neither an int nor a float, and leaves the stack unchanged.
+    pub fn pop_number(&mut self) -> Result<f64, StateError> {
+        let number = self.peek_number()?;
+        self.pop();
+        Ok(number)
+    }
     /// Returns the `UserData` value of the top of the stack, if the top of the stack is a `UserData`. Otherwise returns `None`. Removes the top of the stack.
     pub fn pop_userdata(&mut self) -> Option<NonNull<c_void>> {
         if self.peek_type() == Type::UserData {
+            self.shrink_stack(1);
             NonNull::new(unsafe { yaslapi_sys::YASL_popuserdata(self.state.as_ptr()) })
         } else {
             // Ensure that we still pop the value off the stack for the caller's sake.
@@ -776,6 +1482,7 @@ impl State {
     /// Returns the `UserPtr` value of the top of the stack, if the top of the stack is a `UserPtr`. Otherwise returns `None`. Removes the top of the stack.
     pub fn pop_userptr(&mut self) -> Option<NonNull<c_void>> {
         if self.peek_type() == Type::UserPtr {
+            self.shrink_stack(1);
             NonNull::new(unsafe { yaslapi_sys::YASL_popuserptr(self.state.as_ptr()) })
         } else {
             // Ensure that we still pop the value off the stack for the caller's sake.
@@ -793,44 +1500,66 @@ impl State {
     /// Pushes a boolean value onto the stack.
     pub fn push_bool(&mut self, b: bool) {
         unsafe { yaslapi_sys::YASL_pushbool(self.state.as_ptr(), b) }
+        self.grow_stack(1);
     }
     /// Pushes a C-style function onto the stack.
     pub fn push_cfunction(&mut self, f: CFunction, num_args: i32) {
         unsafe { yaslapi_sys::YASL_pushcfunction(self.state.as_ptr(), Some(f), num_args) }
+        self.grow_stack(1);
     }
     /// Pushes a double value onto the stack.
     pub fn push_float(&mut self, f: f64) {
         unsafe { yaslapi_sys::YASL_pushfloat(self.state.as_ptr(), f) }
+        self.grow_stack(1);
     }
     /// Pushes an integer value onto the stack.
     pub fn push_int(&mut self, i: i64) {
         unsafe { yaslapi_sys::YASL_pushint(self.state.as_ptr(), i) }
+        self.grow_stack(1);
     }
     /// Pushes an empty list onto the stack.
     pub fn push_list(&mut self) {
         unsafe { yaslapi_sys::YASL_pushlist(self.state.as_ptr()) }
+        self.grow_stack(1);
     }
     /// Pushes a nul-terminated string onto the stack. This memory will not be managed by YASL and must outlive the state.
     pub fn push_literal(&mut self, string: &'static CStr) {
         unsafe { yaslapi_sys::YASL_pushlit(self.state.as_ptr(), string.as_ptr().cast()) }
+        self.grow_stack(1);
+    }
+    /// Pushes a string which lives as long as the program, e.g., a constant field name pushed in a loop.
+    /// If the string ends with its only zero byte, e.g., `"name\0"`, YASL borrows it like `push_literal`
+    /// without copying it or converting it to a `CStr`, and the zero byte is not part of the pushed string.
+    /// YASL can only borrow zero-terminated strings, so any other string is copied like `push_str`.
+    pub fn push_str_static(&mut self, string: &'static str) {
+        match CStr::from_bytes_with_nul(string.as_bytes()) {
+            Ok(literal) => self.push_literal(literal),
+            Err(_) => self.push_str(string),
+        }
     }
+    // TODO: Borrow any `&'static str` once YASL's API can push a literal with a length rather than a terminator.
     /// Pushes an empty table onto the stack.
     pub fn push_table(&mut self) {
         unsafe { yaslapi_sys::YASL_pushtable(self.state.as_ptr()) }
+        self.grow_stack(1);
     }
     /// Pushes a string onto the stack. YASL makes a copy of the given string, and manages the memory for it.
     pub fn push_str(&mut self, string: &str) {
         unsafe {
             yaslapi_sys::YASL_pushlstr(self.state.as_ptr(), string.as_ptr().cast(), string.len());
         }
+        self.grow_stack(1);
     }
     /// Pushes an `undef` value onto the stack.
     pub fn push_undef(&mut self) {
         unsafe { yaslapi_sys::YASL_pushundef(self.state.as_ptr()) }
+        self.grow_stack(1);
     }
     /// Pushes user-data onto the stack as a pointer with a unique tag and destructor for this type.
     /// # Safety
     /// Rust cannot make safety guarantees about data that is being pointed to in YASL.
+    /// The data must also be safe to send to another thread, since a `State` may be moved
+    /// to another thread with its values, e.g., by a `pool::StatePool`.
     pub unsafe fn push_userdata(
         &mut self,
         data: Option<NonNull<c_void>>,
@@ -845,10 +1574,12 @@ impl State {
                 destructor,
             );
         }
+        self.grow_stack(1);
     }
     /// Pushes user-data onto the stack as a `Box` along with a unique tag and `Box` destructor.
     /// This allows YASL to safely take ownership of the data and free it when it is no longer needed.
-    pub fn push_userdata_box<T>(&mut self, data: T, tag: &'static CStr) {
+    /// The data must be `Send`, since it is owned by the state, which may be moved to another thread.
+    pub fn push_userdata_box<T: Send>(&mut self, data: T, tag: &'static CStr) {
         /// A helper function for dropping a `Box` of type `T` safely from YASL.
         unsafe extern "C" fn box_drop<Q>(_: *mut YASL_State, data: *mut c_void) {
             unsafe {
@@ -878,10 +1609,12 @@ impl State {
                 userptr.map_or(std::ptr::null_mut(), NonNull::as_ptr),
             );
         }
+        self.grow_stack(1);
     }
     /// Pushes a nul-terminated string onto the stack. YASL makes a copy of the given string, and manages the memory for it.
     pub fn push_zstr(&mut self, cstring: &CStr) {
         unsafe { yaslapi_sys::YASL_pushzstr(self.state.as_ptr(), cstring.as_ptr()) }
+        self.grow_stack(1);
     }
 
     /// Registers a new metatable with the label `name`. Afterwards, the metatable
@@ -889,31 +1622,18 @@ impl State {
     /// E.g., `set_mt(..)` and `load_mt(..)`.
     pub fn register_mt(&mut self, name: &'static CStr) {
         unsafe { yaslapi_sys::YASL_registermt(self.state.as_ptr(), name.as_ptr()) };
+        self.shrink_stack(1);
     }
     /// Registers a new metatable with the label `name`. Afterwards, the metatable
     /// can be referred to by `name` in other functions dealing with metatables.
     /// E.g., `set_mt(..)` and `load_mt(..)`.
-    /// The string `name` is copied as a new `CString` to a static `HashSet<_>` to provide
-    /// a valid C-string pointer for the lifetime of the program, as YASL requires.
+    /// The string `name` is copied to a `CString` which is kept alive as long as the state, as YASL requires.
     /// # Panics
     /// The string slice `name` must not contain internal zero bytes.
     pub fn register_mt_slice(&mut self, name: &str) {
-        let name = CString::new(name).unwrap();
-        let mut lifetime_strings = LIFETIME_CSTRINGS.lock().unwrap();
-
-        // Ensure that if the C-string is already in our map that we use the original pointer.
-        let existing_cstr = lifetime_strings.get(&name);
-        unsafe {
-            yaslapi_sys::YASL_registermt(
-                self.state.as_ptr(),
-                existing_cstr.unwrap_or(&name).as_ptr(),
-            )
-        };
-
-        if existing_cstr.is_none() {
-            // Prevent the C-string from being dropped.
-            lifetime_strings.insert(name);
-        }
+        let name = self.lifetime_cstr(CString::new(name).unwrap());
+        unsafe { yaslapi_sys::YASL_registermt(self.state.as_ptr(), name) };
+        self.shrink_stack(1);
     }
 
     /// Recreate the state machine from the given script path.
@@ -924,15 +1644,67 @@ impl State {
     /// The string slice `script_location` must not contain internal zero bytes.
     pub fn reset_from_script(&mut self, script_location: &str) -> Result<StateSuccess, StateError> {
         let script_location = CString::new(script_location).unwrap();
-        unsafe {
+        let result = unsafe {
             state_result(yaslapi_sys::YASL_resetstate(
                 self.state.as_ptr(),
                 script_location.as_ptr(),
             ))
+        };
+        if result.is_ok() {
+            self.origin = Some(Origin::Script(script_location));
+            if self.has_echo_handler() {
+                self.restore_origin();
+            }
         }
+        result
     }
     /// Recreate the state machine from the given source code.
     pub fn reset_from_source(&mut self, source: &str) {
+        self.reset_source(source);
+        self.origin = Some(Origin::Source(source.as_bytes().to_vec()));
+    }
+
+    /// Recreate the state machine from the given source code without replacing the main program.
+    pub(crate) fn reset_source(&mut self, source: &str) {
+        self.resolve_missing_globals(source);
+        self.load_source(source.as_bytes());
+    }
+
+    /// Recreate the state machine from its main program, if it is known.
+    pub(crate) fn restore_origin(&mut self) {
+        let origin = self.origin.take();
+        match &origin {
+            Some(Origin::Source(source)) => self.load_source(source),
+            Some(Origin::Script(script_location)) => {
+                // Scripts are read here instead of by YASL so that their `echo` statements can be rewritten.
+                let source = if self.has_echo_handler() {
+                    script_path(script_location).and_then(|path| std::fs::read(path).ok())
+                } else {
+                    None
+                };
+                match source {
+                    Some(source) => self.load_source(&source),
+                    // The script may have been removed since, in which case there is nothing to restore.
+                    None => unsafe {
+                        yaslapi_sys::YASL_resetstate(self.state.as_ptr(), script_location.as_ptr());
+                    },
+                }
+            }
+            None => {}
+        }
+        self.origin = origin;
+    }
+
+    /// Recreate the state machine from the given source code, with its `echo` statements rewritten if there is an echo handler.
+    fn load_source(&mut self, source: &[u8]) {
+        let rewritten = if self.has_echo_handler() {
+            std::str::from_utf8(source)
+                .ok()
+                .and_then(|source| syntax::rewrite_echo(source, echo::ECHO_GLOBAL))
+        } else {
+            None
+        };
+        let source = rewritten.as_ref().map_or(source, String::as_bytes);
         unsafe {
             yaslapi_sys::YASL_resetstate_bb(
                 self.state.as_ptr(),
@@ -947,28 +1719,33 @@ impl State {
     /// # Errors
     /// If the global does not exist or is `const` then it will return `StateError::Generic`.
     pub fn set_global(&mut self, name: &CStr) -> Result<StateSuccess, StateError> {
-        unsafe {
+        let result = unsafe {
             state_result(yaslapi_sys::YASL_setglobal(
                 self.state.as_ptr(),
                 name.as_ptr(),
             ))
+        };
+        if result.is_ok() {
+            self.shrink_stack(1);
         }
+        result
     }
     /// Pops the top of the YASL stack and stores it in the given global.
     /// The string `name` is copied to a `CString` before being given to the YASL runtime.
+    /// The `CString` is kept alive as long as the state, since YASL keeps a pointer to the name.
     /// Returns `StateSuccess::Generic` if successful.
     /// # Errors
     /// If the global does not exist or is `const` then it will return `StateError::Generic`.
     /// # Panics
     /// The string slice `name` must not contain internal zero bytes.
     pub fn set_global_slice(&mut self, name: &str) -> Result<StateSuccess, StateError> {
-        let name = CString::new(name).unwrap();
-        unsafe {
-            state_result(yaslapi_sys::YASL_setglobal(
-                self.state.as_ptr(),
-                name.as_ptr(),
-            ))
+        let name = self.lifetime_cstr(CString::new(name).unwrap());
+        let result =
+            unsafe { state_result(yaslapi_sys::YASL_setglobal(self.state.as_ptr(), name)) };
+        if result.is_ok() {
+            self.shrink_stack(1);
         }
+        result
     }
 
     // TODO: Learn what the exact API here is.
@@ -978,14 +1755,27 @@ impl State {
     /// The next object on the stack must be either a `UserData`, `Table`, and `List`
     /// or it will return `StateError::TypeError`.
     pub fn set_mt(&mut self) -> Result<StateSuccess, StateError> {
+        // A metatable is popped before checking the object below it, while `undef` is never popped.
+        if self.is_table() {
+            self.shrink_stack(1);
+        }
         unsafe { state_result(yaslapi_sys::YASL_setmt(self.state.as_ptr())) }
     }
 
-    // TODO: Learn if these should be added.
-    // void YASL_setprintout_tostr(struct YASL_State *S);
-    // void YASL_setprinterr_tostr(struct YASL_State *S);
+    /// Capture the state's standard output, e.g., from `echo`, in a string instead of printing it.
+    /// The captured output can be pushed with `load_printout`.
+    pub fn set_printout_tostr(&mut self) {
+        unsafe { yaslapi_sys::YASL_setprintout_tostr(self.state.as_ptr()) }
+    }
+    /// Capture the state's error output, e.g., syntax and runtime errors, in a string instead of printing it.
+    /// The captured output can be pushed with `load_printerr`.
+    pub fn set_printerr_tostr(&mut self) {
+        unsafe { yaslapi_sys::YASL_setprinterr_tostr(self.state.as_ptr()) }
+    }
 
-    // TODO: Learn what the exact API here is.
+    /// Replaces the top of the stack with its string representation, as YASL's `tostr` would give,
+    /// e.g., by calling the `tostr` method of a table's metatable.
+    /// Errors raised while stringifying, e.g., by a `tostr` method, are thrown to the YASL VM rather than returned.
     pub fn stringify_top(&mut self) {
         unsafe { yaslapi_sys::YASL_stringifytop(self.state.as_ptr()) }
     }
@@ -996,7 +1786,12 @@ impl State {
     /// if we are already at the end of the table.
     /// Returns `true` if the next index and value were pushed, `false` otherwise.
     pub fn table_next(&mut self) -> bool {
-        unsafe { yaslapi_sys::YASL_tablenext(self.state.as_ptr()) }
+        let pushed = unsafe { yaslapi_sys::YASL_tablenext(self.state.as_ptr()) };
+        self.shrink_stack(1);
+        if pushed {
+            self.grow_stack(2);
+        }
+        pushed
     }
 
     /// Inserts a key-value pair into the table. The top-most items are the value, then key,
@@ -1006,6 +1801,8 @@ impl State {
     /// If the object third from the top of the stack is not a table then it will return `StateError::TypeError`.
     /// If the key is of a type that cannot be hashed (e.g., `List`, `Table`, and `UserData`) then it will return `StateError::TypeError`.
     pub fn table_set(&mut self) -> Result<StateSuccess, StateError> {
+        // The key and value are popped even if they can't be inserted.
+        self.shrink_stack(2);
         unsafe { state_result(yaslapi_sys::YASL_tableset(self.state.as_ptr())) }
     }
 
@@ -1051,14 +1848,45 @@ impl TryFrom<*mut YASL_State> for State {
     }
 }
 
+/// A `State` borrowed from the YASL VM for the duration of a callback, e.g., in a function defined with `new_cfn!`.
+/// Dereferences to `State`. Unlike a `State` created with `from_memory`, it borrows the pointer it was created from,
+/// so it can't be kept after the callback returns, when YASL may delete the underlying state.
+/// Like a `State` created from a raw pointer, the underlying state is never deleted by the wrapper.
+pub struct StateRef<'a> {
+    state: State,
+    callback: PhantomData<&'a mut YASL_State>,
+}
+
+impl<'a> StateRef<'a> {
+    /// Borrow the state behind the pointer given to a callback, for as long as the pointer itself is borrowed.
+    /// Returns `None` if the pointer is null.
+    #[must_use]
+    pub fn new(state: &'a mut *mut YASL_State) -> Option<Self> {
+        State::from_memory(*state).map(|state| Self {
+            state,
+            callback: PhantomData,
+        })
+    }
+}
+
+impl Deref for StateRef<'_> {
+    type Target = State;
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
+impl DerefMut for StateRef<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.state
+    }
+}
+
 // Unsafe helper for converting from an integer to a safe YASL `Result`.
 unsafe fn state_result(r: i32) -> Result<StateSuccess, StateError> {
     match num::FromPrimitive::from_i32(r) {
         Some(s) => Ok(s),
-        None => match num::FromPrimitive::from_i32(r) {
-            Some(e) => Err(e),
-            None => panic!("Unknown error was returned: {r:?}"),
-        },
+        None => Err(r.into()),
     }
 }
 
@@ -1069,26 +1897,109 @@ impl From<StateSuccess> for i32 {
     }
 }
 
+/// Convert from a YASL result code to a `StateError`. Codes which this crate doesn't know as errors,
+/// including the codes of `StateSuccess`, give `StateError::Unknown`.
+impl From<i32> for StateError {
+    fn from(r: i32) -> Self {
+        STATE_ERROR_CODES
+            .iter()
+            .find(|&&(_, code)| code == r)
+            .map_or(Self::Unknown(r), |&(e, _)| e)
+    }
+}
+
 /// Convert from a YASL `StateError` enum to the underlying integer.
 impl From<StateError> for i32 {
     fn from(s: StateError) -> Self {
-        s as Self
+        match s {
+            StateError::Unknown(code) => code,
+            s => STATE_ERROR_CODES
+                .iter()
+                .find(|&&(e, _)| e == s)
+                .map_or(0, |&(_, code)| code),
+        }
     }
 }
 
-/// Safely convert from an integer to a YASL `Type`.
+/// Safely convert from an integer to a YASL `Type`. Codes which this crate doesn't know give `Type::Unknown`.
 impl From<i32> for Type {
     fn from(t: i32) -> Self {
-        match num::FromPrimitive::from_i32(t) {
-            Some(r) => r,
-            None => panic!("Unknown type was returned: {t:?}"),
-        }
+        TYPE_CODES
+            .iter()
+            .find(|&&(_, code)| code == t)
+            .map_or(Self::Unknown(t), |&(r, _)| r)
     }
 }
 
 /// Convert from a YASL `Type` to the underlying integer.
 impl From<Type> for i32 {
     fn from(t: Type) -> Self {
-        t as Self
+        match t {
+            Type::Unknown(code) => code,
+            t => TYPE_CODES
+                .iter()
+                .find(|&&(r, _)| r == t)
+                .map_or(0, |&(_, code)| code),
+        }
+    }
+}
+
+impl Type {
+    /// The name YASL uses for values of this type, e.g., in error messages. Functions, closures,
+    /// and C functions are all named `fn`.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Undef => "undef",
+            Self::Float => "float",
+            Self::Int => "int",
+            Self::Bool => "bool",
+            Self::Str => "str",
+            Self::List => "list",
+            Self::Table => "table",
+            Self::Fn | Self::Closure | Self::CFn => "fn",
+            Self::UserPtr => "userptr",
+            Self::UserData => "userdata",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+}
+
+/// Formats the type with its YASL name.
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Error returned when parsing a string which isn't the name of a YASL type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseTypeError;
+
+impl Display for ParseTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not the name of a YASL type")
+    }
+}
+
+impl std::error::Error for ParseTypeError {}
+
+/// Parse a YASL type name, as given by `Type::name`. Since all kinds of functions share a name, `fn` gives `Type::Fn`.
+impl FromStr for Type {
+    type Err = ParseTypeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "undef" => Self::Undef,
+            "float" => Self::Float,
+            "int" => Self::Int,
+            "bool" => Self::Bool,
+            "str" => Self::Str,
+            "list" => Self::List,
+            "table" => Self::Table,
+            "fn" => Self::Fn,
+            "userptr" => Self::UserPtr,
+            "userdata" => Self::UserData,
+            _ => return Err(ParseTypeError),
+        })
     }
 }
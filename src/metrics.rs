@@ -0,0 +1,146 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Counters of the work done by a state, e.g., to export to a monitoring system.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    ffi::CStr,
+    time::{Duration, Instant},
+};
+
+use crate::{modules::with_hidden_userdata, State, StateError, StateSuccess};
+
+/// Name of the hidden global which stores the counters.
+const METRICS_GLOBAL: &str = "__yaslapi_metrics";
+static METRICS_TAG: &CStr = c"yaslapi.metrics";
+
+// TODO: Counters of the memory used by scripts (e.g., allocated bytes and live objects). YASL frees values by
+// reference counting as soon as they are unused, so there is no collection for a host to schedule or force,
+// but it allocates with `malloc` directly, so counting allocations needs upstream support for a custom allocator.
+
+/// Counters of the work done by a state since `State::enable_metrics` or `State::reset_metrics` was called.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// The number of times the state was executed, including by `eval` and `run_chunk`.
+    pub executions: u64,
+    /// The number of executions which failed, by the error they returned.
+    pub errors: HashMap<StateError, u64>,
+    /// The number of calls of host functions which report errors through `aux::run_host_fn`,
+    /// including the functions of this crate's libraries. Other C functions are not counted.
+    pub host_calls: u64,
+    /// The total time spent executing, including the time spent in host functions.
+    pub vm_time: Duration,
+}
+
+impl Metrics {
+    /// The number of executions which failed, with any error.
+    #[must_use]
+    pub fn error_count(&self) -> u64 {
+        self.errors.values().sum()
+    }
+}
+
+thread_local! {
+    /// Whether the state which is executing on this thread counts its host calls, so that host functions
+    /// only look up the counters while they are enabled.
+    static COUNT_HOST_CALLS: Cell<bool> = const { Cell::new(false) };
+}
+
+impl State {
+    /// Start counting the work done by this state. Metrics are off by default, so that states which
+    /// don't export them don't pay for counting each execution and host call.
+    /// The counters are stored in the state, so a `State` created from a raw pointer
+    /// (e.g., inside a host function) counts into the same counters as the one which enabled them.
+    #[allow(clippy::missing_panics_doc)] // The metrics global is a valid identifier.
+    pub fn enable_metrics(&mut self) {
+        if !has_metrics(self) {
+            self.push_userdata_box(Metrics::default(), METRICS_TAG);
+            self.init_global_slice(METRICS_GLOBAL)
+                .expect("Internal Error: The metrics global is a valid identifier.");
+        }
+        self.metrics_enabled = true;
+    }
+
+    /// Get a copy of the counters of the work done by this state, which are all zero if metrics were not enabled.
+    #[must_use]
+    pub fn metrics(&mut self) -> Metrics {
+        with_metrics(self, |metrics| metrics.clone()).unwrap_or_default()
+    }
+
+    /// Set all of the counters of this state back to zero.
+    pub fn reset_metrics(&mut self) {
+        with_metrics(self, |metrics| {
+            *metrics = Metrics::default();
+        });
+    }
+
+    /// Run the VM with `f`, counting the execution, its result, and the time it took if metrics are enabled.
+    /// A `State` created from a raw pointer doesn't know whether they are, so it checks for the counters instead.
+    pub(crate) fn record_execution(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<StateSuccess, StateError>,
+    ) -> Result<StateSuccess, StateError> {
+        let enabled = self.metrics_enabled || (!self.owns_state && has_metrics(self));
+        let count_host_calls = COUNT_HOST_CALLS.replace(enabled);
+        let start = Instant::now();
+        let result = f(self);
+        let elapsed = start.elapsed();
+        COUNT_HOST_CALLS.set(count_host_calls);
+
+        if enabled {
+            with_metrics(self, |metrics| {
+                metrics.executions += 1;
+                metrics.vm_time += elapsed;
+                if let Err(error) = &result {
+                    *metrics.errors.entry(*error).or_default() += 1;
+                }
+            });
+        }
+        result
+    }
+}
+
+/// Count a call of a host function, if the executing state counts them.
+pub(crate) fn record_host_call(state: &mut State) {
+    if !COUNT_HOST_CALLS.get() {
+        return;
+    }
+    with_metrics(state, |metrics| {
+        metrics.host_calls += 1;
+    });
+}
+
+/// Check whether the state's counters exist.
+fn has_metrics(state: &mut State) -> bool {
+    state.load_global_slice(METRICS_GLOBAL).is_ok() && {
+        let is_metrics = state.is_userdata(METRICS_TAG);
+        state.pop();
+        is_metrics
+    }
+}
+
+/// Run `f` with the state's counters, if they exist.
+fn with_metrics<R>(state: &mut State, f: impl FnOnce(&mut Metrics) -> R) -> Option<R> {
+    with_hidden_userdata(state, METRICS_GLOBAL, METRICS_TAG, f).ok()
+}
@@ -0,0 +1,199 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `csv` library, which converts between CSV text and lists of tables:
+//! - `csv.parse(text)`: Parse CSV text whose first row names the columns, and return a table for each other row.
+//!   Fields are always strings, and every row has a key for each column.
+//! - `csv.stringify(rows, columns)`: Write a list of tables as CSV text, with a header row naming the columns.
+//!   The optional `columns` list sets the names and order of the columns, which are otherwise all keys of the rows,
+//!   in sorted order. Missing and `undef` values are written as empty fields.
+
+use std::{collections::BTreeSet, os::raw::c_int};
+
+use yaslapi_sys::YASL_State;
+
+use super::{pop_str_arg, run_lib_fn};
+use crate::{
+    aux::{HashableObject, MetatableFunction, Object, ScriptError},
+    State, StateError, Type,
+};
+
+/// Declare the `csv` library as a global of the state.
+#[allow(clippy::missing_panics_doc)] // `csv` is a valid identifier.
+pub fn declare(state: &mut State) {
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("parse", csv_parse, 1),
+        MetatableFunction::new("stringify", csv_stringify, 2),
+    ]);
+    state
+        .init_global_slice("csv")
+        .expect("Internal Error: `csv` is a valid identifier.");
+}
+
+/// Parse the CSV text into a table for each row after the header.
+fn parse(text: &str) -> Result<Vec<Object>, csv::Error> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let columns = reader
+        .headers()?
+        .iter()
+        .map(|name| HashableObject::Str(name.to_owned()))
+        .collect::<Vec<_>>();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            Ok(Object::Table(
+                columns
+                    .iter()
+                    .cloned()
+                    .zip(record.iter().map(|field| Object::Str(field.to_owned())))
+                    .collect(),
+            ))
+        })
+        .collect()
+}
+
+/// Write the rows as CSV text, with the given columns or else all keys of the rows.
+fn stringify(
+    fn_name: &str,
+    rows: &[Object],
+    columns: Option<Vec<HashableObject>>,
+) -> Result<String, ScriptError> {
+    let type_error = |expected: &str| {
+        ScriptError::new(
+            StateError::TypeError,
+            format!("TypeError: {fn_name} expected {expected}."),
+        )
+    };
+
+    let rows = rows
+        .iter()
+        .map(|row| match row {
+            Object::Table(table) => Ok(table),
+            _ => Err(type_error("rows to be a list of tables")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let columns = columns.unwrap_or_else(|| {
+        rows.iter()
+            .flat_map(|row| row.keys().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    });
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let write_error = |e: csv::Error| {
+        ScriptError::new(
+            StateError::Generic,
+            format!("Error: {fn_name} failed: {e}."),
+        )
+    };
+    writer
+        .write_record(
+            columns
+                .iter()
+                .map(|column| Object::from(column.clone()).to_yasl_string()),
+        )
+        .map_err(write_error)?;
+    for row in rows {
+        let fields = columns
+            .iter()
+            .map(|column| match row.get(column) {
+                None | Some(Object::Undef) => Ok(String::new()),
+                Some(
+                    Object::List(_)
+                    | Object::Table(_)
+                    | Object::UserData { .. }
+                    | Object::UserPtr(_),
+                ) => Err(type_error("fields to be str, int, float, bool, or undef")),
+                Some(value) => Ok(value.to_yasl_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        writer.write_record(fields).map_err(write_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| write_error(e.into_error().into()))?;
+    Ok(String::from_utf8(bytes).expect("Internal Error: CSV of strings is not valid UTF-8."))
+}
+
+/// Implements `csv.parse(text)`.
+unsafe extern "C" fn csv_parse(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let text = pop_str_arg(state, "csv.parse", 0)?;
+        let rows = parse(&text).map_err(|e| {
+            ScriptError::new(
+                StateError::ValueError,
+                format!("ValueError: csv.parse failed: {e}."),
+            )
+        })?;
+        state.push_object(&Object::List(rows)).map_err(|_| {
+            ScriptError::new(
+                StateError::Generic,
+                "Error: csv.parse failed to push the rows.",
+            )
+        })?;
+        Ok(1)
+    })
+}
+
+/// Implements `csv.stringify(rows, columns)`.
+unsafe extern "C" fn csv_stringify(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let columns = match state.peek_type() {
+            Type::Undef => {
+                state.pop();
+                None
+            }
+            Type::List => match state.pop_object(None) {
+                Ok(Object::List(columns)) => Some(
+                    columns
+                        .into_iter()
+                        .map(|column| match column {
+                            Object::Str(name) => Ok(HashableObject::Str(name)),
+                            _ => Err(ScriptError::new(
+                                StateError::TypeError,
+                                "TypeError: csv.stringify expected columns to be a list of str.",
+                            )),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                _ => return Err(ScriptError::bad_arg_type(state, "csv.stringify", 1, "list")),
+            },
+            _ => return Err(ScriptError::bad_arg_type(state, "csv.stringify", 1, "list")),
+        };
+
+        if !state.is_list() {
+            return Err(ScriptError::bad_arg_type(state, "csv.stringify", 0, "list"));
+        }
+        let Ok(Object::List(rows)) = state.pop_object(None) else {
+            return Err(ScriptError::bad_arg_type(state, "csv.stringify", 0, "list"));
+        };
+
+        let text = stringify("csv.stringify", &rows, columns)?;
+        state.push_str(&text);
+        Ok(1)
+    })
+}
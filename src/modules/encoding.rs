@@ -0,0 +1,183 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `encoding` library, which converts strings of arbitrary bytes to and from text:
+//! - `encoding.base64_encode(s)`: Encode `s` with the standard base64 alphabet, with padding.
+//! - `encoding.base64_decode(s)`: Decode padded, standard base64 to a string of bytes.
+//! - `encoding.hex_encode(s)`: Encode each byte of `s` as two lowercase hex digits.
+//! - `encoding.hex_decode(s)`: Decode pairs of hex digits, of either case, to a string of bytes.
+//!
+//! Decoding invalid input is a `ValueError`.
+
+use std::{fmt::Write, os::raw::c_int};
+
+use yaslapi_sys::YASL_State;
+
+use super::{pop_bytes_arg, push_bytes, run_lib_fn};
+use crate::{
+    aux::{MetatableFunction, ScriptError},
+    State, StateError,
+};
+
+/// The standard base64 alphabet.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Declare the `encoding` library as a global of the state.
+#[allow(clippy::missing_panics_doc)] // `encoding` is a valid identifier.
+pub fn declare(state: &mut State) {
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("base64_encode", encoding_base64_encode, 1),
+        MetatableFunction::new("base64_decode", encoding_base64_decode, 1),
+        MetatableFunction::new("hex_encode", encoding_hex_encode, 1),
+        MetatableFunction::new("hex_decode", encoding_hex_decode, 1),
+    ]);
+    state
+        .init_global_slice("encoding")
+        .expect("Internal Error: `encoding` is a valid identifier.");
+}
+
+/// Encode the bytes as lowercase hex digits.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(hex, "{b:02x}");
+    }
+    hex
+}
+
+/// Decode pairs of hex digits, or return `None` if the input has an odd length or a non-hex character.
+fn from_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.chunks_exact(2)
+        .map(|pair| {
+            let digit = |c: u8| char::from(c).to_digit(16);
+            #[allow(clippy::cast_possible_truncation)] // Two hex digits fit in a byte.
+            Some((digit(pair[0])? * 16 + digit(pair[1])?) as u8)
+        })
+        .collect()
+}
+
+/// Encode the bytes as padded, standard base64.
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+
+        // Each byte of input contributes to one more output character, and the rest are padding.
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(
+                    BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize],
+                ));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode padded, standard base64, or return `None` if the input is not valid.
+fn from_base64(text: &[u8]) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let chunks = text.len() / 4;
+    for (index, chunk) in text.chunks_exact(4).enumerate() {
+        // Padding is only allowed at the end of the last chunk.
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && index + 1 < chunks) {
+            return None;
+        }
+
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|&a| a == c)?;
+            #[allow(clippy::cast_possible_truncation)]
+            // Positions in the alphabet are less than 64.
+            let value = value as u32;
+            n = n << 6 | value;
+        }
+        n <<= 6 * padding;
+
+        #[allow(clippy::cast_possible_truncation)] // Each byte is masked by the shift.
+        out.extend_from_slice(&[(n >> 16) as u8, (n >> 8) as u8, n as u8][..3 - padding]);
+    }
+    Some(out)
+}
+
+/// Pop the string argument of `fn_name`, and push it decoded, or raise a `ValueError` if it isn't valid.
+fn push_decoded(
+    state: *mut YASL_State,
+    fn_name: &str,
+    format: &str,
+    decode: fn(&[u8]) -> Option<Vec<u8>>,
+) -> c_int {
+    run_lib_fn(state, |state| {
+        let text = pop_bytes_arg(state, fn_name, 0)?;
+        let bytes = decode(&text).ok_or_else(|| {
+            ScriptError::new(
+                StateError::ValueError,
+                format!("ValueError: {fn_name} expected valid {format}."),
+            )
+        })?;
+        push_bytes(state, &bytes);
+        Ok(1)
+    })
+}
+
+/// Implements `encoding.base64_encode(s)`.
+unsafe extern "C" fn encoding_base64_encode(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let bytes = pop_bytes_arg(state, "encoding.base64_encode", 0)?;
+        state.push_str(&to_base64(&bytes));
+        Ok(1)
+    })
+}
+
+/// Implements `encoding.base64_decode(s)`.
+unsafe extern "C" fn encoding_base64_decode(state: *mut YASL_State) -> c_int {
+    push_decoded(state, "encoding.base64_decode", "base64", from_base64)
+}
+
+/// Implements `encoding.hex_encode(s)`.
+unsafe extern "C" fn encoding_hex_encode(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let bytes = pop_bytes_arg(state, "encoding.hex_encode", 0)?;
+        state.push_str(&to_hex(&bytes));
+        Ok(1)
+    })
+}
+
+/// Implements `encoding.hex_decode(s)`.
+unsafe extern "C" fn encoding_hex_decode(state: *mut YASL_State) -> c_int {
+    push_decoded(state, "encoding.hex_decode", "hex", from_hex)
+}
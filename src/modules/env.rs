@@ -0,0 +1,101 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `env` library, which gives scripts read access to the host process's environment:
+//! - `env.get(name)`: The value of an environment variable, or `undef` if it is unset or not valid Unicode.
+//! - `env.vars()`: A table of all environment variables which are valid Unicode.
+//! - `env.cwd()`: The current working directory, or `undef` if it is unavailable.
+//! - `env.args`: A list of the process's command-line arguments.
+//! - `env.platform`: The name of the operating system, e.g., `linux` or `windows`.
+
+use std::os::raw::c_int;
+
+use yaslapi_sys::YASL_State;
+
+use super::{pop_str_arg, run_lib_fn, table_set_str};
+use crate::{aux::MetatableFunction, State};
+
+/// Declare the `env` library as a global of the state.
+/// Scripts can read the host's environment variables and working directory through it,
+/// so it should not be declared for untrusted scripts.
+#[allow(clippy::missing_panics_doc)] // Setting keys of a new table and initializing `env` can't fail.
+pub fn declare(state: &mut State) {
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("get", env_get, 1),
+        MetatableFunction::new("vars", env_vars, 0),
+        MetatableFunction::new("cwd", env_cwd, 0),
+    ]);
+    table_set_str(state, "platform", std::env::consts::OS);
+
+    state.push_str("args");
+    state.push_list();
+    for arg in std::env::args_os() {
+        state.push_str(&arg.to_string_lossy());
+        state
+            .list_push()
+            .expect("Internal Error: Unable to push to a new list.");
+    }
+    state
+        .table_set()
+        .expect("Internal Error: Unable to set a string key of a new table.");
+
+    state
+        .init_global_slice("env")
+        .expect("Internal Error: `env` is a valid identifier.");
+}
+
+/// Implements `env.get(name)`.
+unsafe extern "C" fn env_get(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let name = pop_str_arg(state, "env.get", 0)?;
+        match std::env::var(name) {
+            Ok(value) => state.push_str(&value),
+            Err(_) => state.push_undef(),
+        }
+        Ok(1)
+    })
+}
+
+/// Implements `env.vars()`.
+unsafe extern "C" fn env_vars(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        state.push_table();
+        for (key, value) in std::env::vars_os() {
+            if let (Some(key), Some(value)) = (key.to_str(), value.to_str()) {
+                table_set_str(state, key, value);
+            }
+        }
+        Ok(1)
+    })
+}
+
+/// Implements `env.cwd()`.
+unsafe extern "C" fn env_cwd(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        match std::env::current_dir() {
+            Ok(dir) => state.push_str(&dir.to_string_lossy()),
+            Err(_) => state.push_undef(),
+        }
+        Ok(1)
+    })
+}
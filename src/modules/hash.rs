@@ -0,0 +1,83 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `hash` library, which computes checksums of strings, including strings of arbitrary bytes:
+//! - `hash.sha256(s)`, `hash.sha1(s)`, `hash.md5(s)`: The digest of `s` as a lowercase hex string.
+//! - `hash.crc32(s)`: The CRC-32 checksum of `s` as a non-negative int.
+
+use std::os::raw::c_int;
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use yaslapi_sys::YASL_State;
+
+use super::{encoding::to_hex, pop_bytes_arg, run_lib_fn};
+use crate::{aux::MetatableFunction, State};
+
+/// Declare the `hash` library as a global of the state.
+#[allow(clippy::missing_panics_doc)] // `hash` is a valid identifier.
+pub fn declare(state: &mut State) {
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("sha256", hash_sha256, 1),
+        MetatableFunction::new("sha1", hash_sha1, 1),
+        MetatableFunction::new("md5", hash_md5, 1),
+        MetatableFunction::new("crc32", hash_crc32, 1),
+    ]);
+    state
+        .init_global_slice("hash")
+        .expect("Internal Error: `hash` is a valid identifier.");
+}
+
+/// Pop the string argument of `fn_name`, and push its digest as a hex string.
+fn push_digest<D: Digest>(state: *mut YASL_State, fn_name: &str) -> c_int {
+    run_lib_fn(state, |state| {
+        let bytes = pop_bytes_arg(state, fn_name, 0)?;
+        state.push_str(&to_hex(&D::digest(bytes)));
+        Ok(1)
+    })
+}
+
+/// Implements `hash.sha256(s)`.
+unsafe extern "C" fn hash_sha256(state: *mut YASL_State) -> c_int {
+    push_digest::<Sha256>(state, "hash.sha256")
+}
+
+/// Implements `hash.sha1(s)`.
+unsafe extern "C" fn hash_sha1(state: *mut YASL_State) -> c_int {
+    push_digest::<Sha1>(state, "hash.sha1")
+}
+
+/// Implements `hash.md5(s)`.
+unsafe extern "C" fn hash_md5(state: *mut YASL_State) -> c_int {
+    push_digest::<Md5>(state, "hash.md5")
+}
+
+/// Implements `hash.crc32(s)`.
+unsafe extern "C" fn hash_crc32(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let bytes = pop_bytes_arg(state, "hash.crc32", 0)?;
+        state.push_int(crc32fast::hash(&bytes).into());
+        Ok(1)
+    })
+}
@@ -0,0 +1,216 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `http` library, which lets scripts make blocking HTTP requests to the hosts allowed by an `HttpConfig`:
+//! - `http.get(url)`: Send a `GET` request.
+//! - `http.post(url, body)`: Send a `POST` request with a string body.
+//!
+//! Both return a table with the response's `status` code, `body` string, and `headers` table with lowercase names.
+//! Responses with error statuses are returned normally, while disallowed hosts and failed connections are runtime errors.
+
+use std::{ffi::CStr, os::raw::c_int, time::Duration};
+
+use yaslapi_sys::YASL_State;
+
+use super::{pop_str_arg, run_lib_fn, table_set_str, with_hidden_userdata};
+use crate::{
+    aux::{MetatableFunction, ScriptError},
+    State, StateError,
+};
+
+/// Name of the hidden global holding the client.
+const CLIENT_GLOBAL: &str = "__yaslapi_http";
+
+/// Tag of the client's user data, which YASL compares by address.
+static CLIENT_TAG: &CStr = c"yaslapi.http";
+
+/// Which hosts scripts may send requests to, and how long each request may take.
+#[derive(Clone, Debug)]
+pub struct HttpConfig {
+    allowed_hosts: Vec<String>,
+    timeout: Duration,
+}
+
+/// The client used by a state's `http` library.
+struct Client {
+    agent: ureq::Agent,
+    allowed_hosts: Vec<String>,
+}
+
+impl HttpConfig {
+    /// Create a configuration which doesn't allow any hosts, with a timeout of 30 seconds per request.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Allow requests to the given host name or IP address, e.g., `api.example.com`. Subdomains are not included.
+    #[must_use]
+    pub fn allow_host(mut self, host: impl Into<String>) -> Self {
+        self.allowed_hosts.push(host.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Set the maximum time for each request, from connecting to reading the whole response.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Declare the `http` library as a global of the state, allowing the hosts in `config`.
+/// Redirects are not followed, since they could lead to a host which isn't allowed.
+#[allow(clippy::missing_panics_doc)] // The library's global names are valid identifiers.
+pub fn declare(state: &mut State, config: HttpConfig) {
+    let client = Client {
+        agent: ureq::AgentBuilder::new()
+            .timeout(config.timeout)
+            .redirects(0)
+            .build(),
+        allowed_hosts: config.allowed_hosts,
+    };
+    state.push_userdata_box(client, CLIENT_TAG);
+    state
+        .init_global_slice(CLIENT_GLOBAL)
+        .expect("Internal Error: The client's global is a valid identifier.");
+
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("get", http_get, 1),
+        MetatableFunction::new("post", http_post, 2),
+    ]);
+    state
+        .init_global_slice("http")
+        .expect("Internal Error: `http` is a valid identifier.");
+}
+
+impl Client {
+    /// Send a request to the URL if its host is allowed, with `body` if it is given.
+    fn send(
+        &self,
+        fn_name: &str,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+    ) -> Result<ureq::Response, ScriptError> {
+        let request = self.agent.request(method, url);
+        let host = request
+            .request_url()
+            .map_err(|e| {
+                ScriptError::new(
+                    StateError::ValueError,
+                    format!("ValueError: {fn_name} got an invalid URL: {e}."),
+                )
+            })?
+            .host()
+            .to_ascii_lowercase();
+        if !self.allowed_hosts.contains(&host) {
+            return Err(ScriptError::new(
+                StateError::ValueError,
+                format!("ValueError: {fn_name} is not allowed to access host {host}."),
+            ));
+        }
+
+        let result = match body {
+            Some(body) => request.send_string(body),
+            None => request.call(),
+        };
+        match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => Ok(response),
+            Err(e) => Err(ScriptError::new(
+                StateError::Generic,
+                format!("Error: {fn_name} failed: {e}."),
+            )),
+        }
+    }
+}
+
+/// Push a table describing the response.
+fn push_response(
+    state: &mut State,
+    fn_name: &str,
+    response: ureq::Response,
+) -> Result<(), ScriptError> {
+    state.push_table();
+    state.push_str("status");
+    state.push_int(response.status().into());
+    state
+        .table_set()
+        .expect("Internal Error: Unable to set a string key of a new table.");
+
+    state.push_str("headers");
+    state.push_table();
+    for name in response.headers_names() {
+        if let Some(value) = response.header(&name) {
+            table_set_str(state, &name, value);
+        }
+    }
+    state
+        .table_set()
+        .expect("Internal Error: Unable to set a string key of a new table.");
+
+    let body = response.into_string().map_err(|e| {
+        ScriptError::new(
+            StateError::Generic,
+            format!("Error: {fn_name} failed to read the response: {e}."),
+        )
+    })?;
+    table_set_str(state, "body", &body);
+    Ok(())
+}
+
+/// Implements `http.get(url)`.
+unsafe extern "C" fn http_get(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let url = pop_str_arg(state, "http.get", 0)?;
+        let response =
+            with_hidden_userdata(state, CLIENT_GLOBAL, CLIENT_TAG, |client: &mut Client| {
+                client.send("http.get", "GET", &url, None)
+            })??;
+        push_response(state, "http.get", response)?;
+        Ok(1)
+    })
+}
+
+/// Implements `http.post(url, body)`.
+unsafe extern "C" fn http_post(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let body = pop_str_arg(state, "http.post", 1)?;
+        let url = pop_str_arg(state, "http.post", 0)?;
+        let response =
+            with_hidden_userdata(state, CLIENT_GLOBAL, CLIENT_TAG, |client: &mut Client| {
+                client.send("http.post", "POST", &url, Some(&body))
+            })??;
+        push_response(state, "http.post", response)?;
+        Ok(1)
+    })
+}
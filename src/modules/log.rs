@@ -0,0 +1,93 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `log` library, which forwards messages from scripts to the `log` crate's logger:
+//! - `log.error(value)`, `log.warn(value)`, `log.info(value)`, `log.debug(value)`: Log the value, converted to a
+//!   string as `echo` would, at the matching level.
+//!
+//! Records use the target given to `declare`, e.g., the script's name, so the host can filter them by script.
+
+use std::{ffi::CStr, os::raw::c_int};
+
+use log::Level;
+use yaslapi_sys::YASL_State;
+
+use super::{pop_bytes_arg, run_lib_fn, with_hidden_userdata};
+use crate::{aux::MetatableFunction, State};
+
+/// Name of the hidden global holding the target.
+const TARGET_GLOBAL: &str = "__yaslapi_log";
+
+/// Tag of the target's user data, which YASL compares by address.
+static TARGET_TAG: &CStr = c"yaslapi.log";
+
+/// Declare the `log` library as a global of the state, logging records with the given target.
+#[allow(clippy::missing_panics_doc)] // The library's global names are valid identifiers.
+pub fn declare(state: &mut State, target: impl Into<String>) {
+    state.push_userdata_box(target.into(), TARGET_TAG);
+    state
+        .init_global_slice(TARGET_GLOBAL)
+        .expect("Internal Error: The target's global is a valid identifier.");
+
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("error", log_error, 1),
+        MetatableFunction::new("warn", log_warn, 1),
+        MetatableFunction::new("info", log_info, 1),
+        MetatableFunction::new("debug", log_debug, 1),
+    ]);
+    state
+        .init_global_slice("log")
+        .expect("Internal Error: `log` is a valid identifier.");
+}
+
+/// Pop the argument of `fn_name` and log it at the given level.
+fn log_top(state: *mut YASL_State, fn_name: &str, level: Level) -> c_int {
+    run_lib_fn(state, |state| {
+        state.stringify_top();
+        let message = pop_bytes_arg(state, fn_name, 0)?;
+        with_hidden_userdata(state, TARGET_GLOBAL, TARGET_TAG, |target: &mut String| {
+            log::log!(target: target, level, "{}", String::from_utf8_lossy(&message));
+        })?;
+        Ok(0)
+    })
+}
+
+/// Implements `log.error(value)`.
+unsafe extern "C" fn log_error(state: *mut YASL_State) -> c_int {
+    log_top(state, "log.error", Level::Error)
+}
+
+/// Implements `log.warn(value)`.
+unsafe extern "C" fn log_warn(state: *mut YASL_State) -> c_int {
+    log_top(state, "log.warn", Level::Warn)
+}
+
+/// Implements `log.info(value)`.
+unsafe extern "C" fn log_info(state: *mut YASL_State) -> c_int {
+    log_top(state, "log.info", Level::Info)
+}
+
+/// Implements `log.debug(value)`.
+unsafe extern "C" fn log_debug(state: *mut YASL_State) -> c_int {
+    log_top(state, "log.debug", Level::Debug)
+}
@@ -0,0 +1,248 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Optional libraries implemented in Rust. Unlike YASL's standard libraries, which `State::declare_libs`
+//! declares all at once, scripts only get access to each of these when the host declares it explicitly.
+
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod encoding;
+pub mod env;
+// TODO: An `events` library through which scripts register handlers (e.g., `events.on(name, f)`) for events emitted by
+// the host, with each handler's runtime errors reported without affecting the others. The host would call handlers
+// after the script has executed, where `YASL_functioncall` has no protected mode and a runtime error jumps to the
+// handler of an execution which has already returned, so this needs YASL to support protected calls first.
+#[cfg(feature = "hash")]
+pub mod hash;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "log")]
+pub mod log;
+pub mod random;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod timer;
+
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_int,
+};
+
+use yaslapi_sys::YASL_State;
+
+use crate::{
+    aux::{Object, ScriptError},
+    State, StateError, Type,
+};
+
+/// Pop the string on top of the stack, which is argument `n` of the function `fn_name`.
+/// # Errors
+/// Returns a type error if the top of the stack is not a string.
+pub(crate) fn pop_str_arg(
+    state: &mut State,
+    fn_name: &str,
+    n: usize,
+) -> Result<String, ScriptError> {
+    state
+        .pop_str_checked()
+        .map_err(|_| ScriptError::bad_arg_type(state, fn_name, n, "str"))
+}
+
+/// Pop the int on top of the stack, which is argument `n` of the function `fn_name`.
+/// # Errors
+/// Returns a type error if the top of the stack is not an int.
+pub(crate) fn pop_int_arg(state: &mut State, fn_name: &str, n: usize) -> Result<i64, ScriptError> {
+    state
+        .pop_int_checked()
+        .map_err(|_| ScriptError::bad_arg_type(state, fn_name, n, "int"))
+}
+
+/// Pop the string on top of the stack as bytes, which is argument `n` of the function `fn_name`.
+/// Unlike `State::pop_str`, the string may contain any bytes, including zero bytes.
+/// # Errors
+/// Returns a type error if the top of the stack is not a string.
+pub(crate) fn pop_bytes_arg(
+    state: &mut State,
+    fn_name: &str,
+    n: usize,
+) -> Result<Vec<u8>, ScriptError> {
+    if !state.is_str() {
+        return Err(ScriptError::bad_arg_type(state, fn_name, n, "str"));
+    }
+
+    // Get the length of a copy of the string, since the C-string copy is only zero-terminated.
+    state.clone_top();
+    unsafe { yaslapi_sys::YASL_len(state.state.as_ptr()) };
+    let len = usize::try_from(state.pop_int()).unwrap_or_default();
+
+    state.shrink_stack(1);
+    unsafe {
+        let ptr = yaslapi_sys::YASL_popcstr(state.state.as_ptr());
+        let bytes = std::slice::from_raw_parts(ptr.cast::<u8>(), len).to_vec();
+        free(ptr.cast());
+        Ok(bytes)
+    }
+}
+
+/// Push a string containing any bytes, including zero bytes.
+pub(crate) fn push_bytes(state: &mut State, bytes: &[u8]) {
+    unsafe { yaslapi_sys::YASL_pushlstr(state.state.as_ptr(), bytes.as_ptr().cast(), bytes.len()) };
+    state.grow_stack(1);
+}
+
+extern "C" {
+    /// Frees the copies of strings which YASL allocates with `malloc`.
+    fn free(ptr: *mut std::ffi::c_void);
+}
+
+/// Push the key and value to the table below them on the stack.
+pub(crate) fn table_set_str(state: &mut State, key: &str, value: &str) {
+    state.push_str(key);
+    state.push_str(value);
+    state
+        .table_set()
+        .expect("Internal Error: Unable to set a string key of a new table.");
+}
+
+/// Declare the hidden global `list` as an empty list of callbacks, which keeps the functions that scripts passed to
+/// a library so that the host can call them later. YASL's API can only refer to a value by its position on the stack
+/// or by the name of a global, and globals can't be removed, so all of a library's callbacks share one hidden global.
+/// Functions are never removed from the list: dropping a closure over a script's top-level locals after the script has
+/// executed crashes YASL, so the functions are kept until the state is deleted.
+pub(crate) fn declare_callbacks(state: &mut State, list: &str) {
+    state.push_list();
+    state
+        .init_global_slice(list)
+        .expect("Internal Error: Invalid callback list identifier.");
+}
+
+/// Push the list of callbacks held by the hidden global `list`.
+/// # Errors
+/// Returns an error if a script replaced the global with a different value.
+fn load_callbacks(state: &mut State, list: &str) -> Result<(), StateError> {
+    state.load_global_slice(list)?;
+    if !state.is_list() {
+        state.pop();
+        return Err(StateError::TypeError);
+    }
+    Ok(())
+}
+
+/// Move the function on top of the stack, which is argument `n` of the function `fn_name`, to the end of the list of
+/// callbacks `list`, and return its index, so that the host can call it later with `call_callback`.
+/// # Errors
+/// Returns a type error if the top of the stack is not a function.
+pub(crate) fn store_callback(
+    state: &mut State,
+    fn_name: &str,
+    n: usize,
+    list: &str,
+) -> Result<i64, ScriptError> {
+    if !matches!(state.peek_type(), Type::Fn | Type::Closure | Type::CFn) {
+        return Err(ScriptError::bad_arg_type(state, fn_name, n, "fn"));
+    }
+    let stored = load_callbacks(state, list).and_then(|()| {
+        state.clone_top();
+        state.len();
+        let index = state.pop_int();
+        // Move the list below the function, as `list_push` expects.
+        state.rotate(2, -1)?;
+        state.list_push()?;
+        state.pop();
+        Ok(index)
+    });
+    stored
+        .map_err(|e| ScriptError::new(e, format!("Error: {fn_name} could not store the function.")))
+}
+
+/// Call the function at `index` of the list of callbacks `list` with the given arguments, and pop its return values.
+/// # Errors
+/// Returns an error if a script replaced the list, or the same errors as `host::call_global`.
+/// # Safety
+/// The function must not raise a runtime error, as for `host::call_global`.
+pub(crate) unsafe fn call_callback(
+    state: &mut State,
+    list: &str,
+    index: i64,
+    args: &[Object],
+) -> Result<Vec<Object>, StateError> {
+    let mut state = state.stack_guard();
+    load_callbacks(&mut state, list)?;
+    state.list_get(isize::try_from(index).map_err(|_| StateError::ValueError)?)?;
+    state.remove(1)?;
+    unsafe { crate::host::call_top(&mut state, args) }
+}
+
+/// Run `f` with the user data which a library's `declare` function stored in a hidden global.
+/// # Errors
+/// Returns an error if a script replaced the global with a different value.
+pub(crate) fn with_hidden_userdata<T, R>(
+    state: &mut State,
+    global: &str,
+    tag: &'static CStr,
+    f: impl FnOnce(&mut T) -> R,
+) -> Result<R, ScriptError> {
+    let data = if state.load_global_slice(global).is_ok() {
+        if state.is_userdata(tag) {
+            state.pop_userdata()
+        } else {
+            state.pop();
+            None
+        }
+    } else {
+        None
+    };
+
+    // The global keeps the data alive after its value is popped.
+    let data = data.ok_or_else(|| {
+        ScriptError::new(
+            StateError::Generic,
+            format!("Error: library data `{global}` is missing."),
+        )
+    })?;
+    Ok(f(unsafe { data.cast::<T>().as_mut() }))
+}
+
+/// Run the body of a library function with a `State` wrapping the raw pointer, and return its number of return values.
+/// An error is printed and then thrown to the YASL VM, after everything owned by the body and the error's message
+/// have been dropped, since throwing jumps over the Rust frames without running any destructors.
+pub(crate) fn run_lib_fn(
+    state: *mut YASL_State,
+    f: impl FnOnce(&mut State) -> Result<c_int, ScriptError>,
+) -> c_int {
+    let error = {
+        let mut wrapper = State::from_memory(state).expect("State is null");
+        crate::metrics::record_host_call(&mut wrapper);
+        match f(&mut wrapper) {
+            Ok(returns) => return returns,
+            Err(error) => error,
+        }
+    };
+
+    // Messages can't contain zero bytes, which would end the C-string early anyway.
+    let ScriptError { error, message } = error;
+    let c_message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    drop(message);
+    unsafe { yaslapi_sys::YASL_print_err(state, c"%s".as_ptr(), c_message.as_ptr()) };
+    drop(c_message);
+    unsafe { yaslapi_sys::YASL_throw_err(state, i32::from(error)) }
+}
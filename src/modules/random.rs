@@ -0,0 +1,147 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `random` library, which generates deterministic pseudo-random numbers, e.g., for procedural generation:
+//! - `random.seed(n)`: Restart the sequence from the integer seed `n`.
+//! - `random.int(a, b)`: A uniformly distributed integer from `a` to `b`, inclusive.
+//! - `random.float()`: A uniformly distributed float from `0.0`, inclusive, to `1.0`, exclusive.
+//!
+//! The generator is SplitMix64, so a seed produces the same sequence on every platform and version of this crate.
+
+use std::{ffi::CStr, os::raw::c_int};
+
+use yaslapi_sys::YASL_State;
+
+use super::{pop_int_arg, run_lib_fn, with_hidden_userdata};
+use crate::{
+    aux::{MetatableFunction, ScriptError},
+    State, StateError,
+};
+
+/// Name of the hidden global holding the generator.
+const GENERATOR_GLOBAL: &str = "__yaslapi_random";
+
+/// Tag of the generator's user data, which YASL compares by address.
+static GENERATOR_TAG: &CStr = c"yaslapi.random";
+
+/// The SplitMix64 generator, which has a single 64-bit word of state.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Return the next 64 random bits.
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Return a uniformly distributed integer in the inclusive range, where `low <= high`.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)] // Offsets wrap in two's complement.
+    fn next_in_range(&mut self, low: i64, high: i64) -> i64 {
+        let span = (high as u64).wrapping_sub(low as u64);
+        if span == u64::MAX {
+            return self.next_u64() as i64;
+        }
+
+        // Reject the values below `threshold`, so that each remainder is equally likely.
+        let n = span + 1;
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let x = self.next_u64();
+            if x >= threshold {
+                return low.wrapping_add((x % n) as i64);
+            }
+        }
+    }
+
+    /// Return a uniformly distributed float in `[0, 1)`, using the top 53 bits for full precision.
+    #[allow(clippy::cast_precision_loss)] // 53-bit integers are exact as floats.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Declare the `random` library as a global of the state, with its generator seeded by `seed`.
+#[allow(clippy::missing_panics_doc)] // The library's global names are valid identifiers.
+pub fn declare(state: &mut State, seed: u64) {
+    state.push_userdata_box(SplitMix64(seed), GENERATOR_TAG);
+    state
+        .init_global_slice(GENERATOR_GLOBAL)
+        .expect("Internal Error: The generator's global is a valid identifier.");
+
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("seed", random_seed, 1),
+        MetatableFunction::new("int", random_int, 2),
+        MetatableFunction::new("float", random_float, 0),
+    ]);
+    state
+        .init_global_slice("random")
+        .expect("Internal Error: `random` is a valid identifier.");
+}
+
+/// Run `f` with the state's generator.
+fn with_generator<R>(
+    state: &mut State,
+    f: impl FnOnce(&mut SplitMix64) -> R,
+) -> Result<R, ScriptError> {
+    with_hidden_userdata(state, GENERATOR_GLOBAL, GENERATOR_TAG, f)
+}
+
+/// Implements `random.seed(n)`.
+unsafe extern "C" fn random_seed(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let seed = pop_int_arg(state, "random.seed", 0)?;
+        #[allow(clippy::cast_sign_loss)] // Negative seeds are as good as any other.
+        with_generator(state, |generator| generator.0 = seed as u64)?;
+        Ok(0)
+    })
+}
+
+/// Implements `random.int(a, b)`.
+unsafe extern "C" fn random_int(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let high = pop_int_arg(state, "random.int", 1)?;
+        let low = pop_int_arg(state, "random.int", 0)?;
+        if low > high {
+            return Err(ScriptError::new(
+                StateError::ValueError,
+                format!("ValueError: random.int expected a range, got {low} > {high}."),
+            ));
+        }
+
+        let n = with_generator(state, |generator| generator.next_in_range(low, high))?;
+        state.push_int(n);
+        Ok(1)
+    })
+}
+
+/// Implements `random.float()`.
+unsafe extern "C" fn random_float(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let x = with_generator(state, SplitMix64::next_f64)?;
+        state.push_float(x);
+        Ok(1)
+    })
+}
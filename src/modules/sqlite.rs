@@ -0,0 +1,208 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `sqlite` library, which lets scripts use SQLite databases:
+//! - `sqlite.open(path)`: Open or create the database file at `path`, or an in-memory database for `':memory:'`.
+//! - `db->execute(sql, params)`: Run a statement, and return the number of rows it changed.
+//! - `db->query(sql, params)`: Run a query, and return its rows as a list of tables keyed by column name.
+//!
+//! The `params` are an optional list of values bound to the statement's `?` placeholders, in order.
+//! Values map to SQLite's types as `int` to `INTEGER`, `float` to `REAL`, `str` to `TEXT`, `bool` to `0` or `1`,
+//! and `undef` to `NULL`. Columns which are `NULL` are missing from row tables, and blobs are decoded as UTF-8 strings.
+
+use std::{ffi::CStr, os::raw::c_int};
+
+use rusqlite::{types::Value, Connection};
+use yaslapi_sys::YASL_State;
+
+use super::{pop_str_arg, run_lib_fn};
+use crate::{
+    aux::{HashableObject, MetatableFunction, Object, ScriptError},
+    State, StateError, Type,
+};
+
+/// Tag of a connection's user data, which YASL compares by address.
+static CONNECTION_TAG: &CStr = c"yaslapi.sqlite";
+
+/// Name of the metatable which gives connections their methods.
+static CONNECTION_MT: &CStr = c"sqlite.connection";
+
+/// Declare the `sqlite` library as a global of the state.
+/// Scripts can read and write any database file the host process can access through it,
+/// so it should not be declared for untrusted scripts.
+#[allow(clippy::missing_panics_doc)] // The library's global names are valid identifiers.
+pub fn declare(state: &mut State) {
+    state.push_table();
+    state.clone_top();
+    state.register_mt(CONNECTION_MT);
+    state.table_set_functions(&[
+        MetatableFunction::new("execute", sqlite_execute, 3),
+        MetatableFunction::new("query", sqlite_query, 3),
+    ]);
+    state.pop();
+
+    state.push_table();
+    state.table_set_functions(&[MetatableFunction::new("open", sqlite_open, 1)]);
+    state
+        .init_global_slice("sqlite")
+        .expect("Internal Error: `sqlite` is a valid identifier.");
+}
+
+/// Pop the arguments of a connection method, and return the connection, which stays on the stack.
+fn pop_method_args<'a>(
+    state: &mut State,
+    fn_name: &str,
+) -> Result<(&'a Connection, String, Vec<Value>), ScriptError> {
+    let params = pop_params(state, fn_name)?;
+    let sql = pop_str_arg(state, fn_name, 1)?;
+    if !state.is_userdata(CONNECTION_TAG) {
+        return Err(ScriptError::bad_arg_type(
+            state,
+            fn_name,
+            0,
+            "sqlite.connection",
+        ));
+    }
+
+    // The connection is kept alive by its value on the stack until the method returns.
+    let connection = state
+        .peek_userdata()
+        .expect("Internal Error: Connection user data is null.");
+    Ok((unsafe { connection.cast().as_ref() }, sql, params))
+}
+
+/// Pop the optional list of parameters, which is the third argument of a connection method.
+fn pop_params(state: &mut State, fn_name: &str) -> Result<Vec<Value>, ScriptError> {
+    let bad_params = || {
+        ScriptError::new(
+            StateError::TypeError,
+            format!("TypeError: {fn_name} expected params to be a list of int, float, str, bool, or undef."),
+        )
+    };
+    let params = match state.peek_type() {
+        Type::Undef => {
+            state.pop();
+            return Ok(Vec::new());
+        }
+        Type::List => state
+            .pop_object(Some(Type::List))
+            .map_err(|_| bad_params())?,
+        _ => return Err(ScriptError::bad_arg_type(state, fn_name, 2, "list")),
+    };
+
+    let Object::List(params) = params else {
+        unreachable!("Internal Error: Popped list is not a list.");
+    };
+    params
+        .into_iter()
+        .map(|param| match param {
+            Object::Undef => Ok(Value::Null),
+            Object::Bool(b) => Ok(Value::Integer(b.into())),
+            Object::Int(i) => Ok(Value::Integer(i)),
+            Object::Float(x) => Ok(Value::Real(x)),
+            Object::Str(s) => Ok(Value::Text(s)),
+            _ => Err(bad_params()),
+        })
+        .collect()
+}
+
+/// Convert an SQLite error to a runtime error of the given function.
+fn sql_error(fn_name: &str, e: &rusqlite::Error) -> ScriptError {
+    ScriptError::new(
+        StateError::Generic,
+        format!("Error: {fn_name} failed: {e}."),
+    )
+}
+
+/// Implements `sqlite.open(path)`.
+unsafe extern "C" fn sqlite_open(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let path = pop_str_arg(state, "sqlite.open", 0)?;
+        let connection = Connection::open(path).map_err(|e| sql_error("sqlite.open", &e))?;
+
+        state.push_userdata_box(connection, CONNECTION_TAG);
+        state
+            .load_mt(CONNECTION_MT)
+            .expect("Internal Error: The connection metatable was not registered.");
+        state
+            .set_mt()
+            .expect("Internal Error: Unable to set the metatable of a connection.");
+        Ok(1)
+    })
+}
+
+/// Implements `db->execute(sql, params)`.
+unsafe extern "C" fn sqlite_execute(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let (connection, sql, params) = pop_method_args(state, "sqlite.execute")?;
+        let changes = connection
+            .execute(&sql, rusqlite::params_from_iter(params))
+            .map_err(|e| sql_error("sqlite.execute", &e))?;
+
+        state.push_int(changes.try_into().unwrap_or(i64::MAX));
+        Ok(1)
+    })
+}
+
+/// Implements `db->query(sql, params)`.
+unsafe extern "C" fn sqlite_query(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let (connection, sql, params) = pop_method_args(state, "sqlite.query")?;
+        let rows = query(connection, &sql, params).map_err(|e| sql_error("sqlite.query", &e))?;
+
+        state.push_object(&Object::List(rows)).map_err(|_| {
+            ScriptError::new(
+                StateError::Generic,
+                "Error: sqlite.query failed to push the rows.",
+            )
+        })?;
+        Ok(1)
+    })
+}
+
+/// Run the query and convert each row to a table keyed by column name.
+fn query(connection: &Connection, sql: &str, params: Vec<Value>) -> rusqlite::Result<Vec<Object>> {
+    let mut statement = connection.prepare(sql)?;
+    let columns = statement
+        .column_names()
+        .into_iter()
+        .map(|name| HashableObject::Str(name.to_owned()))
+        .collect::<Vec<_>>();
+
+    let mut rows = statement.query(rusqlite::params_from_iter(params))?;
+    let mut results = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut table = std::collections::HashMap::with_capacity(columns.len());
+        for (i, column) in columns.iter().enumerate() {
+            let value = match row.get::<_, Value>(i)? {
+                Value::Null => continue,
+                Value::Integer(i) => Object::Int(i),
+                Value::Real(x) => Object::Float(x),
+                Value::Text(s) => Object::Str(s),
+                Value::Blob(b) => Object::Str(String::from_utf8_lossy(&b).into_owned()),
+            };
+            table.insert(column.clone(), value);
+        }
+        results.push(Object::Table(table));
+    }
+    Ok(results)
+}
@@ -0,0 +1,257 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `timer` library, a scheduler for callbacks which scripts want called later:
+//! - `timer.after(seconds, f)`: Call `f()` once, `seconds` after the current time. Returns an id for `timer.cancel`.
+//! - `timer.every(seconds, f)`: Call `f()` every `seconds`, starting `seconds` after the current time. Returns an id.
+//! - `timer.cancel(id)`: Stop calling the timer with the given id. Returns whether it was scheduled.
+//!
+//! The scheduler doesn't read a clock itself. The host calls `Scheduler::tick` with the current time,
+//! e.g., once per frame, which calls every timer which has come due since the previous tick.
+//! Each timer's function is kept by the state until it is deleted, even after the timer has finished or was cancelled.
+
+use std::{
+    ffi::CStr,
+    ops::{Deref, DerefMut},
+    os::raw::c_int,
+    time::Duration,
+};
+
+use yaslapi_sys::YASL_State;
+
+use super::{
+    call_callback, declare_callbacks, pop_int_arg, run_lib_fn, store_callback, with_hidden_userdata,
+};
+use crate::{
+    aux::{MetatableFunction, ScriptError},
+    State, StateError,
+};
+
+/// Name of the hidden global holding the scheduled timers.
+const TIMERS_GLOBAL: &str = "__yaslapi_timer";
+
+/// Name of the hidden global holding the list of the timers' functions.
+const CALLBACKS_GLOBAL: &str = "__yaslapi_timer_callbacks";
+
+/// Tag of the timers' user data, which YASL compares by address.
+static TIMERS_TAG: &CStr = c"yaslapi.timer";
+
+/// Drives the timers of a state with the `timer` library, by calling `tick` with the current time.
+/// Dereferences to the underlying `State`, so the host can keep using the state between ticks.
+pub struct Scheduler<'a> {
+    state: &'a mut State,
+}
+
+/// The timers scheduled by scripts. Each timer's function is kept in the list of callbacks.
+#[derive(Default)]
+struct Timers {
+    /// The time of the latest tick, in seconds.
+    now: f64,
+    scheduled: Vec<Timer>,
+    next_id: i64,
+}
+
+/// A scheduled callback.
+struct Timer {
+    id: i64,
+    /// The index of the timer's function in the list of callbacks.
+    callback: i64,
+    /// When the timer is next due, in seconds.
+    due: f64,
+    /// The period of a repeating timer, in seconds.
+    interval: Option<f64>,
+}
+
+/// An error from calling a single timer's function, e.g., because its function was replaced by another value.
+/// The other timers are still called.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimerError {
+    /// The id which `timer.after` or `timer.every` returned for the timer.
+    pub id: i64,
+    /// The cause of the failure.
+    pub error: StateError,
+}
+
+/// Declare the `timer` library as a global of the state, with `start` as the current time until the first tick.
+/// Returns the scheduler of the state's timers.
+#[allow(clippy::missing_panics_doc)] // The library's global names are valid identifiers.
+pub fn declare(state: &mut State, start: Duration) -> Scheduler<'_> {
+    let timers = Timers {
+        now: start.as_secs_f64(),
+        ..Timers::default()
+    };
+    state.push_userdata_box(timers, TIMERS_TAG);
+    state
+        .init_global_slice(TIMERS_GLOBAL)
+        .expect("Internal Error: The timers' global is a valid identifier.");
+    declare_callbacks(state, CALLBACKS_GLOBAL);
+
+    state.push_table();
+    state.table_set_functions(&[
+        MetatableFunction::new("after", timer_after, 2),
+        MetatableFunction::new("every", timer_every, 2),
+        MetatableFunction::new("cancel", timer_cancel, 1),
+    ]);
+    state
+        .init_global_slice("timer")
+        .expect("Internal Error: `timer` is a valid identifier.");
+    Scheduler::new(state)
+}
+
+impl<'a> Scheduler<'a> {
+    /// Drive the timers of a state whose `timer` library was already declared.
+    pub fn new(state: &'a mut State) -> Self {
+        Self { state }
+    }
+
+    /// Advance the scheduler to the time `now`, and call each timer which is due, in the order they are due.
+    /// A repeating timer is called at most once per tick, even if several of its periods have passed.
+    /// Returns the errors of the timers which could not be called. Nothing is called if the library was not declared.
+    /// # Safety
+    /// None of the timers which are due may raise a runtime error. Their functions are called outside of
+    /// `State::execute`, so YASL would jump to the error handler of an execution which has already returned.
+    pub unsafe fn tick(&mut self, now: Duration) -> Vec<TimerError> {
+        let state = &mut *self.state;
+        let now = now.as_secs_f64();
+        let Ok(mut due) = with_timers(state, |timers| {
+            timers.now = now;
+            timers
+                .scheduled
+                .iter()
+                .filter(|timer| timer.due <= now)
+                .map(|timer| (timer.due, timer.id))
+                .collect::<Vec<_>>()
+        }) else {
+            return Vec::new();
+        };
+        due.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut errors = Vec::new();
+        for (_, id) in due {
+            // Reschedule or remove the timer before calling it, so that the callback may cancel it.
+            // An earlier callback may also have cancelled this timer.
+            let scheduled = with_timers(state, |timers| {
+                let index = timers.scheduled.iter().position(|timer| timer.id == id)?;
+                let timer = &mut timers.scheduled[index];
+                let callback = timer.callback;
+                match timer.interval {
+                    Some(interval) => {
+                        timer.due += interval;
+                        if timer.due <= now {
+                            timer.due = now + interval;
+                        }
+                    }
+                    None => {
+                        timers.scheduled.remove(index);
+                    }
+                }
+                Some(callback)
+            });
+            let Ok(Some(callback)) = scheduled else {
+                continue;
+            };
+
+            // SAFETY: The caller guarantees that the due timers don't raise runtime errors.
+            if let Err(error) = unsafe { call_callback(state, CALLBACKS_GLOBAL, callback, &[]) } {
+                errors.push(TimerError { id, error });
+            }
+        }
+        errors
+    }
+}
+
+impl Deref for Scheduler<'_> {
+    type Target = State;
+
+    fn deref(&self) -> &State {
+        self.state
+    }
+}
+
+impl DerefMut for Scheduler<'_> {
+    fn deref_mut(&mut self) -> &mut State {
+        self.state
+    }
+}
+
+/// Run `f` with the state's timers.
+fn with_timers<R>(state: &mut State, f: impl FnOnce(&mut Timers) -> R) -> Result<R, ScriptError> {
+    with_hidden_userdata(state, TIMERS_GLOBAL, TIMERS_TAG, f)
+}
+
+/// Schedule the function on top of the stack, after the delay below it, for `timer.after` or `timer.every`.
+fn schedule(state: &mut State, fn_name: &str, repeat: bool) -> Result<c_int, ScriptError> {
+    let callback = store_callback(state, fn_name, 1, CALLBACKS_GLOBAL)?;
+
+    // The delay is only checked once the function is stored, since it is below the function on the stack.
+    let seconds = match state.pop_number() {
+        Ok(seconds) if seconds.is_finite() && (seconds > 0.0 || (!repeat && seconds >= 0.0)) => {
+            seconds
+        }
+        Ok(seconds) => {
+            return Err(ScriptError::new(
+                StateError::ValueError,
+                format!(
+                    "ValueError: {fn_name} expected a positive delay in seconds, got {seconds}."
+                ),
+            ));
+        }
+        Err(_) => return Err(ScriptError::bad_arg_type(state, fn_name, 0, "float")),
+    };
+
+    let id = with_timers(state, |timers| {
+        timers.next_id += 1;
+        timers.scheduled.push(Timer {
+            id: timers.next_id,
+            callback,
+            due: timers.now + seconds,
+            interval: repeat.then_some(seconds),
+        });
+        timers.next_id
+    })?;
+    state.push_int(id);
+    Ok(1)
+}
+
+/// Implements `timer.after(seconds, f)`.
+unsafe extern "C" fn timer_after(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| schedule(state, "timer.after", false))
+}
+
+/// Implements `timer.every(seconds, f)`.
+unsafe extern "C" fn timer_every(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| schedule(state, "timer.every", true))
+}
+
+/// Implements `timer.cancel(id)`.
+unsafe extern "C" fn timer_cancel(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let id = pop_int_arg(state, "timer.cancel", 0)?;
+        let removed = with_timers(state, |timers| {
+            let len = timers.scheduled.len();
+            timers.scheduled.retain(|timer| timer.id != id);
+            timers.scheduled.len() < len
+        })?;
+        state.push_bool(removed);
+        Ok(1)
+    })
+}
@@ -0,0 +1,128 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use rmpv::Value;
+
+use crate::{
+    aux::{HashableObject, Object},
+    StateError,
+};
+
+impl TryFrom<Value> for Object {
+    type Error = StateError;
+    /// Convert a MessagePack value into a YASL `Object`. `nil` becomes `undef`.
+    /// # Errors
+    /// Will return `StateError::ValueError` for integers which don't fit an `i64` and strings or binary data which are
+    /// not valid UTF-8, or `StateError::TypeError` for extension types and maps with arrays or maps as keys.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Nil => Self::Undef,
+            Value::Boolean(b) => Self::Bool(b),
+            Value::Integer(i) => Self::Int(i.as_i64().ok_or(StateError::ValueError)?),
+            Value::F32(f) => Self::Float(f.into()),
+            Value::F64(f) => Self::Float(f),
+            Value::String(s) => Self::Str(s.into_str().ok_or(StateError::ValueError)?),
+            Value::Binary(b) => {
+                Self::Str(String::from_utf8(b).map_err(|_| StateError::ValueError)?)
+            }
+            Value::Array(array) => Self::List(
+                array
+                    .into_iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(map) => Self::Table(
+                map.into_iter()
+                    .map(|(k, v)| {
+                        let k = HashableObject::try_from(Self::try_from(k)?)
+                            .map_err(|_| StateError::TypeError)?;
+                        Ok((k, Self::try_from(v)?))
+                    })
+                    .collect::<Result<_, StateError>>()?,
+            ),
+            Value::Ext(..) => return Err(StateError::TypeError),
+        })
+    }
+}
+
+impl TryFrom<&Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a MessagePack value. `undef` becomes `nil`.
+    /// # Errors
+    /// Will return `StateError::TypeError` for user data and user pointers, including as table keys.
+    fn try_from(value: &Object) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Object::Undef => Self::Nil,
+            Object::Bool(b) => Self::Boolean(*b),
+            Object::Int(i) => Self::from(*i),
+            Object::Float(f) => Self::F64(*f),
+            Object::Str(s) => Self::from(s.as_str()),
+            Object::List(list) => {
+                Self::Array(list.iter().map(Self::try_from).collect::<Result<_, _>>()?)
+            }
+            Object::Table(table) => Self::Map(
+                table
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok((Self::try_from(Object::from(k.clone()))?, Self::try_from(v)?))
+                    })
+                    .collect::<Result<_, StateError>>()?,
+            ),
+            Object::UserData { .. } | Object::UserPtr(_) => return Err(StateError::TypeError),
+        })
+    }
+}
+
+impl TryFrom<Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a MessagePack value. `undef` becomes `nil`.
+    /// # Errors
+    /// Has the same errors as converting from `&Object`.
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
+
+impl Object {
+    /// Serialize this object into compact MessagePack bytes.
+    /// # Errors
+    /// Will return `StateError::TypeError` if the object contains user data or user pointers.
+    #[allow(clippy::missing_panics_doc)] // Writing to a `Vec` can't fail.
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, StateError> {
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &Value::try_from(self)?)
+            .expect("Writing MessagePack to a `Vec` should not fail.");
+        Ok(bytes)
+    }
+
+    /// Deserialize an object from MessagePack bytes, which must contain exactly one value.
+    /// # Errors
+    /// Will return `StateError::ValueError` if the bytes are not a single valid MessagePack value,
+    /// or an error if the value could not be converted into an `Object`.
+    pub fn from_msgpack(mut bytes: &[u8]) -> Result<Self, StateError> {
+        let value = rmpv::decode::read_value(&mut bytes).map_err(|_| StateError::ValueError)?;
+        if !bytes.is_empty() {
+            return Err(StateError::ValueError);
+        }
+        Self::try_from(value)
+    }
+}
@@ -0,0 +1,187 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+
+use crate::{aux::Object, send_cell::SendCell, Error, State, StateError};
+
+/// Executes batches of independent scripts across rayon's thread pool, each in its own `State`.
+/// Each script's result is the final value of a global, named `result` by default.
+pub struct ParallelRunner {
+    setup: Box<dyn Fn(&mut State) + Send + Sync>,
+    result_global: String,
+}
+
+/// A script which `ParallelRunner::compile_all` could not compile.
+#[derive(Debug)]
+pub struct CompileError {
+    /// The path of the script.
+    pub path: PathBuf,
+    /// `Error::Load` if the script could not be loaded, otherwise the error from compiling it.
+    pub error: Error,
+    /// The compiler's diagnostics, e.g., the line of a syntax error, or empty if the script could not be loaded.
+    pub message: String,
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)?;
+        if !self.message.is_empty() {
+            write!(f, "\n{}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CompileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl ParallelRunner {
+    /// Create a runner which executes each script in a new state with only the result global declared.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            setup: Box::new(|_| {}),
+            result_global: "result".to_owned(),
+        }
+    }
+
+    /// Set a function which is called for each new state before its script is executed,
+    /// e.g., to declare libraries and register host functions.
+    #[must_use]
+    pub fn setup(mut self, setup: impl Fn(&mut State) + Send + Sync + 'static) -> Self {
+        self.setup = Box::new(setup);
+        self
+    }
+
+    /// Set the name of the global which each script assigns its result to.
+    #[must_use]
+    pub fn result_global(mut self, name: impl Into<String>) -> Self {
+        self.result_global = name.into();
+        self
+    }
+
+    /// Compile and execute each of the scripts in parallel, and return their results in the same order.
+    /// A script which doesn't assign its result global returns `Object::Undef`.
+    ///
+    /// Each result is `StateError::Generic` if the result global's name is not a valid identifier,
+    /// an error from compiling or executing the script, or `StateError::TypeError` if the result
+    /// contains user data, which can't outlive the script's state, or a user pointer, which may not be `Send`.
+    pub fn run<S: AsRef<str> + Sync>(&self, scripts: &[S]) -> Vec<Result<Object, StateError>> {
+        // `run_one` rejects results with pointers, so only plain data is sent back to this thread.
+        scripts
+            .par_iter()
+            .map(|script| SendCell(self.run_one(script.as_ref())))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(SendCell::into_inner)
+            .collect()
+    }
+
+    /// Compile each of the script files in parallel, in new states which are set up the same way as for `run`,
+    /// and return the compiled states in the same order, ready to be executed.
+    /// Each error holds the compiler's diagnostics, so that every broken script can be reported at once.
+    /// The returned states keep capturing their error output, which can be read with `State::load_printerr`.
+    /// They are moved to this thread with any user data the `setup` function gave them,
+    /// which is `Send` since `State::push_userdata_box` only accepts `Send` data.
+    // TODO: Return a shareable `Program` instead of a `State` once YASL can load compiled bytecode.
+    pub fn compile_all<P: AsRef<Path> + Sync>(
+        &self,
+        paths: &[P],
+    ) -> Vec<Result<State, CompileError>> {
+        paths
+            .par_iter()
+            .map(|path| SendCell(self.compile_one(path.as_ref())))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(SendCell::into_inner)
+            .collect()
+    }
+
+    /// Load and compile a single script file in a new state.
+    fn compile_one(&self, path: &Path) -> Result<State, CompileError> {
+        let fail = |error: Error, message: String| CompileError {
+            path: path.to_path_buf(),
+            error,
+            message,
+        };
+        let open = || -> Result<State, Error> {
+            let mut state = State::from_file(path)?;
+            state.declare_global(&self.result_global)?;
+            (self.setup)(&mut state);
+            Ok(state)
+        };
+
+        let mut state = open().map_err(|e| fail(e, String::new()))?;
+        state.set_printerr_tostr();
+        let Err(error) = state.compile() else {
+            return Ok(state);
+        };
+        state.load_printerr();
+        let message = state.pop_str().unwrap_or_default().trim_end().to_owned();
+        Err(fail(error.into(), message))
+    }
+
+    /// Execute a single script in a new state, and take its result.
+    fn run_one(&self, script: &str) -> Result<Object, StateError> {
+        let mut state = State::from_source(script);
+        state
+            .declare_global(&self.result_global)
+            .map_err(|_| StateError::Generic)?;
+        (self.setup)(&mut state);
+        state.execute()?;
+
+        // YASL can't load a global which was never assigned, or was assigned `undef`.
+        if state.load_global_slice(&self.result_global).is_err() {
+            return Ok(Object::Undef);
+        }
+        let result = state.pop_object(None)?;
+        if contains_pointer(&result) {
+            return Err(StateError::TypeError);
+        }
+        Ok(result)
+    }
+}
+
+impl Default for ParallelRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the object or any of its items is user data or a user pointer.
+fn contains_pointer(object: &Object) -> bool {
+    match object {
+        Object::UserData { .. } | Object::UserPtr(_) => true,
+        Object::List(list) => list.iter().any(contains_pointer),
+        Object::Table(table) => table.values().any(contains_pointer),
+        _ => false,
+    }
+}
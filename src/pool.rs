@@ -0,0 +1,148 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Condvar, Mutex},
+};
+
+use crate::{send_cell::SendCell, State};
+
+/// A fixed number of pre-initialized states which can be checked out by any thread,
+/// e.g., one per request of a multi-threaded server, instead of creating a new state each time.
+///
+/// Globals set while a state is checked out are kept when it is returned to the pool,
+/// so each script should be loaded with `reset_from_source` or `reset_from_script` before executing it.
+/// States move between threads with their values, so any user data they hold must be `Send`,
+/// as `State::push_userdata_box` requires.
+pub struct StatePool {
+    states: Mutex<Vec<SendCell<State>>>,
+    returned: Condvar,
+    size: usize,
+    setup: Box<dyn Fn(&mut State) + Send + Sync>,
+}
+
+/// A state checked out from a `StatePool`, which is returned to the pool when dropped.
+pub struct PooledState<'a> {
+    state: Option<State>,
+    pool: &'a StatePool,
+}
+
+impl StatePool {
+    /// Create a pool of `size` states. The `setup` function is called once for each new state,
+    /// e.g., to declare libraries and register host functions.
+    /// # Panics
+    /// The `size` must not be zero, since `get` would wait forever for a state.
+    pub fn new(size: usize, setup: impl Fn(&mut State) + Send + Sync + 'static) -> Self {
+        assert!(size > 0, "A state pool must have at least one state.");
+        let setup: Box<dyn Fn(&mut State) + Send + Sync> = Box::new(setup);
+        let states = (0..size).map(|_| SendCell(new_state(&setup))).collect();
+        Self {
+            states: Mutex::new(states),
+            returned: Condvar::new(),
+            size,
+            setup,
+        }
+    }
+
+    /// Check out a state, waiting until one is returned if they are all in use.
+    #[allow(clippy::missing_panics_doc)] // Unwrapping mutex lock should never fail.
+    pub fn get(&self) -> PooledState<'_> {
+        let mut states = self
+            .returned
+            .wait_while(self.states.lock().unwrap(), |states| states.is_empty())
+            .unwrap();
+        PooledState {
+            state: states.pop().map(SendCell::into_inner),
+            pool: self,
+        }
+    }
+
+    /// Check out a state, or return `None` if they are all in use.
+    #[allow(clippy::missing_panics_doc)] // Unwrapping mutex lock should never fail.
+    pub fn try_get(&self) -> Option<PooledState<'_>> {
+        let state = self.states.lock().unwrap().pop()?;
+        Some(PooledState {
+            state: Some(state.into_inner()),
+            pool: self,
+        })
+    }
+
+    /// The total number of states owned by the pool.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of states which are not checked out.
+    #[allow(clippy::missing_panics_doc)] // Unwrapping mutex lock should never fail.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.states.lock().unwrap().len()
+    }
+
+    /// Return a state to the pool and wake a waiting thread.
+    fn put(&self, state: State) {
+        // States in the pool aren't used until they are checked out by a single thread.
+        self.states.lock().unwrap().push(SendCell(state));
+        self.returned.notify_one();
+    }
+}
+
+impl PooledState<'_> {
+    /// Replace the checked out state with a newly initialized one, e.g., after a runtime error
+    /// left its stack or globals in an unknown condition. The new state is returned to the pool when dropped.
+    pub fn discard(&mut self) {
+        self.state = Some(new_state(&self.pool.setup));
+    }
+}
+
+impl Deref for PooledState<'_> {
+    type Target = State;
+    fn deref(&self) -> &State {
+        self.state
+            .as_ref()
+            .expect("Internal Error: Pooled state was already returned.")
+    }
+}
+impl DerefMut for PooledState<'_> {
+    fn deref_mut(&mut self) -> &mut State {
+        self.state
+            .as_mut()
+            .expect("Internal Error: Pooled state was already returned.")
+    }
+}
+
+impl Drop for PooledState<'_> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            self.pool.put(state);
+        }
+    }
+}
+
+/// Create an empty state and initialize it with the pool's setup function.
+fn new_state(setup: &dyn Fn(&mut State)) -> State {
+    let mut state = State::from_source("");
+    setup(&mut state);
+    state
+}
@@ -0,0 +1,244 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Rendering of `Object`s in the same format as YASL's `tostr` and `echo`, or as YASL literals.
+
+use std::fmt::{self, Display, Write};
+
+use crate::{
+    aux::{HashableObject, Object},
+    syntax::KEYWORDS,
+};
+
+/// Formats the object as a YASL literal which evaluates to an equal value, e.g., `{ .a: [1, 'b\n'] }`.
+/// Table entries are sorted by key. User data and user pointers have no literal syntax, so they are
+/// written as `<userptr: 0x...>` and the result is not valid YASL.
+impl Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Str(s) => write_literal_str(f, s),
+            Self::Float(x) => write_literal_float(f, *x),
+            // The literal `9223372036854775808` is out of range, so it can't be negated.
+            Self::Int(i64::MIN) => write!(f, "({} - 1)", i64::MIN + 1),
+            Self::List(list) => {
+                f.write_char('[')?;
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Self::Table(table) if table.is_empty() => f.write_str("{}"),
+            Self::Table(_) => {
+                f.write_str("{ ")?;
+                for (i, (k, v)) in self.table_sorted_iter().into_iter().flatten().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    match k {
+                        HashableObject::Str(s) if is_plain_name(s) => write!(f, ".{s}")?,
+                        _ => write!(f, "{k}")?,
+                    }
+                    write!(f, ": {v}")?;
+                }
+                f.write_str(" }")
+            }
+            _ => f.write_str(&self.to_yasl_string()),
+        }
+    }
+}
+
+/// Formats the key as a YASL literal, the same as the equivalent `Object`.
+impl Display for HashableObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&Object::from(self.clone()), f)
+    }
+}
+
+/// Write a single-quoted string literal, escaping quotes, backslashes, and control characters.
+fn write_literal_str(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('\'')?;
+    for c in s.chars() {
+        match c {
+            '\'' | '\\' => write!(f, "\\{c}")?,
+            '\x07' => f.write_str("\\a")?,
+            '\x08' => f.write_str("\\b")?,
+            '\x0C' => f.write_str("\\f")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\x0B' => f.write_str("\\v")?,
+            '\0' => f.write_str("\\0")?,
+            c if c.is_ascii_control() => write!(f, "\\x{:02x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('\'')
+}
+
+/// Write a float literal with full precision. YASL has no exponent syntax, or literals for non-finite values.
+fn write_literal_float(f: &mut fmt::Formatter<'_>, x: f64) -> fmt::Result {
+    if x.is_nan() {
+        f.write_str("(0.0 / 0.0)")
+    } else if x.is_infinite() {
+        f.write_str(if x > 0. {
+            "(1.0 / 0.0)"
+        } else {
+            "(-1.0 / 0.0)"
+        })
+    } else {
+        // Rust's `Display` for floats never uses an exponent, but omits the decimal point for whole numbers.
+        let s = x.to_string();
+        f.write_str(&s)?;
+        if s.contains('.') {
+            Ok(())
+        } else {
+            f.write_str(".0")
+        }
+    }
+}
+
+/// Whether a string key can be written with the `.name` shorthand.
+fn is_plain_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&s)
+}
+
+impl Object {
+    /// Render the object on a single line, as YASL's `tostr` would, e.g., `[1, a, {k: 2.0}]`.
+    /// Strings are not quoted, and table entries are sorted by key so that the output is deterministic.
+    #[must_use]
+    pub fn to_yasl_string(&self) -> String {
+        let mut out = String::new();
+        write_object(&mut out, self, None, 0);
+        out
+    }
+
+    /// Render the object like `to_yasl_string`, but with each item of a non-empty list or table
+    /// on its own line, indented by `indent` spaces per level of nesting.
+    #[must_use]
+    pub fn to_pretty_string(&self, indent: usize) -> String {
+        let mut out = String::new();
+        write_object(&mut out, self, Some(indent), 0);
+        out
+    }
+}
+
+/// Format a float the way YASL does: fixed-point with trailing zeros removed, keeping at least one decimal.
+pub(crate) fn format_float(f: f64) -> String {
+    if f.is_nan() {
+        return "nan".to_owned();
+    }
+    if f.is_infinite() {
+        return if f > 0. { "inf" } else { "-inf" }.to_owned();
+    }
+
+    let mut s = format!("{f:.6}");
+    let trimmed = s.trim_end_matches('0').len();
+    s.truncate(if s[..trimmed].ends_with('.') {
+        trimmed + 1
+    } else {
+        trimmed
+    });
+    s
+}
+
+/// Write a scalar, list, or table to `out`. When `indent` is set, nested items are written on separate lines.
+fn write_object(out: &mut String, object: &Object, indent: Option<usize>, depth: usize) {
+    match object {
+        Object::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Object::Int(i) => out.push_str(&i.to_string()),
+        Object::Float(f) => out.push_str(&format_float(*f)),
+        Object::Str(s) => out.push_str(s),
+        Object::List(list) => {
+            write_items(out, ('[', ']'), list, indent, depth, |out, item| {
+                write_object(out, item, indent, depth + 1);
+            });
+        }
+        Object::Table(_) => {
+            let entries = object
+                .table_sorted_iter()
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            write_items(out, ('{', '}'), &entries, indent, depth, |out, (k, v)| {
+                write_object(out, &Object::from((*k).clone()), indent, depth + 1);
+                out.push_str(": ");
+                write_object(out, v, indent, depth + 1);
+            });
+        }
+        Object::UserData { data, tag } => {
+            let name = tag.map_or("userdata".into(), |tag| tag.to_string_lossy());
+            let _ = write!(
+                out,
+                "<{name}: {:p}>",
+                data.map_or(std::ptr::null_mut(), |p| p.as_ptr())
+            );
+        }
+        Object::UserPtr(p) => {
+            let _ = write!(
+                out,
+                "<userptr: {:p}>",
+                p.map_or(std::ptr::null_mut(), |p| p.as_ptr())
+            );
+        }
+        Object::Undef => out.push_str("undef"),
+    }
+}
+
+/// Write the items of a list or table between the given brackets.
+fn write_items<T>(
+    out: &mut String,
+    (open, close): (char, char),
+    items: &[T],
+    indent: Option<usize>,
+    depth: usize,
+    mut write_item: impl FnMut(&mut String, &T),
+) {
+    out.push(open);
+    match indent {
+        Some(width) if !items.is_empty() => {
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(if i == 0 { "\n" } else { ",\n" });
+                out.push_str(&" ".repeat(width * (depth + 1)));
+                write_item(out, item);
+            }
+            out.push('\n');
+            out.push_str(&" ".repeat(width * depth));
+        }
+        _ => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_item(out, item);
+            }
+        }
+    }
+    out.push(close);
+}
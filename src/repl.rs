@@ -0,0 +1,200 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! An embeddable read-eval-print loop, which keeps globals between inputs and
+//! reports each result as an `Object` instead of printing it.
+
+use crate::{
+    aux::{HashableObject, Object},
+    syntax::{self, Token},
+    State, StateError,
+};
+
+/// The outcome of feeding a line of input to a `ReplSession`.
+#[derive(Debug)]
+pub enum ReplResult {
+    /// The input so far is unfinished, e.g., an unclosed block. More lines are needed.
+    Incomplete,
+    /// The input was evaluated. Expressions produce their value, statements produce `Object::Undef`.
+    Value(Object),
+}
+
+/// A REPL session over a single YASL `State`.
+/// Globals, including those declared with `let` at the top level, are preserved across inputs.
+pub struct ReplSession {
+    state: State,
+    pending: String,
+}
+
+impl ReplSession {
+    /// Create a new session with the standard libraries declared.
+    #[must_use]
+    pub fn new() -> Self {
+        let mut state = State::default();
+        state.declare_libs();
+        Self::with_state(state)
+    }
+
+    /// Create a new session that evaluates input against an existing `State`.
+    #[must_use]
+    pub fn with_state(state: State) -> Self {
+        Self {
+            state,
+            pending: String::new(),
+        }
+    }
+
+    /// Get the underlying state, e.g., to register host functions or read globals.
+    pub fn state(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Whether earlier lines are waiting for more input to be complete.
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Discard any unfinished input, e.g., when the user cancels a multi-line entry.
+    pub fn clear_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Feed a line of input to the session. Returns `ReplResult::Incomplete` if the input
+    /// so far is unfinished, otherwise evaluates all pending input and returns the result.
+    /// # Errors
+    /// Will return `StateError::SyntaxError` if the complete input contains invalid syntax.
+    /// May return runtime errors depending on the input and execution state.
+    /// Diagnostics are printed by YASL as usual.
+    pub fn feed(&mut self, line: &str) -> Result<ReplResult, StateError> {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+
+        if State::is_input_incomplete(&self.pending) {
+            return Ok(ReplResult::Incomplete);
+        }
+        let is_expression = syntax::scan(&self.pending).is_expression();
+        let input = std::mem::take(&mut self.pending);
+
+        if is_expression {
+            return self.state.eval(&input).map(ReplResult::Value);
+        }
+
+        // Statements are executed in REPL mode so that top-level declarations become globals.
+        self.state.reset_source(&input);
+        let result = self.state.execute_repl();
+        self.state.restore_origin();
+        result?;
+
+        // Remember top-level declarations so that they can be offered as completions.
+        let scan = syntax::scan(&input);
+        let mut depth = 0usize;
+        for pair in scan.tokens.windows(2) {
+            match pair {
+                [(Token::Symbol("(" | "[" | "{"), _), _] => depth += 1,
+                [(Token::Symbol(")" | "]" | "}"), _), _] => depth = depth.saturating_sub(1),
+                [(Token::Ident("let" | "const" | "fn"), _), (Token::Ident(name), _)]
+                    if depth == 0 =>
+                {
+                    self.state.record_global(name);
+                }
+                _ => {}
+            }
+        }
+        Ok(ReplResult::Value(Object::Undef))
+    }
+}
+
+impl State {
+    /// Get the known names which complete the given partial identifier, in sorted order.
+    /// A partial containing dots, such as `math.sq`, completes the string keys of the table
+    /// found by following the path before the last dot, e.g., `math.sqrt`.
+    /// Otherwise, candidates are the globals declared through this `State`,
+    /// including the standard libraries and top-level declarations made in a `ReplSession`.
+    #[allow(clippy::missing_panics_doc)] // Every prefix in a valid path is a valid identifier.
+    pub fn completions(&mut self, partial: &str) -> Vec<String> {
+        let Some((path, stem)) = partial.rsplit_once('.') else {
+            return self
+                .globals
+                .iter()
+                .filter(|name| name.starts_with(partial))
+                .cloned()
+                .collect();
+        };
+
+        let mut segments = path.split('.');
+        let root = segments.next().unwrap_or_default();
+        if !crate::is_valid_identifier(root) || self.load_global_slice(root).is_err() {
+            return Vec::new();
+        }
+
+        // Each nested value is left on the stack above its table, or `undef` if the key is missing,
+        // so count the pushed values to restore the stack when we're done.
+        let mut pushed = 1;
+        for segment in segments {
+            // Stops at the first value which isn't a table, which then has no keys to complete.
+            if self
+                .table_get_key(&HashableObject::Str(segment.to_owned()))
+                .is_err()
+            {
+                break;
+            }
+            pushed += 1;
+        }
+
+        let mut keys = if self.is_table() {
+            self.table_str_keys()
+        } else {
+            Vec::new()
+        };
+        for _ in 0..pushed {
+            self.pop();
+        }
+
+        keys.retain(|key| key.starts_with(stem));
+        keys.sort_unstable();
+        keys.into_iter()
+            .map(|key| format!("{path}.{key}"))
+            .collect()
+    }
+
+    /// Collect the string keys of the table on top of the stack, leaving the table in place.
+    fn table_str_keys(&mut self) -> Vec<String> {
+        let mut keys = Vec::new();
+        self.push_undef();
+        while self.table_next() {
+            // Drop the value so that the key is on top for the next iteration.
+            self.pop();
+            if let Some(key) = self.is_str().then(|| self.peek_str()).flatten() {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+}
+
+/// Implement a default session with the standard libraries declared.
+impl Default for ReplSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,182 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    ffi::CStr,
+    os::raw::c_int,
+    path::{Path, PathBuf},
+};
+
+use yaslapi_sys::YASL_State;
+
+use crate::{
+    aux::ScriptError,
+    modules::{pop_str_arg, push_bytes, run_lib_fn, with_hidden_userdata},
+    State, StateError,
+};
+
+/// Name of the hidden global which stores the search paths of `require`.
+const PATHS_GLOBAL: &str = "__yaslapi_require_paths";
+static PATHS_TAG: &CStr = c"yaslapi.require_paths";
+
+/// Name of the hidden global which is `true` once `require_c` is disabled.
+const REQUIRE_C_DISABLED_GLOBAL: &str = "__yaslapi_require_c_disabled";
+
+extern "C" {
+    /// Loads the module at the path on top of the stack. Not part of the generated bindings.
+    fn YASL_require(state: *mut YASL_State) -> c_int;
+}
+
+impl State {
+    /// Replace the global `require` with one which looks for each module `name` at `<dir>/<name>.yasl`,
+    /// in each of the given directories in order, instead of YASL's default paths and the current directory.
+    /// Modules are loaded by YASL's `require` once found, and use its default search paths while they run.
+    #[allow(clippy::missing_panics_doc)] // The globals' names are valid identifiers.
+    pub fn set_require_paths(&mut self, paths: &[impl AsRef<Path>]) {
+        let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+        self.push_userdata_box(paths, PATHS_TAG);
+        self.init_global_slice(PATHS_GLOBAL)
+            .expect("Internal Error: The paths' global is a valid identifier.");
+        self.push_cfunction(require, 1);
+        self.init_global_slice("require")
+            .expect("Internal Error: `require` is a valid identifier.");
+    }
+
+    /// Prevent scripts from loading native code with `__require_c__`, which is replaced by a function that raises an error.
+    /// It stays disabled when the standard libraries are declared again, e.g., by `declare_libs`, or by `require` loading a module.
+    /// Modules loaded by `require` can still use YASL's `__require_c__` while they run,
+    /// so `require` should only be able to reach trusted modules, e.g., with `set_require_paths`.
+    #[allow(clippy::missing_panics_doc)] // The global's name is a valid identifier.
+    pub fn disable_require_c(&mut self) {
+        self.push_bool(true);
+        self.init_global_slice(REQUIRE_C_DISABLED_GLOBAL)
+            .expect("Internal Error: The flag's global is a valid identifier.");
+        self.apply_require_overrides();
+    }
+
+    /// Check whether `disable_require_c` was called for this state.
+    pub fn is_require_c_disabled(&mut self) -> bool {
+        self.get_global(REQUIRE_C_DISABLED_GLOBAL).unwrap_or(false)
+    }
+
+    /// Replace the standard `require` and `__require_c__` again, if they were replaced before
+    /// and YASL has since declared its standard libraries.
+    pub(crate) fn apply_require_overrides(&mut self) {
+        let has_paths = self.load_global_slice(PATHS_GLOBAL).is_ok() && {
+            let is_userdata = self.is_userdata(PATHS_TAG);
+            self.pop();
+            is_userdata
+        };
+        let require_c_disabled = self.is_require_c_disabled();
+        if require_c_disabled {
+            self.push_cfunction(require_c_disabled_fn, 1);
+            self.init_global_slice("__require_c__")
+                .expect("Internal Error: `__require_c__` is a valid identifier.");
+        }
+
+        // Only wrap `require` if it was declared, so that scripts don't gain access to it.
+        let has_require = self.load_global_slice("require").is_ok() && {
+            let is_defined = !self.is_undef();
+            self.pop();
+            is_defined
+        };
+        if has_require && (has_paths || require_c_disabled) {
+            self.push_cfunction(require, 1);
+            let _ = self.set_global_slice("require");
+        }
+    }
+
+    /// Add a directory to the end of the search paths of `require`, like `set_require_paths`.
+    pub fn add_require_path(&mut self, path: impl AsRef<Path>) {
+        let mut paths = self.require_paths();
+        paths.push(path.as_ref().to_path_buf());
+        self.set_require_paths(&paths);
+    }
+
+    /// Get the directories which `require` searches for modules, if they were set by `set_require_paths`.
+    pub fn require_paths(&mut self) -> Vec<PathBuf> {
+        with_hidden_userdata(self, PATHS_GLOBAL, PATHS_TAG, |paths: &mut Vec<PathBuf>| {
+            paths.clone()
+        })
+        .unwrap_or_default()
+    }
+}
+
+/// Implements `require(name)`, which searches the paths set by `State::set_require_paths` if there are any.
+unsafe extern "C" fn require(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        let name = pop_str_arg(state, "require", 0)?;
+        let Ok(path) = with_hidden_userdata(
+            state,
+            PATHS_GLOBAL,
+            PATHS_TAG,
+            |paths: &mut Vec<PathBuf>| {
+                paths
+                    .iter()
+                    .map(|dir| dir.join(format!("{name}.yasl")))
+                    .find(|path| path.is_file())
+            },
+        ) else {
+            // Without search paths, YASL finds the module itself.
+            state.push_str(&name);
+            return Ok(0);
+        };
+        let path = path.ok_or_else(|| {
+            ScriptError::new(
+                StateError::Generic,
+                format!("Error: could not open package {name}."),
+            )
+        })?;
+
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+        #[cfg(not(unix))]
+        let bytes = path
+            .to_str()
+            .ok_or_else(|| {
+                ScriptError::new(
+                    StateError::ValueError,
+                    format!("ValueError: require can't load package {name} from its path."),
+                )
+            })?
+            .as_bytes();
+        push_bytes(state, bytes);
+        Ok(0)
+    });
+
+    // YASL declares its standard libraries in the globals while loading a module, which replaces this function.
+    let returns = unsafe { YASL_require(state) };
+    State::from_memory(state)
+        .expect("State is null")
+        .apply_require_overrides();
+    returns
+}
+
+/// Replaces `__require_c__` once `State::disable_require_c` is called.
+unsafe extern "C" fn require_c_disabled_fn(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |_| {
+        Err(ScriptError::new(
+            StateError::Generic,
+            "Error: __require_c__ is disabled.",
+        ))
+    })
+}
@@ -0,0 +1,144 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Helpers for checking that values survive being pushed to a YASL stack and popped back.
+//!
+//! With the `proptest` feature, this module also provides strategies which generate the plain data that
+//! YASL can hold, so conversions can be checked against many values at once:
+//!
+//! ```ignore
+//! proptest::proptest! {
+//!     #[test]
+//!     fn objects_round_trip(object in yaslapi::roundtrip::any_object()) {
+//!         yaslapi::roundtrip::assert_roundtrip(&object);
+//!     }
+//! }
+//! ```
+
+#[cfg(feature = "proptest")]
+use std::collections::HashMap;
+
+#[cfg(feature = "proptest")]
+use proptest::{collection, num, prelude::*};
+
+#[cfg(feature = "proptest")]
+use crate::aux::{HashableF64, HashableObject};
+use crate::{aux::Object, State};
+
+/// The deepest nesting of lists and tables in an object from `any_object`.
+#[cfg(feature = "proptest")]
+const MAX_DEPTH: u32 = 4;
+
+/// Push the object to a new state, pop it back, and assert that the result is the same object.
+/// Unlike `==`, this requires ints and floats to keep their type, and floats to keep their exact value,
+/// so `NaN` matches `NaN` but `0.0` doesn't match `-0.0`.
+/// # Panics
+/// Panics if the object can't be pushed or popped, or if the popped object differs from the original.
+pub fn assert_roundtrip(object: &Object) {
+    let mut state = State::default();
+    if let Err(e) = state.push_object(object) {
+        panic!("failed to push {object}: {e:?}");
+    }
+    match state.pop_object(None) {
+        Ok(popped) => assert!(
+            is_same(object, &popped),
+            "round trip changed the object\n  pushed: {object}\n  popped: {popped}"
+        ),
+        Err(e) => panic!("failed to pop {object}: {e:?}"),
+    }
+}
+
+/// Whether the objects have the same types and values throughout.
+fn is_same(a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (Object::Float(a), Object::Float(b)) => is_same_float(*a, *b),
+        (Object::List(a), Object::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| is_same(a, b))
+        }
+        (Object::Table(a), Object::Table(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, v)| b.get(k).is_some_and(|w| is_same(v, w)))
+        }
+        (Object::Float(_) | Object::List(_) | Object::Table(_), _) => false,
+        _ => a == b,
+    }
+}
+
+/// Whether the floats have the same bits, treating all `NaN`s as the same.
+fn is_same_float(a: f64, b: f64) -> bool {
+    a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+}
+
+/// Generate objects with at most four levels of nested lists and tables, and never user data or pointers.
+/// Table keys come from `any_key`, and table values are never `undef`, which YASL would remove.
+/// Strings never contain zero bytes, since popping a string stops at the first one.
+#[cfg(feature = "proptest")]
+pub fn any_object() -> impl Strategy<Value = Object> {
+    let leaf = prop_oneof![
+        Just(Object::Undef),
+        any::<bool>().prop_map(Object::Bool),
+        any::<i64>().prop_map(Object::Int),
+        num::f64::ANY.prop_map(Object::Float),
+        any_str().prop_map(Object::Str),
+    ];
+    leaf.prop_recursive(MAX_DEPTH, 64, 8, |inner| {
+        prop_oneof![
+            collection::vec(inner.clone(), 0..8).prop_map(Object::List),
+            collection::vec((any_key(), inner), 0..8).prop_map(|entries| {
+                Object::Table(
+                    entries
+                        .into_iter()
+                        .map(|(k, v)| match v {
+                            Object::Undef => (k, Object::Bool(false)),
+                            v => (k, v),
+                        })
+                        .collect::<HashMap<_, _>>(),
+                )
+            }),
+        ]
+    })
+}
+
+/// Generate table keys which YASL can iterate over: bools, ints, strings without zero bytes, and floats
+/// other than `NaN`. A `NaN` key is never equal to itself, so YASL can't find it again to continue iterating.
+#[cfg(feature = "proptest")]
+pub fn any_key() -> impl Strategy<Value = HashableObject> {
+    let float = num::f64::POSITIVE
+        | num::f64::NEGATIVE
+        | num::f64::NORMAL
+        | num::f64::SUBNORMAL
+        | num::f64::ZERO
+        | num::f64::INFINITE;
+    prop_oneof![
+        any::<bool>().prop_map(HashableObject::Bool),
+        any::<i64>().prop_map(HashableObject::Int),
+        float.prop_map(|f| HashableObject::Float(HashableF64(f))),
+        any_str().prop_map(HashableObject::Str),
+    ]
+}
+
+/// Generate strings without zero bytes.
+#[cfg(feature = "proptest")]
+fn any_str() -> impl Strategy<Value = String> {
+    any::<String>().prop_map(|s| s.replace('\0', ""))
+}
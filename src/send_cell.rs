@@ -0,0 +1,44 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A wrapper for moving values with raw pointers, such as `State`s and `Object`s, between threads.
+
+/// Wrapper for moving values which aren't `Send` because they hold raw pointers, e.g., a `State` or `Object`s
+/// with user data, to and from the thread which uses them.
+/// Only values whose pointers are never dereferenced by two threads at once may be wrapped, i.e., a `State` which
+/// is handed over to another thread, values which refer to data owned by such a `State`, or data that only the host dereferences.
+/// The data a `State` owns is `Send` itself, since `State::push_userdata_box` requires it, and `State::push_userdata`
+/// makes its callers guarantee it.
+pub(crate) struct SendCell<T>(pub(crate) T);
+
+// SAFETY: Wrapped values are only moved between threads, never shared, and their pointers are only dereferenced
+// by whichever thread currently has exclusive use of the `State` or host data they point to.
+// Any user data owned by a `State` is `Send`, so it may be dropped or used by the thread the state is moved to.
+unsafe impl<T> Send for SendCell<T> {}
+
+impl<T> SendCell<T> {
+    /// Unwrap the inner value. Closures must call this instead of destructuring,
+    /// which would only capture the non-`Send` field.
+    pub(crate) fn into_inner(self) -> T {
+        self.0
+    }
+}
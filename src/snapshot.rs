@@ -0,0 +1,136 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Capturing the values of a state's globals, so that they can be rolled back later,
+//! e.g., after each input to a REPL or between test cases.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{aux::Object, State, StateError, Type};
+
+/// Lists and tables nested deeper than this are not captured, which also excludes self-referencing values.
+const MAX_DEPTH: usize = 64;
+
+/// The values of a state's globals at some point in time, created by `State::snapshot_globals`.
+/// Only globals which can be represented as an `Object` are captured. Globals holding functions
+/// or userdata, even nested within a list or table, are left untouched when the snapshot is restored.
+#[derive(Clone, Debug, Default)]
+pub struct GlobalsSnapshot {
+    values: BTreeMap<String, Object>,
+    skipped: BTreeSet<String>,
+}
+
+impl GlobalsSnapshot {
+    /// Get the captured value of the global `name`, if it was captured.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Object> {
+        self.values.get(name)
+    }
+
+    /// Iterate over the captured globals and their values, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Object)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+impl State {
+    /// Capture the values of the globals known to this `State`, i.e., those declared through this API,
+    /// by the standard libraries, or at the top level of a `ReplSession`.
+    #[allow(clippy::missing_panics_doc)] // Known globals always exist and are valid identifiers.
+    pub fn snapshot_globals(&mut self) -> GlobalsSnapshot {
+        let mut snapshot = GlobalsSnapshot::default();
+        for name in self.globals.clone() {
+            if self.load_global_slice(&name).is_err() {
+                continue;
+            }
+            if self.is_plain_data(0) {
+                if let Ok(value) = self.pop_object(None) {
+                    snapshot.values.insert(name, value);
+                    continue;
+                }
+            } else {
+                self.pop();
+            }
+            snapshot.skipped.insert(name);
+        }
+        snapshot
+    }
+
+    /// Restore the globals captured in the snapshot. Known globals which were declared after
+    /// the snapshot was taken are set to `undef`, since YASL can't remove a declared global.
+    /// Constants are never modified.
+    /// Lists and tables are restored as copies, so references to the previous values are not updated.
+    /// # Errors
+    /// Will return an error if a captured value can no longer be pushed to the stack.
+    pub fn restore_globals(&mut self, snapshot: &GlobalsSnapshot) -> Result<(), StateError> {
+        for name in self.globals.clone() {
+            match snapshot.values.get(&name) {
+                Some(value) => self.push_object(value)?,
+                None if !snapshot.skipped.contains(&name) => self.push_undef(),
+                None => continue,
+            }
+
+            // The value is only popped if the global could be set, e.g., not for constants.
+            if self.set_global_slice(&name).is_err() {
+                self.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the value on top of the stack can be converted to an `Object` and back without losing data.
+    fn is_plain_data(&mut self, depth: usize) -> bool {
+        match self.peek_type() {
+            Type::Undef | Type::Bool | Type::Int | Type::Float | Type::Str | Type::UserPtr => true,
+            Type::List if depth < MAX_DEPTH => {
+                self.clone_top();
+                self.len();
+                let n = self.pop_int();
+                (0..n).all(|i| {
+                    #[allow(clippy::cast_possible_truncation)]
+                    if self.list_get(i as isize).is_err() {
+                        return false;
+                    }
+                    let plain = self.is_plain_data(depth + 1);
+                    self.pop();
+                    plain
+                })
+            }
+            Type::Table if depth < MAX_DEPTH => {
+                self.push_undef();
+                while self.table_next() {
+                    let plain = self.is_plain_data(depth + 1);
+                    self.pop();
+                    if !plain {
+                        // Pop the key which would otherwise be consumed by `table_next`.
+                        self.pop();
+                        return false;
+                    }
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
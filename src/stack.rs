@@ -0,0 +1,376 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bookkeeping of the height of the YASL stack, which the C API does not expose directly.
+
+use std::{
+    ffi::CString,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::LazyLock,
+};
+
+use crate::{aux::Object, pretty::format_float, State, StateError, Type, HIDDEN_GLOBAL_PREFIX};
+
+/// Longest value preview shown by `StackDump`, in characters.
+const PREVIEW_LEN: usize = 40;
+
+/// Number of slots of YASL's stack, which bounds how many values can be rotated at once.
+const STACK_SIZE: usize = 1024;
+
+/// Names of the hidden globals which `State::rotate` moves values through, shared by every state.
+/// The deepest value is stored in the first scratch global.
+static SCRATCH_GLOBALS: LazyLock<Vec<CString>> = LazyLock::new(|| {
+    (0..STACK_SIZE)
+        .map(|i| CString::new(format!("{HIDDEN_GLOBAL_PREFIX}stack_{i}")).unwrap())
+        .collect()
+});
+
+/// Floats pushed to find the top of the current frame. They are subnormal, so scripts rarely produce them,
+/// and are only matched as a pair, so a single copy left on the stack isn't mistaken for the top.
+const FRAME_MARKERS: [f64; 2] = [
+    f64::from_bits(0x0000_5941_534c_0001),
+    f64::from_bits(0x0000_5941_534c_0002),
+];
+
+/// Guard which records the height of the stack and pops any values above it when dropped,
+/// so that early returns in host code can't leave stray values on the stack.
+/// Dereferences to the underlying `State`.
+///
+/// ```
+/// use yaslapi::State;
+///
+/// let mut state = State::default();
+/// {
+///     let mut guard = state.stack_guard();
+///     guard.push_int(1);
+///     guard.push_int(2);
+/// }
+/// assert_eq!(state.stack_len(), 0);
+/// ```
+pub struct StackGuard<'a> {
+    state: &'a mut State,
+    height: usize,
+}
+
+/// A single slot of the stack, as captured by `State::stack_dump`.
+#[derive(Clone, Debug)]
+pub struct StackSlot {
+    /// Index of the slot, counting from the bottom of the current frame.
+    pub index: usize,
+    /// The type name of the value, which is the tag for user-data.
+    pub type_name: String,
+    /// A short rendering of the value. Strings, lists, and tables can only be read from the top
+    /// of the stack, so this is `None` for those values anywhere else.
+    pub preview: Option<String>,
+}
+
+/// The slots of the stack at some point in time, with the top of the stack last.
+/// Displays one slot per line, e.g., `[0] int: 3`.
+#[derive(Clone, Debug)]
+pub struct StackDump(pub Vec<StackSlot>);
+
+impl fmt::Display for StackDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for slot in &self.0 {
+            write!(f, "[{}] {}", slot.index, slot.type_name)?;
+            if let Some(preview) = &slot.preview {
+                write!(f, ": {preview}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    /// Capture the type and a preview of every value on the stack, for debugging.
+    /// Covers the `stack_len()` slots at the bottom of the current frame.
+    #[must_use]
+    pub fn stack_dump(&mut self) -> StackDump {
+        let len = self.stack_len;
+        let slots = (0..len)
+            .map(|index| {
+                let preview = match self.peek_n_type(index) {
+                    Type::Bool => Some(self.peek_n_bool(index).to_string()),
+                    Type::Int => Some(self.peek_n_int(index).to_string()),
+                    Type::Float => Some(format_float(self.peek_n_float(index))),
+                    Type::Undef => None,
+                    _ if index + 1 == len => self.top_preview(),
+                    _ => None,
+                };
+                StackSlot {
+                    index,
+                    type_name: self
+                        .peek_n_typename_slice(index)
+                        .unwrap_or("unknown")
+                        .to_owned(),
+                    preview,
+                }
+            })
+            .collect();
+        StackDump(slots)
+    }
+
+    /// Render every value on the stack with its index, type name, and a preview, one per line.
+    /// See `stack_dump` for details.
+    #[must_use]
+    pub fn dump_stack(&mut self) -> String {
+        self.stack_dump().to_string()
+    }
+
+    /// Render the top of the stack, shortened to `PREVIEW_LEN` characters.
+    fn top_preview(&mut self) -> Option<String> {
+        let preview = match self.peek_type() {
+            Type::Str | Type::List | Type::Table | Type::UserPtr => {
+                self.peek_object().ok().map(|object| match object {
+                    Object::Str(s) => format!("{s:?}"),
+                    object => object.to_yasl_string(),
+                })
+            }
+            _ => None,
+        }?;
+
+        Some(if preview.chars().count() > PREVIEW_LEN {
+            let mut short = preview.chars().take(PREVIEW_LEN).collect::<String>();
+            short.push_str("...");
+            short
+        } else {
+            preview
+        })
+    }
+
+    /// Create a guard which restores the stack to its current height when dropped.
+    /// The height is tracked by this `State`, so only values pushed and popped through its API are counted.
+    pub fn stack_guard(&mut self) -> StackGuard<'_> {
+        StackGuard {
+            height: self.stack_len,
+            state: self,
+        }
+    }
+
+    /// Get the number of values on the stack, as tracked by this `State`.
+    /// Only values pushed and popped through this API are counted. For a `State` created from
+    /// a raw pointer, values already on the stack (e.g., function arguments) are not included.
+    /// After a runtime error the count may be inaccurate, since YASL does not unwind the stack.
+    #[must_use]
+    pub fn stack_len(&self) -> usize {
+        self.stack_len
+    }
+
+    /// Get the index of the top of the stack, counting from the bottom, or `None` if the stack is empty.
+    /// Has the same limitations as `stack_len`.
+    #[must_use]
+    pub fn top_index(&self) -> Option<usize> {
+        self.stack_len.checked_sub(1)
+    }
+
+    /// Rotate the top `n` values of the stack by `k` positions towards the top, wrapping around.
+    /// A negative `k` rotates towards the bottom instead. E.g., rotating `[a, b, c]` (with `c` on top)
+    /// by `1` gives `[c, a, b]`. Values are moved rather than copied, so tables and functions keep their identity.
+    /// Unlike `stack_len`, this counts every value of the current frame, so the arguments of a C function
+    /// can be reordered through a `State` created from a raw pointer.
+    /// # Errors
+    /// Will return `StateError::ValueError` if `n` is larger than the number of values in the current frame.
+    pub fn rotate(&mut self, n: usize, k: isize) -> Result<(), StateError> {
+        if n > self.frame_len() {
+            return Err(StateError::ValueError);
+        }
+        if n == 0 {
+            return Ok(());
+        }
+
+        // There is no API to index the stack, so move each value into a hidden global.
+        // Moving values which this `State` didn't push would change the tracked height, so it is restored afterwards.
+        let height = self.stack_len;
+        let names = &SCRATCH_GLOBALS[..n];
+        for name in names.iter().rev() {
+            // YASL copies the name when declaring a global, and declaring it again has no effect.
+            unsafe { yaslapi_sys::YASL_declglobal(self.state.as_ptr(), name.as_ptr()) };
+            self.set_global(name)?;
+        }
+
+        // Push the values back in their new order, then release the scratch globals.
+        #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+        let shift = k.rem_euclid(n as isize) as usize;
+        for i in 0..n {
+            self.load_global(&names[(i + n - shift) % n])?;
+        }
+        for name in names {
+            self.push_undef();
+            self.set_global(name)?;
+        }
+        self.stack_len = height;
+        Ok(())
+    }
+
+    /// Count the values of the current frame, including those which this `State` didn't push.
+    /// YASL's API doesn't expose the height of the stack, so a pair of markers is pushed and the frame is
+    /// searched from the bottom for them. Popped values are left above the top, so they are only reached
+    /// after the markers.
+    fn frame_len(&mut self) -> usize {
+        for marker in FRAME_MARKERS {
+            self.push_float(marker);
+        }
+        let mut len = 0;
+        while !(self.is_n_marker(len, FRAME_MARKERS[0])
+            && self.is_n_marker(len + 1, FRAME_MARKERS[1]))
+        {
+            len += 1;
+        }
+        self.pop();
+        self.pop();
+        len
+    }
+
+    /// Whether the value at index `n` of the current frame is the float `marker`, bit for bit.
+    fn is_n_marker(&mut self, n: usize, marker: f64) -> bool {
+        self.is_n_float(n) && self.peek_n_float(n).to_bits() == marker.to_bits()
+    }
+
+    /// Move the top of the stack down so that `n` values are above it.
+    /// E.g., inserting at `2` in `[a, b, c]` (with `c` on top) gives `[c, a, b]`.
+    /// # Errors
+    /// Will return `StateError::ValueError` if there are not more than `n` values in the current frame.
+    pub fn insert(&mut self, n: usize) -> Result<(), StateError> {
+        self.rotate(n + 1, 1)
+    }
+
+    /// Remove the value with `n` values above it from the stack.
+    /// E.g., removing at `1` in `[a, b, c]` (with `c` on top) gives `[a, c]`.
+    /// # Errors
+    /// Will return `StateError::ValueError` if there are not more than `n` values in the current frame.
+    pub fn remove(&mut self, n: usize) -> Result<(), StateError> {
+        self.rotate(n + 1, -1)?;
+        self.pop();
+        Ok(())
+    }
+
+    /// Render the value at index `n`, counting from the bottom of the current frame, as YASL's `tostr` would,
+    /// with the same caveats as `stringify_top`. The stack is left as it was.
+    /// Zero bytes are kept, and invalid UTF-8 is replaced with `U+FFFD`.
+    /// # Errors
+    /// Will return `StateError::ValueError` if `n` is not less than `stack_len()`.
+    pub fn to_display_string(&mut self, n: usize) -> Result<String, StateError> {
+        let len = self
+            .stack_len
+            .checked_sub(n)
+            .ok_or(StateError::ValueError)?;
+        if len == 0 {
+            return Err(StateError::ValueError);
+        }
+
+        // Bring the value to the top to stringify a copy of it, then move it back.
+        self.rotate(len, -1)?;
+        self.clone_top();
+        self.stringify_top();
+        let bytes = crate::modules::pop_bytes_arg(self, "tostr", 0).unwrap_or_default();
+        self.rotate(len, 1)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Get the length in bytes of the string on top of the stack without copying it out of the VM,
+    /// or `None` if the top of the stack is not a string. The stack is left as it was.
+    pub fn peek_str_len(&mut self) -> Option<usize> {
+        if !self.is_str() {
+            return None;
+        }
+        self.clone_top();
+        self.len();
+        usize::try_from(self.pop_int()).ok()
+    }
+
+    /// Get the length in bytes of the string at index `n`, counting from the bottom of the current frame,
+    /// or `None` if that value is not a string or `n` is not less than `stack_len()`. The stack is left as it was.
+    pub fn peek_n_str_len(&mut self, n: usize) -> Option<usize> {
+        let len = self.stack_len.checked_sub(n).filter(|&len| len > 0)?;
+        if !self.is_n_str(n) {
+            return None;
+        }
+
+        // Strings can only be read from the top of the stack, so bring the value there and back.
+        self.rotate(len, -1).ok()?;
+        let str_len = self.peek_str_len();
+        self.rotate(len, 1).ok()?;
+        str_len
+    }
+
+    /// Replace the top `n` values of the stack with one string joining them in order, as YASL's `~` would,
+    /// with each value rendered by `stringify_top`. E.g., concatenating `[1, 'a', 2.5]` (with `2.5` on top)
+    /// by `3` gives `'1a2.5'`. Concatenating zero values pushes an empty string.
+    /// # Errors
+    /// Will return `StateError::ValueError` if `n` is larger than `stack_len()`.
+    pub fn concat_top(&mut self, n: usize) -> Result<(), StateError> {
+        if n > self.stack_len {
+            return Err(StateError::ValueError);
+        }
+
+        // Values are popped from the top, so the parts are collected in reverse.
+        let mut parts = Vec::with_capacity(n);
+        for _ in 0..n {
+            self.stringify_top();
+            parts.push(crate::modules::pop_bytes_arg(self, "tostr", 0).unwrap_or_default());
+        }
+        let bytes = parts.into_iter().rev().flatten().collect::<Vec<_>>();
+        crate::modules::push_bytes(self, &bytes);
+        Ok(())
+    }
+
+    /// Swap the top two values of the stack.
+    /// # Errors
+    /// Will return `StateError::ValueError` if there are fewer than two values in the current frame.
+    pub fn swap_top_two(&mut self) -> Result<(), StateError> {
+        self.rotate(2, 1)
+    }
+
+    /// Record that `n` values were pushed to the stack.
+    pub(crate) fn grow_stack(&mut self, n: usize) {
+        self.stack_len += n;
+    }
+
+    /// Record that `n` values were popped from the stack. A `State` created from a raw pointer doesn't
+    /// know about values which were already on the stack, such as function arguments, so this saturates at zero.
+    pub(crate) fn shrink_stack(&mut self, n: usize) {
+        self.stack_len = self.stack_len.saturating_sub(n);
+    }
+}
+
+impl Deref for StackGuard<'_> {
+    type Target = State;
+    fn deref(&self) -> &Self::Target {
+        self.state
+    }
+}
+impl DerefMut for StackGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.state
+    }
+}
+
+/// Pop every value pushed since the guard was created.
+impl Drop for StackGuard<'_> {
+    fn drop(&mut self) {
+        while self.state.stack_len > self.height {
+            self.state.pop();
+        }
+    }
+}
@@ -0,0 +1,647 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A lightweight scanner for YASL source code. It does not replace the YASL compiler,
+//! but recognizes enough of the lexical structure (strings, comments, brackets, and operators)
+//! to answer questions about source without compiling it.
+
+use std::collections::BTreeSet;
+
+/// Operators made of more than one character, ordered so that longer operators match first.
+const MULTI_CHAR_SYMBOLS: [&str; 33] = [
+    "**=", "//=", "<<=", ">>=", "&&=", "||=", "??=", "&^=", "===", "!==", "->", "**", "//", "<<",
+    ">>", "&&", "||", "??", "&^", "==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "%=", "&=", "|=",
+    "^=", "~=", "..",
+];
+
+/// Words with a special meaning to the YASL compiler, which cannot be used as identifiers.
+pub(crate) const KEYWORDS: [&str; 22] = [
+    "assert",
+    "break",
+    "const",
+    "continue",
+    "echo",
+    "else",
+    "elseif",
+    "elseifdef",
+    "export",
+    "false",
+    "fn",
+    "for",
+    "if",
+    "ifdef",
+    "in",
+    "len",
+    "let",
+    "match",
+    "return",
+    "true",
+    "undef",
+    "while",
+];
+
+/// Keywords which can never end a statement.
+const CONTINUATION_KEYWORDS: [&str; 15] = [
+    "assert",
+    "const",
+    "echo",
+    "else",
+    "elseif",
+    "elseifdef",
+    "export",
+    "fn",
+    "for",
+    "if",
+    "ifdef",
+    "in",
+    "let",
+    "match",
+    "while",
+];
+
+/// A single lexical token of YASL source.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Token<'a> {
+    /// An identifier or keyword.
+    Ident(&'a str),
+    /// A numeric literal.
+    Number(&'a str),
+    /// A string literal of any kind.
+    Str,
+    /// An operator or punctuation.
+    Symbol(&'a str),
+    /// A line break, which YASL may treat as the end of a statement.
+    Newline,
+}
+
+/// The result of scanning a piece of source code.
+#[derive(Debug, Default)]
+pub(crate) struct Scan<'a> {
+    /// The tokens of the source paired with the line they appear on, starting from `1`.
+    pub tokens: Vec<(Token<'a>, usize)>,
+    /// The range of bytes of the source covered by each token, in the same order as `tokens`.
+    pub spans: Vec<(usize, usize)>,
+    /// Brackets which were opened but never closed, in the order they were opened.
+    pub open_brackets: Vec<char>,
+    /// Whether the source ended inside a raw string or block comment, which may span lines.
+    pub unterminated: bool,
+}
+
+impl Token<'_> {
+    /// Whether this token is an assignment operator, such as `=` or `+=`.
+    pub(crate) fn is_assignment(&self) -> bool {
+        matches!(self, Token::Symbol(s) if s.ends_with('=') && !matches!(*s, "==" | "!=" | "<=" | ">=" | "===" | "!=="))
+    }
+}
+
+impl<'a> Scan<'a> {
+    /// Add a token which covers the bytes from `start` to `end` of the source.
+    fn push(&mut self, token: Token<'a>, line: usize, start: usize, end: usize) {
+        self.tokens.push((token, line));
+        self.spans.push((start, end));
+    }
+
+    /// Whether the scanned source is unfinished, so that more input could complete it.
+    pub(crate) fn is_incomplete(&self) -> bool {
+        if self.unterminated || !self.open_brackets.is_empty() {
+            return true;
+        }
+
+        // A trailing binary operator, separator, or keyword which must be followed by more code
+        // means the statement continues on the next line.
+        let last = self.tokens.iter().rev().find(|(t, _)| *t != Token::Newline);
+        match last {
+            Some((Token::Symbol(s), _)) => !matches!(*s, ")" | "]" | "}" | ";"),
+            Some((Token::Ident(word), _)) => CONTINUATION_KEYWORDS.contains(word),
+            _ => false,
+        }
+    }
+
+    /// Whether the scanned source stays within a pair of parentheses wrapped around it, i.e., it never closes
+    /// a bracket it didn't open, leaves no bracket, raw string, or block comment open, and has no `;` outside of brackets.
+    pub(crate) fn is_self_contained(&self) -> bool {
+        if self.unterminated || !self.open_brackets.is_empty() {
+            return false;
+        }
+
+        let mut depth = 0usize;
+        for (token, _) in &self.tokens {
+            match token {
+                Token::Symbol("(" | "[" | "{") => depth += 1,
+                Token::Symbol(")" | "]" | "}") => match depth.checked_sub(1) {
+                    Some(d) => depth = d,
+                    None => return false,
+                },
+                Token::Symbol(";") if depth == 0 => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// The byte offset just past the last token of the source, which excludes trailing comments and whitespace.
+    pub(crate) fn code_end(&self) -> usize {
+        self.tokens
+            .iter()
+            .zip(&self.spans)
+            .rev()
+            .find(|((token, _), _)| *token != Token::Newline)
+            .map_or(0, |(_, &(_, end))| end)
+    }
+
+    /// Whether the scanned source is a single expression, as opposed to one or more statements.
+    pub(crate) fn is_expression(&self) -> bool {
+        let mut depth = 0usize;
+        let mut tokens = self.tokens.iter().map(|(t, _)| t).peekable();
+
+        // Statements are introduced by keywords which can't begin an expression.
+        // A function is only an expression when it is anonymous, i.e., not `fn name(..)`.
+        if let [(Token::Ident("fn"), _), (Token::Ident(_), _), ..] = self.tokens.as_slice() {
+            return false;
+        }
+        match tokens.peek() {
+            None => return false,
+            Some(Token::Ident(word))
+                if KEYWORDS.contains(word)
+                    && !matches!(*word, "true" | "false" | "undef" | "len" | "fn") =>
+            {
+                return false
+            }
+            _ => {}
+        }
+
+        let mut ended = false;
+        for token in tokens {
+            match token {
+                Token::Symbol("(" | "[" | "{") => depth += 1,
+                Token::Symbol(")" | "]" | "}") => depth = depth.saturating_sub(1),
+                Token::Newline | Token::Symbol(";") if depth == 0 => {
+                    ended = true;
+                    continue;
+                }
+                t if depth == 0 && t.is_assignment() => return false,
+                _ => {}
+            }
+
+            // Any token after the end of the first statement makes this a sequence of statements.
+            if ended {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// How long the names bound by a scope stay visible.
+#[derive(Clone, Copy, PartialEq)]
+enum ScopeEnd {
+    /// Until the bracket which opened the scope is closed.
+    Bracket,
+    /// Until the end of the statement, as for the parameters of `fn(x) -> x + 1`.
+    Statement,
+    /// Until the end of the next block, as for the variable of `for let i = 0; i < n; i += 1 { .. }`.
+    Block,
+}
+
+/// Names bound by the script within some region of the source.
+struct Scope<'a> {
+    names: Vec<&'a str>,
+    /// The number of brackets enclosing the scope.
+    depth: usize,
+    end: ScopeEnd,
+}
+
+/// The globals which a piece of source reads and assigns, found by `Scan::globals`.
+#[derive(Debug, Default)]
+pub(crate) struct GlobalUses<'a> {
+    pub reads: BTreeSet<&'a str>,
+    pub assigns: BTreeSet<&'a str>,
+}
+
+impl<'a> Scan<'a> {
+    /// Find the identifiers which refer to globals, i.e., which aren't bound by `let`, `const`, `fn`, `for`,
+    /// function parameters, or `match` patterns in an enclosing scope. Fields and method names are ignored.
+    pub(crate) fn globals(&self) -> GlobalUses<'a> {
+        let tokens = self.tokens.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        let ident = |i: usize| match tokens.get(i) {
+            Some(Token::Ident(word)) if !KEYWORDS.contains(word) => Some(*word),
+            _ => None,
+        };
+
+        let mut uses = GlobalUses::default();
+        let mut scopes = vec![Scope {
+            names: Vec::new(),
+            depth: 0,
+            end: ScopeEnd::Bracket,
+        }];
+        let mut depth = 0usize;
+        // Names bound by the next block, e.g., function parameters and loop variables.
+        let mut pending = Vec::new();
+        // Names declared by `let` or `const`, which are bound once their statement ends.
+        let mut declared: Vec<(&str, usize)> = Vec::new();
+        // The depth of the brackets enclosing a function's parameters, while they are being read.
+        let mut params = None;
+        let mut fn_header = false;
+        let mut at_start = true;
+        let mut prev = None;
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i];
+            let start = std::mem::take(&mut at_start);
+            i += 1;
+            match token {
+                Token::Newline if !ends_statement(prev) => {
+                    at_start = start;
+                    continue;
+                }
+                Token::Newline | Token::Symbol(";") => {
+                    while scopes
+                        .last()
+                        .is_some_and(|s| s.end == ScopeEnd::Statement && s.depth == depth)
+                    {
+                        scopes.pop();
+                    }
+                    let scope = scopes
+                        .last_mut()
+                        .expect("the outermost scope is never closed");
+                    declared.retain(|&(name, d)| {
+                        if d == depth {
+                            scope.names.push(name);
+                        }
+                        d != depth
+                    });
+                    at_start = true;
+                }
+                Token::Symbol(open @ ("(" | "[" | "{")) => {
+                    let mut names = comprehension_names(&tokens[i..]);
+                    if open == "{" {
+                        names.append(&mut pending);
+                        names.extend(
+                            declared
+                                .iter()
+                                .filter(|&&(_, d)| d == depth)
+                                .map(|&(name, _)| name),
+                        );
+                        at_start = true;
+                    } else if open == "(" && fn_header {
+                        params = Some(depth + 1);
+                    }
+                    fn_header = false;
+                    depth += 1;
+                    scopes.push(Scope {
+                        names,
+                        depth,
+                        end: ScopeEnd::Bracket,
+                    });
+                }
+                Token::Symbol(close @ (")" | "]" | "}")) => {
+                    while scopes.len() > 1
+                        && scopes.last().is_some_and(|s| {
+                            s.depth >= depth
+                                || (s.end == ScopeEnd::Block
+                                    && s.depth + 1 == depth
+                                    && close == "}")
+                        })
+                    {
+                        scopes.pop();
+                    }
+                    declared.retain(|&(_, d)| d < depth);
+                    depth = depth.saturating_sub(1);
+
+                    if params.is_some_and(|p| p == depth + 1) {
+                        params = None;
+                        // A function with an arrow body has no block for its parameters to be bound by.
+                        if tokens.get(i) == Some(&Token::Symbol("->")) {
+                            scopes.push(Scope {
+                                names: std::mem::take(&mut pending),
+                                depth,
+                                end: ScopeEnd::Statement,
+                            });
+                            i += 1;
+                        }
+                    }
+                    at_start = close == "}";
+                }
+                // Field and method names follow `.` and `->`.
+                Token::Symbol("." | "->") if ident(i).is_some() => i += 1,
+                Token::Ident(word) if params == Some(depth) => {
+                    pending.extend((word != "const").then_some(word));
+                }
+                Token::Ident("let" | "const") => {
+                    let for_header = prev == Some(Token::Ident("for"));
+                    while let Some(name) = ident(i) {
+                        if for_header {
+                            scopes.push(Scope {
+                                names: vec![name],
+                                depth,
+                                end: ScopeEnd::Block,
+                            });
+                        } else {
+                            declared.push((name, depth));
+                        }
+                        i += 1;
+                        if tokens.get(i) != Some(&Token::Symbol(",")) || ident(i + 1).is_none() {
+                            break;
+                        }
+                        i += 1;
+                    }
+                }
+                Token::Ident("for") => {
+                    if let Some(name) = ident(i) {
+                        // Comprehension variables were already bound by the enclosing bracket.
+                        if start {
+                            pending.push(name);
+                        }
+                        i += 1;
+                    }
+                }
+                Token::Ident("fn") => {
+                    fn_header = true;
+                    if let Some(name) = ident(i) {
+                        i += 1;
+                        if tokens.get(i) == Some(&Token::Symbol(".")) {
+                            // `fn table.method(..)` sets a field of the table.
+                            if !is_bound(&scopes, name) {
+                                uses.reads.insert(name);
+                            }
+                        } else {
+                            scopes
+                                .last_mut()
+                                .expect("the outermost scope is never closed")
+                                .names
+                                .push(name);
+                        }
+                    }
+                }
+                Token::Ident(word) if KEYWORDS.contains(&word) => {}
+                Token::Ident(word) => {
+                    // A statement may start by assigning to a comma-separated list of names.
+                    let mut targets = vec![word];
+                    let mut j = i;
+                    while start && tokens.get(j) == Some(&Token::Symbol(",")) {
+                        let Some(name) = ident(j + 1) else { break };
+                        targets.push(name);
+                        j += 2;
+                    }
+                    match tokens.get(j) {
+                        Some(op) if start && op.is_assignment() => {
+                            for name in targets {
+                                if !is_bound(&scopes, name) {
+                                    uses.assigns.insert(name);
+                                    if *op != Token::Symbol("=") {
+                                        uses.reads.insert(name);
+                                    }
+                                }
+                            }
+                            i = j;
+                        }
+                        _ if !is_bound(&scopes, word) => {
+                            uses.reads.insert(word);
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+            prev = Some(token);
+        }
+        uses
+    }
+}
+
+/// Whether the name is bound by any enclosing scope.
+fn is_bound(scopes: &[Scope<'_>], name: &str) -> bool {
+    scopes.iter().any(|s| s.names.contains(&name))
+}
+
+/// Whether a line break after the given token ends the statement.
+fn ends_statement(prev: Option<Token<'_>>) -> bool {
+    match prev {
+        Some(Token::Symbol(s)) => matches!(s, ")" | "]" | "}" | ";"),
+        Some(Token::Ident(word)) => !CONTINUATION_KEYWORDS.contains(&word),
+        _ => true,
+    }
+}
+
+/// Find the variables bound by a comprehension, e.g., `x` in `[x * 2 for x in xs]`, given the
+/// tokens after the opening bracket. A `for` which starts a statement is a loop instead.
+fn comprehension_names<'a>(tokens: &[Token<'a>]) -> Vec<&'a str> {
+    let mut names = Vec::new();
+    let mut depth = 0usize;
+    let mut prev = None;
+    for window in tokens.windows(2) {
+        match window[0] {
+            Token::Symbol("(" | "[" | "{") => depth += 1,
+            Token::Symbol(")" | "]" | "}") if depth == 0 => break,
+            Token::Symbol(")" | "]" | "}") => depth -= 1,
+            Token::Ident("for")
+                if depth == 0
+                    && !matches!(prev, None | Some(Token::Newline | Token::Symbol(";"))) =>
+            {
+                if let Token::Ident(name) = window[1] {
+                    names.push(name);
+                }
+            }
+            _ => {}
+        }
+        prev = Some(window[0]);
+    }
+    names
+}
+
+/// Scan the given source into tokens, tracking unclosed brackets and multi-line constructs.
+pub(crate) fn scan(source: &str) -> Scan<'_> {
+    let mut scan = Scan::default();
+    scan_into(source, 0, &mut 1, &mut scan, false);
+    scan
+}
+
+/// Rewrite each `echo` statement of the source into a call of the global `function` with the same values,
+/// e.g., `echo a, b;` into `function(a, b);`, keeping the rest of the source on the same lines.
+/// Returns `None` if the source has no `echo` statements.
+pub(crate) fn rewrite_echo(source: &str, function: &str) -> Option<String> {
+    let scan = scan(source);
+    let mut edits = Vec::new();
+    for (i, &(token, _)) in scan.tokens.iter().enumerate() {
+        if token != Token::Ident("echo") {
+            continue;
+        }
+        let (start, end) = scan.spans[i];
+        edits.push((start, end, format!("{function}(")));
+
+        // Find where the last value ends, which may be inside an interpolation of a string.
+        let mut depth = 0usize;
+        let mut values_end = end;
+        let mut prev = Some(token);
+        for (&(token, _), &(_, end)) in scan.tokens.iter().zip(&scan.spans).skip(i + 1) {
+            match token {
+                Token::Symbol(";") if depth == 0 => break,
+                Token::Newline if depth == 0 && ends_statement(prev) => break,
+                Token::Newline => continue,
+                Token::Symbol(")" | "]" | "}") if depth == 0 => break,
+                Token::Symbol(")" | "]" | "}") => depth -= 1,
+                Token::Symbol("(" | "[" | "{") => depth += 1,
+                _ => {}
+            }
+            values_end = values_end.max(end);
+            prev = Some(token);
+        }
+        edits.push((values_end, values_end, ")".to_owned()));
+    }
+    if edits.is_empty() {
+        return None;
+    }
+
+    // Statements nested in a function which is echoed are rewritten inside the outer call.
+    edits.sort_by_key(|&(start, _, _)| start);
+    let mut rewritten = String::with_capacity(source.len() + edits.len() * function.len());
+    let mut copied = 0;
+    for (start, end, replacement) in edits {
+        rewritten.push_str(&source[copied..start]);
+        rewritten.push_str(&replacement);
+        copied = end;
+    }
+    rewritten.push_str(&source[copied..]);
+    Some(rewritten)
+}
+
+/// Scan from byte offset `start` until the end of the source, or until the brace closing an
+/// interpolation when `interpolation` is set. Returns the offset where scanning stopped.
+fn scan_into<'a>(
+    source: &'a str,
+    start: usize,
+    line: &mut usize,
+    scan: &mut Scan<'a>,
+    interpolation: bool,
+) -> usize {
+    let bytes = source.as_bytes();
+    let mut depth = 0usize;
+    let mut i = start;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\n' => {
+                scan.push(Token::Newline, *line, i, i + 1);
+                *line += 1;
+                i += 1;
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            b'#' => {
+                // Line comments run until the end of the line.
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => match source[i + 2..].find("*/") {
+                Some(end) => {
+                    *line += source[i..i + 2 + end].matches('\n').count();
+                    i += end + 4;
+                }
+                None => {
+                    scan.unterminated = true;
+                    return bytes.len();
+                }
+            },
+            b'`' => match source[i + 1..].find('`') {
+                // Raw strings have no escapes and may span multiple lines.
+                Some(end) => {
+                    scan.push(Token::Str, *line, i, i + end + 2);
+                    *line += source[i..i + 1 + end].matches('\n').count();
+                    i += end + 2;
+                }
+                None => {
+                    scan.unterminated = true;
+                    return bytes.len();
+                }
+            },
+            b'\'' | b'"' => {
+                // The end of the string is only known once any interpolations have been scanned.
+                let index = scan.tokens.len();
+                scan.push(Token::Str, *line, i, i);
+                i += 1;
+                while i < bytes.len() && bytes[i] != c && bytes[i] != b'\n' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    } else if c == b'"' && bytes[i] == b'#' && bytes.get(i + 1) == Some(&b'{') {
+                        // Interpolated expressions are ordinary code.
+                        i = scan_into(source, i + 2, line, scan, true);
+                        continue;
+                    }
+                    i += 1;
+                }
+
+                // Unlike raw strings, quoted strings end at the line, which YASL reports as an error.
+                if i < bytes.len() && bytes[i] == c {
+                    i += 1;
+                }
+                scan.spans[index].1 = i;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || c == b'$' => {
+                let end = source[i..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+                    .map_or(bytes.len(), |n| i + n);
+                scan.push(Token::Ident(&source[i..end]), *line, i, end);
+                i = end;
+            }
+            c if c.is_ascii_digit() => {
+                let end = source[i..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                    .map_or(bytes.len(), |n| i + n);
+                scan.push(Token::Number(&source[i..end]), *line, i, end);
+                i = end;
+            }
+            _ => {
+                let len = MULTI_CHAR_SYMBOLS
+                    .iter()
+                    .find(|s| source[i..].starts_with(*s))
+                    .map_or_else(
+                        || source[i..].chars().next().map_or(1, char::len_utf8),
+                        |s| s.len(),
+                    );
+                let symbol = &source[i..i + len];
+                let start = i;
+                i += len;
+
+                match symbol {
+                    "(" | "[" | "{" => {
+                        depth += 1;
+                        scan.open_brackets
+                            .push(symbol.chars().next().unwrap_or_default());
+                    }
+                    ")" | "]" | "}" => {
+                        if interpolation && depth == 0 {
+                            // This brace closes the interpolation rather than a bracket of its own.
+                            return i;
+                        }
+                        depth = depth.saturating_sub(1);
+                        scan.open_brackets.pop();
+                    }
+                    _ => {}
+                }
+                scan.push(Token::Symbol(symbol), *line, start, i);
+            }
+        }
+    }
+    i
+}
@@ -0,0 +1,187 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Text templates with embedded YASL, e.g., to generate reports or HTML with the logic written in YASL.
+//! - `#{expr}`: Insert the value of the expression, converted to a string as `~` would.
+//! - `#{% code %}`: Run the statements, which may open and close blocks around other parts of the template.
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use yaslapi::{aux::Object, template::Template};
+//!
+//! let template = Template::parse(
+//!     "#{% for item in items { %}- #{item}\n#{% } %}Total: #{len items}",
+//! )
+//! .unwrap();
+//! let items = Object::List(vec![Object::Str("apple".to_owned()), Object::Int(3)]);
+//! let globals = HashMap::from([("items".to_owned(), items)]);
+//! assert_eq!(template.render(&globals).unwrap(), "- apple\n- 3\nTotal: 2");
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use crate::{aux::Object, Error, State, StateError, HIDDEN_GLOBAL_PREFIX};
+
+/// Name of the hidden global holding the text between the template's tags.
+const TEXT_GLOBAL: &str = "__yaslapi_template_text";
+
+/// Name of the hidden global which the rendered output is appended to.
+const OUTPUT_GLOBAL: &str = "__yaslapi_template_out";
+
+/// A template which has been parsed into a YASL program, and can be rendered any number of times.
+#[derive(Clone, Debug)]
+pub struct Template {
+    /// The text between the template's tags, which the program appends to the output by index.
+    text: Vec<Object>,
+    program: String,
+}
+
+/// Error returned when a template can't be parsed or rendered.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TemplateError {
+    /// A tag starting at the given byte offset is never closed.
+    Unclosed(usize),
+    /// A global has a name which is not a valid identifier, or is reserved for this crate.
+    InvalidGlobal(String),
+    /// The template's code failed to compile or run.
+    Script(StateError),
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unclosed(offset) => write!(f, "unclosed tag at byte {offset}"),
+            Self::InvalidGlobal(name) => write!(f, "invalid global name {name:?}"),
+            Self::Script(e) => write!(f, "the template's code failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl Template {
+    /// Parse a template. Its code is only compiled when it is rendered.
+    /// # Errors
+    /// Will return `TemplateError::Unclosed` if a tag is never closed.
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut text = Vec::new();
+        let mut program = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("#{") {
+            let offset = template.len() - rest.len() + start;
+            if start > 0 {
+                program.push_str(&format!(
+                    "{OUTPUT_GLOBAL} = {OUTPUT_GLOBAL} ~ {TEXT_GLOBAL}[{}];\n",
+                    text.len()
+                ));
+                text.push(Object::Str(rest[..start].to_owned()));
+            }
+
+            let tag = &rest[start + 2..];
+            if let Some(code) = tag.strip_prefix('%') {
+                let end = code.find("%}").ok_or(TemplateError::Unclosed(offset))?;
+                program.push_str(&code[..end]);
+                program.push('\n');
+                rest = &code[end + 2..];
+            } else {
+                let end = closing_brace(tag).ok_or(TemplateError::Unclosed(offset))?;
+                program.push_str(&format!(
+                    "{OUTPUT_GLOBAL} = {OUTPUT_GLOBAL} ~ ({});\n",
+                    &tag[..end]
+                ));
+                rest = &tag[end + 1..];
+            }
+        }
+        if !rest.is_empty() {
+            program.push_str(&format!(
+                "{OUTPUT_GLOBAL} = {OUTPUT_GLOBAL} ~ {TEXT_GLOBAL}[{}];\n",
+                text.len()
+            ));
+            text.push(Object::Str(rest.to_owned()));
+        }
+        Ok(Self { text, program })
+    }
+
+    /// Render the template in a new state, whose globals are the given values. The state has no standard libraries.
+    /// Invalid UTF-8 in the output is replaced with `U+FFFD`.
+    /// # Errors
+    /// Will return `TemplateError::InvalidGlobal` if the name of a global is not a valid identifier,
+    /// or `TemplateError::Script` if the template's code fails to compile or run.
+    #[allow(clippy::missing_panics_doc)] // The hidden globals' names are valid identifiers.
+    pub fn render(&self, globals: &HashMap<String, Object>) -> Result<String, TemplateError> {
+        let mut state = State::from_source(&self.program);
+        for (name, value) in globals {
+            if name.starts_with(HIDDEN_GLOBAL_PREFIX) {
+                return Err(TemplateError::InvalidGlobal(name.clone()));
+            }
+            state.init_global_value(name, value).map_err(|e| match e {
+                Error::State(e) => TemplateError::Script(e),
+                _ => TemplateError::InvalidGlobal(name.clone()),
+            })?;
+        }
+        state
+            .init_global_value(TEXT_GLOBAL, &Object::List(self.text.clone()))
+            .map_err(|e| match e {
+                Error::State(e) => TemplateError::Script(e),
+                _ => unreachable!("Internal Error: The text's global is a valid identifier."),
+            })?;
+        state.push_str("");
+        state
+            .init_global_slice(OUTPUT_GLOBAL)
+            .expect("Internal Error: The output's global is a valid identifier.");
+
+        state.execute().map_err(TemplateError::Script)?;
+        state
+            .load_global_slice(OUTPUT_GLOBAL)
+            .map_err(TemplateError::Script)?;
+        let output = crate::modules::pop_bytes_arg(&mut state, OUTPUT_GLOBAL, 0)
+            .map_err(|_| TemplateError::Script(StateError::TypeError))?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+}
+
+/// Find the brace which closes an expression tag, skipping nested braces and string literals.
+fn closing_brace(tag: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in tag.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '{' => depth += 1,
+                '}' if depth == 0 => return Some(i),
+                '}' => depth -= 1,
+                _ => {}
+            },
+        }
+    }
+    None
+}
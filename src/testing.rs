@@ -0,0 +1,223 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A harness for running suites of YASL test scripts under `cargo test`.
+//!
+//! Each script runs in a new state with only the `collections`, `error`, `math`, and `mt` standard libraries,
+//! plus an `expect(actual, expected)` function which fails the test unless the values are equal by YASL's `==`.
+//! A script fails if it doesn't compile, or raises any error, e.g., from an `assert` statement.
+//!
+//! ```no_run
+//! #[test]
+//! fn script_tests() {
+//!     yaslapi::testing::TestRunner::new().assert_dir("tests/scripts");
+//! }
+//! ```
+
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    os::raw::c_int,
+    path::{Path, PathBuf},
+};
+
+use yaslapi_sys::YASL_State;
+
+use crate::{aux::ScriptError, modules::run_lib_fn, State, StateError, Type};
+
+/// Runs YASL test scripts, each in a new state.
+pub struct TestRunner {
+    setup: Box<dyn Fn(&mut State)>,
+}
+
+/// A test script which failed, with the error output of YASL.
+#[derive(Debug, PartialEq)]
+pub struct TestFailure {
+    /// The path of the script.
+    pub path: PathBuf,
+    /// The line which raised the error, if YASL reported one.
+    pub line: Option<usize>,
+    /// The error which the script raised.
+    pub error: StateError,
+    /// The error message, e.g., `AssertError: expected 2, got 3.`.
+    pub message: String,
+}
+
+impl Display for TestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Find the `.yasl` files in the directory and its subdirectories, sorted by path.
+/// # Errors
+/// Returns an error if a directory can't be read.
+pub fn discover(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut scripts = Vec::new();
+    let mut dirs = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "yasl") {
+                scripts.push(path);
+            }
+        }
+    }
+    scripts.sort();
+    Ok(scripts)
+}
+
+impl TestRunner {
+    /// Create a runner for test scripts which only use the standard libraries and `expect`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            setup: Box::new(|_| {}),
+        }
+    }
+
+    /// Set a function which is called for each new state before its script is executed,
+    /// e.g., to register the host functions under test.
+    #[must_use]
+    pub fn setup(mut self, setup: impl Fn(&mut State) + 'static) -> Self {
+        self.setup = Box::new(setup);
+        self
+    }
+
+    /// Compile and execute the test script at `path`.
+    /// # Errors
+    /// Returns a `TestFailure` if the script can't be read, doesn't compile, or raises an error.
+    #[allow(clippy::missing_panics_doc)] // `expect` is a valid identifier.
+    pub fn run_file(&self, path: impl AsRef<Path>) -> Result<(), TestFailure> {
+        let path = path.as_ref();
+        let mut state = State::from_file(path).map_err(|e| TestFailure {
+            path: path.to_path_buf(),
+            line: None,
+            error: StateError::Generic,
+            message: e.to_string(),
+        })?;
+
+        state.set_printerr_tostr();
+        state.declare_lib_collections();
+        state.declare_lib_error();
+        state.declare_lib_math();
+        state.declare_lib_mt();
+        state.push_cfunction(expect, 2);
+        state
+            .init_global_slice("expect")
+            .expect("Internal Error: `expect` is a valid identifier.");
+        (self.setup)(&mut state);
+
+        state.execute().map(|_| ()).map_err(|error| {
+            state.load_printerr();
+            let output = state.pop_str().unwrap_or_default();
+            let (message, line) = split_line(output.trim());
+            TestFailure {
+                path: path.to_path_buf(),
+                line,
+                error,
+                message: message.to_owned(),
+            }
+        })
+    }
+
+    /// Run every test script found in the directory by `discover`, and return the failures.
+    /// # Errors
+    /// Returns an error if a directory can't be read.
+    pub fn run_dir(&self, dir: impl AsRef<Path>) -> io::Result<Vec<TestFailure>> {
+        Ok(discover(dir)?
+            .iter()
+            .filter_map(|path| self.run_file(path).err())
+            .collect())
+    }
+
+    /// Run every test script in the directory, like `run_dir`, e.g., from a `#[test]` function.
+    /// # Panics
+    /// Panics if a directory can't be read, or with a list of the failures if any script fails.
+    pub fn assert_dir(&self, dir: impl AsRef<Path>) {
+        let dir = dir.as_ref();
+        let failures = self
+            .run_dir(dir)
+            .unwrap_or_else(|e| panic!("Unable to read test scripts in {}: {e}", dir.display()));
+        if !failures.is_empty() {
+            let list = failures
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n");
+            panic!("{} test script(s) failed:\n{list}", failures.len());
+        }
+    }
+}
+
+impl Default for TestRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split the ` (line N)` suffix, which YASL adds to error messages, from the message.
+fn split_line(output: &str) -> (&str, Option<usize>) {
+    output
+        .rsplit_once(" (line ")
+        .and_then(|(message, rest)| {
+            let line = rest.strip_suffix(')')?.parse().ok()?;
+            Some((message, Some(line)))
+        })
+        .unwrap_or((output, None))
+}
+
+/// Implements `expect(actual, expected)`.
+unsafe extern "C" fn expect(state: *mut YASL_State) -> c_int {
+    run_lib_fn(state, |state| {
+        // Functions would be popped as `undef`, so they are rejected before popping.
+        let is_fn = |n| matches!(state.peek_n_type(n), Type::Fn | Type::Closure | Type::CFn);
+        if is_fn(0) || is_fn(1) {
+            return Err(ScriptError::new(
+                StateError::TypeError,
+                "TypeError: expect can't compare functions.",
+            ));
+        }
+        let values = (state.pop_object(None), state.pop_object(None));
+        let (Ok(expected), Ok(actual)) = values else {
+            return Err(ScriptError::new(
+                StateError::ValueError,
+                "ValueError: expect can't compare values which contain themselves.",
+            ));
+        };
+
+        if actual == expected {
+            Ok(0)
+        } else {
+            Err(ScriptError::new(
+                StateError::AssertError,
+                format!("AssertError: expected {expected}, got {actual}."),
+            ))
+        }
+    })
+}
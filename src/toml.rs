@@ -0,0 +1,94 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use ::toml::{Table, Value};
+
+use crate::{
+    aux::{HashableObject, Object},
+    StateError,
+};
+
+impl From<Value> for Object {
+    /// Convert a TOML value into a YASL `Object`. Tables have string keys, and datetimes become strings.
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Boolean(b) => Self::Bool(b),
+            Value::Integer(i) => Self::Int(i),
+            Value::Float(f) => Self::Float(f),
+            Value::String(s) => Self::Str(s),
+            Value::Datetime(d) => Self::Str(d.to_string()),
+            Value::Array(array) => Self::List(array.into_iter().map(Self::from).collect()),
+            Value::Table(table) => Self::Table(
+                table
+                    .into_iter()
+                    .map(|(k, v)| (HashableObject::Str(k), Self::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<&Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a TOML value.
+    /// # Errors
+    /// Will return `StateError::TypeError` for `undef`, which TOML can't represent, user data, user pointers,
+    /// and tables with keys which are not strings.
+    fn try_from(value: &Object) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Object::Bool(b) => Self::Boolean(*b),
+            Object::Int(i) => Self::Integer(*i),
+            Object::Float(f) => Self::Float(*f),
+            Object::Str(s) => Self::String(s.clone()),
+            Object::List(list) => Self::Array(
+                list.iter()
+                    .map(<Self as TryFrom<&Object>>::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Object::Table(table) => Self::Table(
+                table
+                    .iter()
+                    .map(|(k, v)| match k {
+                        HashableObject::Str(k) => {
+                            Ok((k.clone(), <Self as TryFrom<&Object>>::try_from(v)?))
+                        }
+                        _ => Err(StateError::TypeError),
+                    })
+                    .collect::<Result<Table, _>>()?,
+            ),
+            Object::UserData { .. } | Object::UserPtr(_) | Object::Undef => {
+                return Err(StateError::TypeError)
+            }
+        })
+    }
+}
+
+impl TryFrom<Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a TOML value.
+    /// # Errors
+    /// Has the same errors as converting from `&Object`.
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        // `toml::Value` has an inherent `try_from` for serializable types, which takes precedence.
+        <Self as TryFrom<&Object>>::try_from(&value)
+    }
+}
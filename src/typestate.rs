@@ -0,0 +1,183 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! An optional typestate layer over `State`, which makes the order of operations part of the type.
+//! Scripts are set up as a `SourceState`, then compiled into a `CompiledState` and executed into
+//! an `ExecutedState`. Only an `ExecutedState` gives access to the full `State` API, e.g., `load_global`
+//! and `function_call`, so using them before the script has run is a compile-time error.
+//!
+//! ```
+//! use yaslapi::typestate::SourceState;
+//!
+//! let mut source = SourceState::from_source("let double = fn(x) -> x * 2;");
+//! source.declare_libs();
+//! let mut state = source.compile().unwrap().execute().unwrap();
+//! // Top-level `let` is local to the script, so only globals declared by the host are visible here.
+//! assert!(state.load_global_slice("math").is_ok());
+//! ```
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{aux::Object, CFunction, Error, InvalidIdentifier, State, StateError};
+
+/// A state whose source has not been compiled yet. Globals and host functions are declared here.
+pub struct SourceState(State);
+
+/// A state whose source compiled successfully, but has not been executed.
+pub struct CompiledState(State);
+
+/// A state whose script has been executed. Dereferences to the underlying `State`.
+pub struct ExecutedState(State);
+
+/// The state handed back alongside the error of a failed stage, boxed since a state is large.
+pub type StageError<S> = Box<(S, StateError)>;
+
+impl SourceState {
+    /// Initialize a new state from a string containing the source code.
+    #[must_use]
+    pub fn from_source(source: &str) -> Self {
+        Self(State::from_source(source))
+    }
+
+    /// Initialize a new state from a script's filepath. Returns `None` if the file does not exist or cannot be read.
+    /// # Panics
+    /// The string slice `script_location` must not contain internal zero bytes.
+    #[must_use]
+    pub fn from_path(script_location: &str) -> Option<Self> {
+        State::from_path(script_location).map(Self)
+    }
+
+    /// Loads all standard libraries into the state and declares them with their default names.
+    pub fn declare_libs(&mut self) {
+        self.0.declare_libs();
+    }
+
+    /// Add a new global variable to the state with default value `undef`.
+    /// # Errors
+    /// Will return an `InvalidIdentifier` if the given name is not a valid YASL identifier.
+    pub fn declare_global(&mut self, name: &str) -> Result<(), InvalidIdentifier> {
+        self.0.declare_global(name)
+    }
+
+    /// Declare a global variable initialized with the given value.
+    /// # Errors
+    /// Will return `Error::InvalidIdentifier` if the given name is not a valid YASL identifier,
+    /// or `Error::State` from pushing the value to the stack.
+    pub fn init_global(&mut self, name: &str, value: &Object) -> Result<(), Error> {
+        self.0.init_global_value(name, value)
+    }
+
+    /// Declare a global host function which takes `args` arguments.
+    /// # Errors
+    /// Will return an `InvalidIdentifier` if the given name is not a valid YASL identifier.
+    pub fn init_cfunction(
+        &mut self,
+        name: &str,
+        f: CFunction,
+        args: i32,
+    ) -> Result<(), InvalidIdentifier> {
+        crate::validate_identifier(name)?;
+        self.0.push_cfunction(f, args);
+        self.0.init_global_slice(name)
+    }
+
+    /// Compile the source without running it.
+    /// # Errors
+    /// Returns the state alongside `StateError::SyntaxError` if the source code contains invalid syntax.
+    pub fn compile(mut self) -> Result<CompiledState, StageError<Self>> {
+        match self.0.compile() {
+            Ok(_) => Ok(CompiledState(self.0)),
+            Err(e) => Err(Box::new((self, e))),
+        }
+    }
+
+    /// Compile and execute the source.
+    /// # Errors
+    /// Returns the state alongside `StateError::SyntaxError` if the source code contains invalid syntax,
+    /// or a runtime error depending on the source and execution state.
+    pub fn execute(mut self) -> Result<ExecutedState, StageError<Self>> {
+        match self.0.execute() {
+            Ok(_) => Ok(ExecutedState(self.0)),
+            Err(e) => Err(Box::new((self, e))),
+        }
+    }
+
+    /// Unwrap the underlying state to use the dynamic API.
+    #[must_use]
+    pub fn into_inner(self) -> State {
+        self.0
+    }
+}
+
+impl CompiledState {
+    /// Execute the compiled script.
+    /// # Errors
+    /// Returns the state alongside a runtime error, depending on the source and execution state.
+    pub fn execute(mut self) -> Result<ExecutedState, StageError<Self>> {
+        match self.0.execute() {
+            Ok(_) => Ok(ExecutedState(self.0)),
+            Err(e) => Err(Box::new((self, e))),
+        }
+    }
+
+    /// Unwrap the underlying state to use the dynamic API.
+    #[must_use]
+    pub fn into_inner(self) -> State {
+        self.0
+    }
+}
+
+impl ExecutedState {
+    /// Unwrap the underlying state.
+    #[must_use]
+    pub fn into_inner(self) -> State {
+        self.0
+    }
+}
+
+/// An executed state allows the full `State` API, such as calling functions and reading globals.
+impl Deref for ExecutedState {
+    type Target = State;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl DerefMut for ExecutedState {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Implement opaque debug formatting, so that results returning a stage can be unwrapped.
+macro_rules! impl_debug {
+    ($($stage:ident),*) => {$(
+        impl fmt::Debug for $stage {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($stage)).finish_non_exhaustive()
+            }
+        }
+    )*};
+}
+impl_debug!(SourceState, CompiledState, ExecutedState);
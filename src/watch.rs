@@ -0,0 +1,177 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Hot reloading of a script when its file changes, e.g., to live-edit game scripts.
+//!
+//! ```no_run
+//! use yaslapi::watch::ScriptWatcher;
+//!
+//! let mut watcher = ScriptWatcher::new("scripts/game.yasl")
+//!     .unwrap()
+//!     .setup(|state| {
+//!         state.declare_global("score").unwrap();
+//!     })
+//!     .keep_global("score")
+//!     .on_error(|message| eprintln!("{message}"));
+//! let mut state = watcher.load().unwrap();
+//! loop {
+//!     watcher.poll(&mut state);
+//!     // Run a frame using `state`...
+//! }
+//! ```
+
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{aux::Object, State};
+
+/// Watches a script file, and replaces a state with a newly loaded one whenever the file changes.
+pub struct ScriptWatcher {
+    path: PathBuf,
+    setup: Box<dyn Fn(&mut State)>,
+    keep: Vec<String>,
+    on_error: Box<dyn FnMut(&str)>,
+    events: Receiver<notify::Result<Event>>,
+    // Stops watching when dropped.
+    _watcher: RecommendedWatcher,
+}
+
+impl ScriptWatcher {
+    /// Start watching the script at `path`. The directory containing the script is watched,
+    /// so that editors which save by replacing the file are also noticed.
+    /// # Errors
+    /// Will return an error if the directory can't be watched.
+    pub fn new(path: impl Into<PathBuf>) -> notify::Result<Self> {
+        let path = path.into();
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The receiver is only gone once the `ScriptWatcher` is dropped.
+            let _ = sender.send(event);
+        })?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => ".".as_ref(),
+        };
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            path,
+            setup: Box::new(|_| {}),
+            keep: Vec::new(),
+            on_error: Box::new(|_| {}),
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// Set a function which is called for each new state before it is compiled, e.g., to declare
+    /// libraries and register host functions. It may be called more than once per reload.
+    #[must_use]
+    pub fn setup(mut self, setup: impl Fn(&mut State) + 'static) -> Self {
+        self.setup = Box::new(setup);
+        self
+    }
+
+    /// Copy the global `name` from the old state into each reloaded state, after the new script has run.
+    /// Only values which can be converted to an `Object` are kept, and user data is not.
+    #[must_use]
+    pub fn keep_global(mut self, name: impl Into<String>) -> Self {
+        self.keep.push(name.into());
+        self
+    }
+
+    /// Set a function which receives the message of each error from loading the script,
+    /// e.g., a syntax error from an unfinished edit. The previous state stays in use after an error.
+    #[must_use]
+    pub fn on_error(mut self, on_error: impl FnMut(&str) + 'static) -> Self {
+        self.on_error = Box::new(on_error);
+        self
+    }
+
+    /// Load, compile, and execute the script in a new state.
+    /// Returns `None` after reporting the error if the script can't be read, doesn't compile, or fails while running.
+    pub fn load(&mut self) -> Option<State> {
+        // Compile in a separate state first, so that the error message can be captured
+        // without redirecting the error output of the state which is kept.
+        let mut trial = self.open()?;
+        trial.set_printerr_tostr();
+        if trial.compile().is_err() {
+            trial.load_printerr();
+            let message = trial.pop_str().unwrap_or_default();
+            (self.on_error)(message.trim_end());
+            return None;
+        }
+
+        let mut state = self.open()?;
+        if let Err(e) = state.execute() {
+            (self.on_error)(&format!("{e:?} while running {}", self.path.display()));
+            return None;
+        }
+        Some(state)
+    }
+
+    /// Reload the script if its file changed since the last poll, replacing `state` if it loads successfully.
+    /// Returns whether the state was replaced.
+    pub fn poll(&mut self, state: &mut State) -> bool {
+        let name = self.path.file_name();
+        // Drain every event, so that one change doesn't cause a reload on the next poll too.
+        let mut changed = false;
+        for event in self.events.try_iter().flatten() {
+            changed |= !event.kind.is_access() && event.paths.iter().any(|p| p.file_name() == name);
+        }
+        if !changed {
+            return false;
+        }
+
+        let Some(mut new_state) = self.load() else {
+            return false;
+        };
+        for name in &self.keep {
+            match state.pop_global_slice(name, None) {
+                Ok(Object::UserData { .. }) | Err(_) => {}
+                Ok(value) => {
+                    let _ = new_state.init_global_value(name, &value);
+                }
+            }
+        }
+        *state = new_state;
+        true
+    }
+
+    /// Create a state for the script and run the setup function, reporting any error.
+    fn open(&mut self) -> Option<State> {
+        match State::from_file(&self.path) {
+            Ok(mut state) => {
+                (self.setup)(&mut state);
+                Some(state)
+            }
+            Err(e) => {
+                (self.on_error)(&e.to_string());
+                None
+            }
+        }
+    }
+}
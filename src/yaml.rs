@@ -0,0 +1,108 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use serde_yaml::{Mapping, Number, Value};
+
+use crate::{
+    aux::{HashableObject, Object},
+    StateError,
+};
+
+impl TryFrom<Value> for Object {
+    type Error = StateError;
+    /// Convert a YAML value into a YASL `Object`. `null` becomes `undef`, and tags are ignored.
+    /// # Errors
+    /// Will return `StateError::ValueError` if a number fits neither an `i64` nor an `f64`,
+    /// or `StateError::TypeError` if a mapping has a sequence or mapping as a key.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Null => Self::Undef,
+            Value::Bool(b) => Self::Bool(b),
+            Value::Number(n) => {
+                // Unsigned integers larger than `i64::MAX` are rejected rather than losing precision.
+                if let Some(i) = n.as_i64() {
+                    Self::Int(i)
+                } else if n.is_f64() {
+                    Self::Float(n.as_f64().ok_or(StateError::ValueError)?)
+                } else {
+                    return Err(StateError::ValueError);
+                }
+            }
+            Value::String(s) => Self::Str(s),
+            Value::Sequence(sequence) => Self::List(
+                sequence
+                    .into_iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Mapping(mapping) => Self::Table(
+                mapping
+                    .into_iter()
+                    .map(|(k, v)| {
+                        let k = HashableObject::try_from(Self::try_from(k)?)
+                            .map_err(|_| StateError::TypeError)?;
+                        Ok((k, Self::try_from(v)?))
+                    })
+                    .collect::<Result<_, StateError>>()?,
+            ),
+            Value::Tagged(tagged) => Self::try_from(tagged.value)?,
+        })
+    }
+}
+
+impl TryFrom<&Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a YAML value. `undef` becomes `null`.
+    /// # Errors
+    /// Will return `StateError::TypeError` for user data and user pointers, including as table keys.
+    fn try_from(value: &Object) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Object::Undef => Self::Null,
+            Object::Bool(b) => Self::Bool(*b),
+            Object::Int(i) => Self::Number((*i).into()),
+            Object::Float(f) => Self::Number(Number::from(*f)),
+            Object::Str(s) => Self::String(s.clone()),
+            Object::List(list) => {
+                Self::Sequence(list.iter().map(Self::try_from).collect::<Result<_, _>>()?)
+            }
+            Object::Table(table) => Self::Mapping(
+                table
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok((Self::try_from(Object::from(k.clone()))?, Self::try_from(v)?))
+                    })
+                    .collect::<Result<Mapping, StateError>>()?,
+            ),
+            Object::UserData { .. } | Object::UserPtr(_) => return Err(StateError::TypeError),
+        })
+    }
+}
+
+impl TryFrom<Object> for Value {
+    type Error = StateError;
+    /// Convert a YASL `Object` into a YAML value. `undef` becomes `null`.
+    /// # Errors
+    /// Has the same errors as converting from `&Object`.
+    fn try_from(value: Object) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
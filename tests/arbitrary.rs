@@ -0,0 +1,72 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use yaslapi::{
+    aux::{HashableObject, Object},
+    State,
+};
+
+/// Pseudo-random bytes for the generator, from a fixed linear congruential sequence.
+fn bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut x = seed;
+    (0..len)
+        .map(|_| {
+            x = x
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (x >> 56) as u8
+        })
+        .collect()
+}
+
+/// Count the levels of nested lists and tables.
+fn depth(object: &Object) -> usize {
+    match object {
+        Object::List(list) => 1 + list.iter().map(depth).max().unwrap_or(0),
+        Object::Table(table) => 1 + table.values().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Test that generated objects are bounded, and survive a round trip through the YASL stack.
+#[test]
+fn test_arbitrary_round_trip() {
+    let mut state = State::default();
+    for seed in 0..200 {
+        let data = bytes(seed, 4096);
+        let mut u = Unstructured::new(&data);
+        let object = Object::arbitrary(&mut u).unwrap();
+        assert!(depth(&object) <= 4);
+
+        // Formatting compares values exactly, even NaN.
+        state.push_object(&object).unwrap();
+        let popped = state.pop_object(None).unwrap();
+        assert_eq!(popped.to_string(), object.to_string(), "seed {seed}");
+        assert_eq!(state.stack_len(), 0);
+
+        let key = HashableObject::arbitrary(&mut u).unwrap();
+        assert!(!matches!(key, HashableObject::UserPtr(_)));
+    }
+}
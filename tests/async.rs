@@ -0,0 +1,50 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "async")]
+
+use yaslapi::{aux::Object, State};
+
+/// Test executing a script and calling one of its functions from an async context.
+#[test]
+fn test_async_execute_and_call() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Failed to build the runtime.");
+
+    runtime.block_on(async {
+        let mut state = State::from_source("add = fn(a, b) -> a + b;");
+        state.declare_global("add").unwrap();
+
+        // Execute the script so that `add` is defined.
+        let (state, result) = state.execute_async().await;
+        result.expect("Failed to execute the state machine");
+
+        // Call the script function with two integers.
+        // SAFETY: Adding two integers can't raise a runtime error.
+        let (_, result) =
+            unsafe { state.call_global_async("add", vec![Object::Int(40), Object::Int(2)]) }.await;
+        let results = result.expect("Failed to call the global function");
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Object::Int(42)));
+    });
+}
@@ -0,0 +1,113 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::io::ErrorKind;
+
+use yaslapi::{
+    aux::Object, builder::Library, Error, InvalidIdentifier, LoadError, State, StateError,
+};
+use yaslapi_sys::YASL_State;
+
+unsafe extern "C" fn answer(state: *mut YASL_State) -> i32 {
+    State::from_memory(state).unwrap().push_int(42);
+    1
+}
+
+#[test]
+fn test_builder_configures_state() {
+    let mut state = State::builder()
+        .source("echo math.max(answer(), 7); echo collections;")
+        .library(Library::Math)
+        .function("answer", answer, 0)
+        .capture_printout()
+        .capture_printerr()
+        .build()
+        .unwrap();
+
+    // Only the requested library is declared.
+    assert_eq!(state.execute(), Err(StateError::SyntaxError));
+    state.load_printerr();
+    assert!(state.pop_str().unwrap().contains("collections"));
+
+    let mut state = State::builder()
+        .source("const c = collections; echo math.max(answer(), 7);")
+        .all_libraries()
+        .function("answer", answer, 0)
+        .capture_printout()
+        .build()
+        .unwrap();
+    state.execute().unwrap();
+    state.load_printout();
+    assert_eq!(state.pop_str().unwrap(), "42\n");
+}
+
+#[test]
+fn test_builder_missing_path() {
+    let error = State::builder()
+        .path("does/not/exist.yasl")
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(error, Error::Load(LoadError::Io(e)) if e.kind() == ErrorKind::NotFound));
+}
+
+#[test]
+fn test_builder_invalid_function_name() {
+    let error = State::builder()
+        .function("not valid", answer, 0)
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        Error::InvalidIdentifier(InvalidIdentifier::InvalidChar {
+            index: 3,
+            character: ' '
+        })
+    ));
+}
+
+#[test]
+fn test_lazy_libraries() {
+    let mut state = State::builder()
+        .source("echo math.max(answer(), 7);")
+        .all_libraries()
+        .lazy_libraries()
+        .function("answer", answer, 0)
+        .capture_printout()
+        .build()
+        .unwrap();
+    state.execute().unwrap();
+    state.load_printout();
+    assert_eq!(state.pop_str().unwrap(), "42\n");
+
+    // Only the library which the script reads was declared.
+    assert!(state.load_global_slice("io").is_err());
+    assert!(state.load_global_slice("math").is_ok());
+    state.pop();
+
+    // Source compiled later declares the libraries it reads.
+    let mut state = State::from_source("");
+    state.declare_libs_lazily();
+    assert_eq!(state.eval("math.max(1, 2)"), Ok(Object::Int(2)));
+    assert!(state.load_global_slice("os").is_err());
+}
@@ -0,0 +1,97 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "config")]
+
+use serde::Deserialize;
+use yaslapi::{
+    config::{self, ConfigError},
+    StateError,
+};
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum Mode {
+    Windowed,
+    Fullscreen { monitor: i64 },
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Settings {
+    width: i64,
+    height: i64,
+    scale: f64,
+    title: Option<String>,
+    tags: Vec<String>,
+    mode: Mode,
+    fallback: Mode,
+}
+
+/// Test deserializing the globals of a configuration script.
+#[test]
+fn test_load_config() {
+    let settings: Settings = config::load_source(
+        "
+        const base = 320;
+        width = base * 2;
+        height = width // 16 * 9;
+        scale = 2;
+        tags = ['a', 'b'];
+        mode = { 'Fullscreen': { 'monitor': 1 } };
+        fallback = 'Windowed';
+        ",
+    )
+    .unwrap();
+    assert_eq!(
+        settings,
+        Settings {
+            width: 640,
+            height: 360,
+            scale: 2.0,
+            title: None,
+            tags: vec!["a".to_owned(), "b".to_owned()],
+            mode: Mode::Fullscreen { monitor: 1 },
+            fallback: Mode::Windowed,
+        }
+    );
+}
+
+/// Test the errors from loading a configuration.
+#[test]
+fn test_config_errors() {
+    assert!(matches!(
+        config::load_source::<Settings>("width = ;"),
+        Err(ConfigError::Script(StateError::SyntaxError))
+    ));
+    assert!(matches!(
+        config::load_source::<Settings>("width = 'wide';"),
+        Err(ConfigError::Invalid(_))
+    ));
+    // Scripts have no standard libraries.
+    assert!(matches!(
+        config::load_source::<Settings>("width = math.max(1, 2);"),
+        Err(ConfigError::Script(StateError::SyntaxError))
+    ));
+    assert!(matches!(
+        config::load::<Settings>("tests/missing.yasl"),
+        Err(ConfigError::Io(_))
+    ));
+}
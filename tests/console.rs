@@ -0,0 +1,95 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use yaslapi::{
+    aux::Object,
+    console::{CommandConsole, ConsoleError},
+    State, StateError,
+};
+
+/// Test parsing arguments and calling functions from the console.
+#[test]
+fn test_console_commands() {
+    let mut state = State::from_source(
+        "
+        echo_args = fn(a, b, c, d, e, f) -> a, b, c, d, e, f;
+        god_mode = false;
+        ",
+    );
+    state.declare_global("echo_args").unwrap();
+    state.declare_global("god_mode").unwrap();
+    state.execute().unwrap();
+
+    let console = CommandConsole::new()
+        .command("echo_args")
+        .command("god_mode");
+    assert_eq!(
+        console.commands().collect::<Vec<_>>(),
+        ["echo_args", "god_mode"]
+    );
+    // SAFETY: `echo_args` only returns its arguments, so it can't raise a runtime error.
+    let mut run = |line: &str| unsafe { console.run(&mut state, line) };
+    assert_eq!(
+        run(r#"  echo_args -7 2.5 true undef "a \"b\"" nan  "#),
+        Ok(vec![
+            Object::Int(-7),
+            Object::Float(2.5),
+            Object::Bool(true),
+            Object::Undef,
+            Object::Str("a \"b\"".to_owned()),
+            Object::Str("nan".to_owned()),
+        ])
+    );
+
+    assert_eq!(run("god_mode true"), Ok(Vec::new()));
+    assert_eq!(run("god_mode"), Ok(vec![Object::Bool(true)]));
+    assert_eq!(state.stack_len(), 0);
+}
+
+/// Test the errors from running console commands.
+#[test]
+fn test_console_errors() {
+    let mut state = State::from_source("speed = 1;");
+    state.declare_global("speed").unwrap();
+    state.declare_global("hidden").unwrap();
+    state.execute().unwrap();
+
+    let console = CommandConsole::new().command("speed").command("missing");
+    // SAFETY: None of the commands is a function, so nothing is called.
+    let mut run = |line: &str| unsafe { console.run(&mut state, line) };
+    assert_eq!(run("   "), Err(ConsoleError::Empty));
+    assert_eq!(
+        run("missing 1"),
+        Err(ConsoleError::State(StateError::Generic))
+    );
+    assert_eq!(
+        run("hidden"),
+        Err(ConsoleError::UnknownCommand("hidden".to_owned()))
+    );
+    assert_eq!(run("speed 'fast"), Err(ConsoleError::UnclosedQuote(6)));
+    assert_eq!(
+        run("speed 1 2"),
+        Err(ConsoleError::TooManyArgs("speed".to_owned()))
+    );
+    assert_eq!(run("speed"), Ok(vec![Object::Int(1)]));
+    assert_eq!(state.stack_len(), 0);
+}
@@ -20,7 +20,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use yaslapi::{State, Type};
+use yaslapi::{
+    aux::{run_host_fn, HashableObject, Object, ScriptError, YaslCFn},
+    convert::IntoYasl,
+    validate_identifier, Error, InvalidIdentifier, LoadError, ParseTypeError, State, StateError,
+    StateRef, StateSuccess, Type,
+};
 use yaslapi_sys::YASL_State;
 
 // C-style function to print a constant string.
@@ -129,3 +134,1528 @@ fn test_global_mutability() {
         .expect("Failed to load the global");
     assert_eq!(state.pop_int(), new_value + 3);
 }
+
+/// Test evaluating expressions against the globals of a state without losing its main program.
+#[test]
+fn test_eval() {
+    let mut state = State::from_source("x += 1;");
+    state.push_int(41);
+    state.init_global_slice("x").unwrap();
+
+    // Evaluate an expression using the global.
+    let value = state
+        .eval("x + 1")
+        .expect("Failed to evaluate the expression");
+    assert!(matches!(value, Object::Int(42)));
+
+    // Ensure the main program still runs after evaluating.
+    state
+        .execute()
+        .expect("Failed to execute the state machine");
+    let value = state.eval("x").expect("Failed to evaluate the expression");
+    assert!(matches!(value, Object::Int(42)));
+
+    // Syntax errors are reported instead of a value.
+    assert_eq!(state.eval("x +").unwrap_err(), StateError::SyntaxError);
+
+    // A trailing comment doesn't hide the end of the expression.
+    assert!(matches!(state.eval("3 # comment"), Ok(Object::Int(3))));
+    assert!(matches!(state.eval("x; # comment"), Ok(Object::Int(42))));
+
+    // Only a single expression is evaluated, so nothing else can run alongside it.
+    for source in ["1); x = (5", "1; x = 5", "(1"] {
+        assert_eq!(
+            state.eval(source).unwrap_err(),
+            StateError::SyntaxError,
+            "{source}"
+        );
+    }
+    assert!(matches!(state.eval("x"), Ok(Object::Int(42))));
+}
+
+/// Test running extra chunks of source without replacing the main program.
+#[test]
+fn test_run_chunk() {
+    let mut state = State::from_source("total += 1;");
+    state.push_int(0);
+    state.init_global_slice("total").unwrap();
+    state.declare_global("double").unwrap();
+
+    // Chunks see globals assigned by earlier chunks.
+    state
+        .run_chunk("double = fn(n) { return n * 2; };")
+        .unwrap();
+    state.run_chunk("total = double(20);").unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<i64>("total"), Ok(41));
+
+    // A failed chunk leaves the main program in place.
+    assert_eq!(
+        state.run_chunk("total +").unwrap_err(),
+        StateError::SyntaxError
+    );
+    assert!(state.run_chunk("total = undefined_global;").is_err());
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<i64>("total"), Ok(42));
+}
+
+/// Test that popping a list or table removes the container itself, leaving the value below it on top.
+#[test]
+fn test_pop_containers() {
+    let mut state = State::default();
+    let values = [
+        Object::List(vec![Object::Int(1), Object::List(vec![Object::Int(2)])]),
+        Object::Table(std::collections::HashMap::from([(
+            HashableObject::Str("k".to_owned()),
+            Object::List(vec![Object::Bool(true)]),
+        )])),
+    ];
+    for value in values {
+        state.push_int(7);
+        state.push_object(&value).unwrap();
+        assert_eq!(state.pop_object(None), Ok(value));
+        assert_eq!(state.peek_type(), Type::Int);
+        assert_eq!(state.pop_int(), 7);
+        assert_eq!(state.stack_len(), 0);
+    }
+}
+
+/// Test rendering objects the same way YASL's `tostr` does.
+#[test]
+fn test_yasl_string() {
+    let mut state = State::default();
+
+    // Compare against YASL's own formatting for values with an unambiguous order.
+    for source in [
+        "[1, 'a', [2.0, 0.1, -0.0], undef, true, {'k': [1.5]}, [], {}]",
+        "100000000000000000000.0",
+        "[1.0 / 0.0, -1.0 / 0.0]",
+    ] {
+        let value = state
+            .eval(source)
+            .expect("Failed to evaluate the expression");
+        state
+            .push_object(&value)
+            .expect("Failed to push the object");
+        state.stringify_top();
+        assert_eq!(value.to_yasl_string(), state.pop_str().unwrap());
+    }
+
+    // Tables are sorted by key, and pretty printing indents each level.
+    let value = state
+        .eval("{'b': [1, {}], 'a': 2, 3: 'c'}")
+        .expect("Failed to evaluate the expression");
+    assert_eq!(value.to_yasl_string(), "{3: c, a: 2, b: [1, {}]}");
+    assert_eq!(
+        value.to_pretty_string(2),
+        "{\n  3: c,\n  a: 2,\n  b: [\n    1,\n    {}\n  ]\n}"
+    );
+}
+
+/// Test exposing command-line arguments to a script as the global list `args`.
+#[test]
+fn test_set_args() {
+    let mut state = State::from_source("let count = len args;");
+    state.set_args(&["script.yasl", "--flag", "value"]);
+    assert!(state.execute().is_ok());
+
+    let args = state
+        .eval("args")
+        .expect("Failed to evaluate the expression");
+    assert_eq!(args.to_yasl_string(), "[script.yasl, --flag, value]");
+}
+
+/// Test that a stack guard pops values left behind by an early return.
+#[test]
+fn test_stack_guard() {
+    fn leaky(state: &mut State) -> Result<(), StateError> {
+        let mut guard = state.stack_guard();
+        guard.push_list();
+        guard.push_str("stray");
+        guard.load_global_slice("missing")?;
+        Ok(())
+    }
+
+    let mut state = State::default();
+    state.push_int(7);
+    assert_eq!(leaky(&mut state), Err(StateError::Generic));
+
+    // Only the value pushed before the guard remains.
+    assert!(state.is_int());
+    assert_eq!(state.pop_int(), 7);
+}
+
+/// Test that the tracked stack length follows pushes, pops, table iteration, and function calls.
+#[test]
+fn test_stack_len() {
+    let mut state = State::from_source("pair = fn(x) { return x, x; };");
+    state.declare_global("pair").unwrap();
+    state
+        .execute()
+        .expect("Failed to execute the state machine");
+    assert_eq!(state.stack_len(), 0);
+    assert_eq!(state.top_index(), None);
+
+    // Build a table with one entry, then iterate over it.
+    state.push_table();
+    state.push_str("key");
+    state.push_int(1);
+    state.table_set().expect("Failed to set the table entry");
+    assert_eq!(state.stack_len(), 1);
+    state.push_undef();
+    assert!(state.table_next());
+    assert_eq!(state.stack_len(), 3);
+    state.pop();
+    assert!(!state.table_next());
+    assert_eq!(state.top_index(), Some(0));
+
+    // Converting an object pops the whole table.
+    state.pop_object(None).expect("Failed to pop the table");
+    assert_eq!(state.stack_len(), 0);
+
+    // A function call replaces the function and its arguments with the results.
+    state
+        .load_global_slice("pair")
+        .expect("Failed to load the function");
+    state.push_int(5);
+    assert_eq!(state.function_call(1), 2);
+    assert_eq!(state.stack_len(), 2);
+    assert_eq!(state.pop_int(), 5);
+    assert_eq!(state.pop_int(), 5);
+    assert_eq!(state.stack_len(), 0);
+}
+
+/// Test reading and writing table entries by key without pushing the key and value first.
+#[test]
+fn test_table_key_access() {
+    let mut state = State::default();
+    state.push_table();
+    let name = HashableObject::Str("name".to_owned());
+    let count = HashableObject::Int(3);
+    state
+        .table_set_key(&name, &Object::Str("yasl".to_owned()))
+        .unwrap();
+    state.table_set_key(&count, &Object::Float(1.5)).unwrap();
+    state
+        .table_set_key(&HashableObject::Bool(true), &Object::Int(7))
+        .unwrap();
+    assert_eq!(state.stack_len(), 1);
+
+    state.table_get_key(&count).unwrap();
+    assert_eq!(state.pop_float(), 1.5);
+    state.table_get_key(&name).unwrap();
+    assert_eq!(state.pop_str().unwrap(), "yasl");
+    state
+        .table_get_key(&HashableObject::Str("missing".to_owned()))
+        .unwrap();
+    assert!(state.is_undef());
+    state.pop();
+    assert_eq!(state.stack_len(), 1);
+
+    state.push_int(1);
+    assert_eq!(state.table_get_key(&name), Err(StateError::TypeError));
+    assert_eq!(
+        state.table_set_key(&name, &Object::Undef),
+        Err(StateError::TypeError)
+    );
+}
+
+/// Test converting values to objects without removing them from the stack.
+#[test]
+fn test_peek_object() {
+    let mut state = State::default();
+    state.push_int(3);
+    state.push_float(1.5);
+    state.push_list();
+    state.push_str("item");
+    state.list_push().unwrap();
+
+    // Scalars can be read from any index, counting from the bottom of the current frame.
+    assert!(matches!(state.peek_n_object(0), Ok(Object::Int(3))));
+    assert!(matches!(state.peek_n_object(1), Ok(Object::Float(f)) if f == 1.5));
+    assert_eq!(state.peek_n_object(2).unwrap_err(), StateError::TypeError);
+
+    // Any value can be read from the top.
+    let list = state.peek_object().expect("Failed to peek the list");
+    assert_eq!(list.to_yasl_string(), "[item]");
+    assert_eq!(state.stack_len(), 3);
+    assert!(state.is_list());
+}
+
+/// Test that checked pops report type mismatches and leave the value on the stack.
+#[test]
+fn test_checked_pops() {
+    let mut state = State::default();
+    state.push_str("text");
+    assert_eq!(state.pop_int_checked(), Err(StateError::TypeError));
+    assert_eq!(state.pop_bool_checked(), Err(StateError::TypeError));
+    assert_eq!(state.pop_str_checked().as_deref(), Ok("text"));
+
+    state.push_float(2.5);
+    state.push_int(4);
+    assert_eq!(state.pop_checked::<i64>(), Ok(4));
+    assert_eq!(state.pop_float_checked(), Ok(2.5));
+    assert_eq!(state.stack_len(), 0);
+}
+
+/// Test rendering the stack for debugging.
+#[test]
+fn test_dump_stack() {
+    let mut state = State::default();
+    state.push_int(3);
+    state.push_str("hidden");
+    state.push_bool(true);
+    state.push_list();
+    state.push_float(0.5);
+    state.list_push().unwrap();
+
+    assert_eq!(
+        state.dump_stack(),
+        "[0] int: 3\n[1] str\n[2] bool: true\n[3] list: [0.5]\n"
+    );
+    assert_eq!(state.stack_len(), 4);
+}
+
+/// Test reordering values on the stack without copying them.
+#[test]
+fn test_stack_reordering() {
+    let mut state = State::default();
+    for i in 0..4 {
+        state.push_int(i);
+    }
+    state.rotate(3, 1).unwrap();
+    assert_eq!(
+        state.dump_stack(),
+        "[0] int: 0\n[1] int: 3\n[2] int: 1\n[3] int: 2\n"
+    );
+    state.swap_top_two().unwrap();
+    state.remove(2).unwrap();
+    assert_eq!(state.dump_stack(), "[0] int: 0\n[1] int: 2\n[2] int: 1\n");
+    state.insert(2).unwrap();
+    assert_eq!(state.dump_stack(), "[0] int: 1\n[1] int: 0\n[2] int: 2\n");
+    assert_eq!(state.rotate(4, 1), Err(StateError::ValueError));
+
+    // Build a table entry by pushing the table last and moving it below the key and value.
+    state.push_str("key");
+    state.push_int(5);
+    state.push_table();
+    state.insert(2).unwrap();
+    state.table_set().unwrap();
+    let table = state.pop_object(None).unwrap();
+    assert_eq!(table.to_yasl_string(), "{key: 5}");
+    assert_eq!(state.stack_len(), 3);
+}
+
+// Host function which computes `a - b` after swapping its arguments, so that `a` is popped first.
+unsafe extern "C" fn swapped_sub(state: *mut YASL_State) -> i32 {
+    let mut state = State::from_memory(state).unwrap();
+    state.swap_top_two().unwrap();
+    let a = state.pop_int();
+    let b = state.pop_int();
+    state.push_int(a - b);
+    1
+}
+
+/// Test reordering the arguments of a host function, which its `State` didn't push.
+#[test]
+fn test_stack_reordering_in_callback() {
+    let mut state = State::default();
+    state.push_cfunction(swapped_sub, 2);
+    state.init_global_slice("sub").unwrap();
+    assert_eq!(state.eval("sub(10, 3)"), Ok(Object::Int(7)));
+}
+
+/// Test pushing several values of the same type in order.
+#[test]
+fn test_push_all() {
+    let mut state = State::from_source("sum = fn(a, b, c) -> a + b + c;");
+    state.declare_global("sum").unwrap();
+    state.execute().unwrap();
+
+    state.load_global_slice("sum").unwrap();
+    assert_eq!(state.push_all([1, 2, 3]), Ok(3));
+    assert_eq!(state.function_call(3), 1);
+    assert_eq!(state.pop_int_checked(), Ok(6));
+
+    let words = vec!["a".to_owned(), "b".to_owned()];
+    assert_eq!(state.push_all(&words), Ok(2));
+    assert_eq!(state.dump_stack(), "[0] str\n[1] str: \"b\"\n");
+}
+
+/// Test pushing values of different types through the generic entry point.
+#[test]
+fn test_generic_push() {
+    fn push_twice<T: Clone + IntoYasl>(state: &mut State, value: T) {
+        state.push(value.clone()).unwrap();
+        state.push(value).unwrap();
+    }
+
+    let mut state = State::from_source("");
+    push_twice(&mut state, 1.5);
+    push_twice(&mut state, "text");
+    state.push(Some(true)).unwrap();
+    state.push(None::<i64>).unwrap();
+    state.push((7, "b")).unwrap();
+    assert_eq!(state.stack_len(), 8);
+    assert_eq!(state.pop_str_checked(), Ok("b".to_owned()));
+    assert_eq!(state.pop_int_checked(), Ok(7));
+    assert_eq!(state.peek_type(), Type::Undef);
+}
+
+/// Test pushing lists and tables in one call.
+#[test]
+fn test_push_collections() {
+    let mut state = State::from_source("");
+
+    state.push_vec(&[1, 2, 3]).unwrap();
+    assert_eq!(
+        state.pop_object(None).unwrap().to_yasl_string(),
+        "[1, 2, 3]"
+    );
+
+    let map = std::collections::HashMap::from([("one", 1.5), ("two", 2.0)]);
+    state.push_map(&map).unwrap();
+    let Ok(Object::Table(table)) = state.pop_object(None) else {
+        panic!("expected a table");
+    };
+    assert_eq!(table.len(), 2);
+    assert!(matches!(
+        table.get(&HashableObject::Str("two".to_owned())),
+        Some(Object::Float(f)) if *f == 2.0
+    ));
+    assert_eq!(state.stack_len(), 0);
+}
+
+/// Test that `Option` maps onto `undef`.
+#[test]
+fn test_option_undef() {
+    let mut state = State::from_source("");
+
+    assert_eq!(state.push_all([Some(1), None]), Ok(2));
+    assert!(state.is_undef());
+    assert_eq!(state.pop_checked::<Option<i64>>(), Ok(None));
+    assert_eq!(state.pop_checked::<Option<i64>>(), Ok(Some(1)));
+
+    // Values of the wrong type are still errors and remain on the stack.
+    state.push_str("one");
+    assert_eq!(
+        state.pop_checked::<Option<i64>>(),
+        Err(StateError::TypeError)
+    );
+    assert_eq!(state.stack_len(), 1);
+}
+
+/// Test calling script functions with tuples of arguments and return values.
+#[test]
+fn test_tuple_calls() {
+    let mut state =
+        State::from_source("divmod = fn(a, b) -> a // b, a % b;\nname = fn() -> 'yasl', 3;");
+    state.declare_global("divmod").unwrap();
+    state.declare_global("name").unwrap();
+    state.execute().unwrap();
+
+    assert_eq!(state.call_global_slice("divmod", (17, 5)), Ok((3, 2)));
+    assert_eq!(
+        state.call_global_slice::<(String, i64)>("name", ()),
+        Ok(("yasl".to_owned(), 3))
+    );
+
+    // The number of return values must match.
+    assert_eq!(
+        state.call_global_slice::<i64>("divmod", (17, 5)),
+        Err(StateError::ValueError)
+    );
+    assert_eq!(state.stack_len(), 0);
+
+    // Tuples can also be pushed and popped directly.
+    assert_eq!(state.push_all([(1, true), (2, false)]), Ok(2));
+    assert_eq!(state.pop_checked::<(i64, bool)>(), Ok((2, false)));
+    assert_eq!(state.pop_checked::<(i64, bool)>(), Ok((1, true)));
+}
+
+/// Test that objects display as YASL literals which evaluate to the same value.
+#[test]
+fn test_display_literal() {
+    let object = Object::Table(std::collections::HashMap::from([
+        (
+            HashableObject::Str("list".to_owned()),
+            Object::List(vec![
+                Object::Int(i64::MIN),
+                Object::Float(1e20),
+                Object::Float(-0.125),
+                Object::Str("it's\n\\ok\x01".to_owned()),
+                Object::Undef,
+            ]),
+        ),
+        (HashableObject::Str("if".to_owned()), Object::Bool(true)),
+        (HashableObject::Int(2), Object::Table(Default::default())),
+    ]));
+    let literal = object.to_string();
+    assert_eq!(
+        literal,
+        r"{ 2: {}, 'if': true, .list: [(-9223372036854775807 - 1), 100000000000000000000.0, -0.125, 'it\'s\n\\ok\x01', undef] }"
+    );
+
+    let mut state = State::from_source(&format!("value = {literal};"));
+    state.declare_global("value").unwrap();
+    state.execute().unwrap();
+    let value = state.pop_global_slice("value", None).unwrap();
+    assert_eq!(value.to_string(), literal);
+}
+
+/// Test that popped objects compare the same way as YASL's `==`.
+#[test]
+fn test_object_eq() {
+    let mut state = State::from_source("value = [1, 2.0, 'three', { .four: 4 }];");
+    state.declare_global("value").unwrap();
+    state.execute().unwrap();
+
+    let value = state.pop_global_slice("value", None).unwrap();
+    assert_eq!(
+        value,
+        Object::List(vec![
+            Object::Float(1.0),
+            Object::Int(2),
+            Object::Str("three".to_owned()),
+            Object::Table(std::collections::HashMap::from([(
+                HashableObject::Str("four".to_owned()),
+                Object::Int(4)
+            )])),
+        ])
+    );
+    assert_ne!(Object::Float(f64::NAN), Object::Float(f64::NAN));
+    assert_ne!(Object::Int(0), Object::Bool(false));
+    assert_ne!(Object::Undef, Object::List(vec![]));
+}
+
+/// Test that table keys have a deterministic order across types.
+#[test]
+fn test_sorted_table_keys() {
+    let mut state = State::from_source("value = { 'b': 1, 'a': 2, 2: 3, 1.5: 4, true: 5, 1: 6 };");
+    state.declare_global("value").unwrap();
+    state.execute().unwrap();
+
+    let value = state.pop_global_slice("value", None).unwrap();
+    let keys = value
+        .table_sorted_iter()
+        .unwrap()
+        .map(|(k, _)| k.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, ["true", "1", "1.5", "2", "'a'", "'b'"]);
+    assert!(HashableObject::Int(1) < HashableObject::try_from(Object::Float(1.0)).unwrap());
+    assert!(Object::Int(1).table_sorted_iter().is_none());
+}
+
+/// Test that dynamically named globals stay valid after the original strings are dropped.
+#[test]
+fn test_dynamic_global_names() {
+    let mut state = State::from_source("total = value_3 + value_99;");
+    state.declare_global("total").unwrap();
+    for i in 0..100 {
+        let name = format!("value_{i}");
+        state.push_int(i);
+        state.init_global_slice(&name).unwrap();
+        state.push_int(i * 2);
+        state.set_global_slice(&name).unwrap();
+    }
+    state.execute().unwrap();
+
+    assert_eq!(state.pop_global_slice("total", None), Ok(Object::Int(204)));
+    for i in 0..100 {
+        assert_eq!(
+            state.pop_global_slice(&format!("value_{i}"), None),
+            Ok(Object::Int(i * 2))
+        );
+    }
+}
+
+/// Test that identifiers are validated as a whole, and that the first invalid character is reported.
+#[test]
+fn test_validate_identifier() {
+    assert_eq!(validate_identifier("_snake$Case9"), Ok(()));
+    assert_eq!(validate_identifier(""), Err(InvalidIdentifier::Empty));
+    assert_eq!(
+        validate_identifier("1 + 1"),
+        Err(InvalidIdentifier::InvalidChar {
+            index: 0,
+            character: '1'
+        })
+    );
+    assert_eq!(
+        validate_identifier("a^b"),
+        Err(InvalidIdentifier::InvalidChar {
+            index: 1,
+            character: '^'
+        })
+    );
+    assert_eq!(
+        validate_identifier("naïve"),
+        Err(InvalidIdentifier::InvalidChar {
+            index: 2,
+            character: 'ï'
+        })
+    );
+
+    let mut state = State::from_source("");
+    assert_eq!(
+        state.declare_global("`x`"),
+        Err(InvalidIdentifier::InvalidChar {
+            index: 0,
+            character: '`'
+        })
+    );
+}
+
+/// Test popping deeply nested lists. Each level of nesting uses a slot of YASL's stack while its items are popped.
+#[test]
+fn test_pop_nested_object() {
+    const DEPTH: usize = 1_000;
+    let mut state = State::from_source("");
+
+    // Build `[[[...]]]` from the inside out.
+    state.push_list();
+    for _ in 0..DEPTH {
+        state.push_list();
+        state.swap_top_two().unwrap();
+        state.list_push().unwrap();
+    }
+
+    let mut object = state.pop_object(Some(Type::List)).unwrap();
+    assert_eq!(state.stack_len(), 0);
+    for _ in 0..DEPTH {
+        let Object::List(mut items) = object else {
+            panic!("expected a list");
+        };
+        assert_eq!(items.len(), 1);
+        object = items.pop().unwrap();
+    }
+    assert_eq!(object, Object::List(vec![]));
+}
+
+/// Test popping lists and tables which contain themselves.
+#[test]
+fn test_pop_cyclic_object() {
+    let mut state = State::from_source(
+        "
+        const shared = [1];
+        pair = [shared, shared];
+        list = [1, 2];
+        list->push(list);
+        table = { 'inner': { 'n': 0 } };
+        table.inner.outer = table;
+        ",
+    );
+    for name in ["pair", "list", "table"] {
+        state.declare_global(name).unwrap();
+    }
+    state.execute().unwrap();
+
+    // A value referenced twice is not a cycle.
+    state.load_global_slice("pair").unwrap();
+    let shared = Object::List(vec![Object::Int(1)]);
+    assert_eq!(
+        state.pop_object(None).unwrap(),
+        Object::List(vec![shared.clone(), shared])
+    );
+
+    for name in ["list", "table"] {
+        state.load_global_slice(name).unwrap();
+        assert_eq!(state.pop_object(None), Err(StateError::ValueError));
+        assert_eq!(state.stack_len(), 0);
+    }
+
+    // The stack is left as it was.
+    state.push_int(7);
+    state.load_global_slice("table").unwrap();
+    assert!(state.pop_object(None).is_err());
+    assert_eq!(state.pop_int(), 7);
+}
+
+#[test]
+fn test_analyze() {
+    let info = State::analyze(
+        r##"
+        let total = offset
+        fn add(a, const b) {
+            let sum = a + b + bonus
+            return sum
+        }
+        const double = fn(x) -> x * scale
+        for let i = 0; i < limit; i += 1 {
+            total += add(i, 1)
+        }
+        for item in items {
+            echo "#{item}: #{label}"
+        }
+        let squares = [n * n for n in range(count)]
+        counter += 1
+        result, status = api.run(total)->unwrap(), squares
+        fn config.reload() { return 0; }
+        "##,
+    );
+    let names = |names: &[&str]| names.iter().map(|&n| n.to_owned()).collect();
+    assert_eq!(
+        info.reads,
+        names(&[
+            "api", "bonus", "config", "count", "counter", "items", "label", "limit", "offset",
+            "range", "scale",
+        ])
+    );
+    assert_eq!(info.assigns, names(&["counter", "result", "status"]));
+    assert_eq!(info.globals().count(), 13);
+}
+
+#[test]
+fn test_type_names() {
+    let mut state = State::from_source("");
+    state.push_list();
+    assert_eq!(state.peek_type().to_string(), "list");
+    assert_eq!(Some(state.peek_type().name()), state.peek_type_name_slice());
+
+    assert_eq!(Type::CFn.name(), "fn");
+    assert_eq!(
+        format!("expected {}, got {}", Type::Str, Type::Table),
+        "expected str, got table"
+    );
+    assert_eq!("userdata".parse(), Ok(Type::UserData));
+    assert_eq!("fn".parse(), Ok(Type::Fn));
+    assert_eq!("string".parse::<Type>(), Err(ParseTypeError));
+}
+
+/// Test assigning globals from `Object`s in one call.
+#[test]
+fn test_global_values() {
+    let mut state = State::from_source("const doubled = [x * 2 for x in items];");
+    let items = Object::List(vec![Object::Int(1), Object::Int(2)]);
+    state.init_global_value("items", &Object::Undef).unwrap();
+    state.set_global_value("items", &items).unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.pop_global_slice("items", Some(Type::List)), Ok(items));
+
+    assert!(matches!(
+        state.init_global_value("not valid", &Object::Int(1)),
+        Err(Error::InvalidIdentifier(InvalidIdentifier::InvalidChar {
+            index: 3,
+            character: ' '
+        }))
+    ));
+    assert_eq!(
+        state.set_global_value("missing", &Object::Int(1)),
+        Err(StateError::Generic)
+    );
+    assert_eq!(state.stack_len(), 0);
+}
+
+/// Test reading typed values back from globals.
+#[test]
+fn test_get_global() {
+    let mut state = State::from_source("name = 'yasl'; count = 3; missing = undef;");
+    state.declare_global("name").unwrap();
+    state.declare_global("count").unwrap();
+    state.declare_global("missing").unwrap();
+    state.execute().unwrap();
+
+    assert_eq!(state.get_global::<String>("name"), Ok("yasl".to_owned()));
+    assert_eq!(state.get_global::<i64>("count"), Ok(3));
+    assert_eq!(state.get_global::<Option<i64>>("missing"), Ok(None));
+    assert_eq!(state.get_global::<f64>("count"), Err(StateError::TypeError));
+    assert_eq!(
+        state.get_global::<(i64, i64)>("count"),
+        Err(StateError::ValueError)
+    );
+    assert_eq!(state.get_global::<i64>("nothing"), Err(StateError::Generic));
+    assert_eq!(state.stack_len(), 0);
+}
+
+/// Test initializing globals with names which don't outlive the call.
+#[test]
+fn test_init_global_borrowed_name() {
+    let mut state = State::from_source("total = first + second;");
+    for (i, name) in ["first", "second"].into_iter().enumerate() {
+        let owned = std::ffi::CString::new(name).unwrap();
+        state.push_int(i as i64 + 1);
+        state.init_global(&owned).unwrap();
+    }
+    state.push_undef();
+    state.init_global(c"total").unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<i64>("total"), Ok(3));
+}
+
+/// Test loading source from bytes and readers.
+#[test]
+fn test_from_reader() {
+    // Bytes outside of string literals must still be valid source, but comments may hold anything.
+    let mut state = State::from_bytes(b"# \xff\xfe\nx = 6 * 7;");
+    state.declare_global("x").unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<i64>("x"), Ok(42));
+
+    let mut state = State::from_reader(std::io::Cursor::new("y = 'read';")).unwrap();
+    state.declare_global("y").unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<String>("y"), Ok("read".to_owned()));
+
+    struct Failing;
+    impl std::io::Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+    assert!(State::from_reader(Failing).is_err());
+}
+
+/// Test the errors from loading a script file.
+#[test]
+fn test_from_file() {
+    let path = std::path::Path::new("tests").join("test.yasl");
+    assert!(State::from_file(&path).is_ok());
+
+    let missing = State::from_file("tests/missing.yasl").err().unwrap();
+    assert!(matches!(missing, LoadError::Io(ref e) if e.kind() == std::io::ErrorKind::NotFound));
+    assert!(missing.to_string().starts_with("unable to open the script"));
+
+    assert!(matches!(
+        State::from_file("tests/test.yasl\0"),
+        Err(LoadError::Io(_) | LoadError::InvalidPath)
+    ));
+}
+
+/// Test loading modules from the search paths set for `require`.
+#[test]
+fn test_require_paths() {
+    let mut state = State::from_source(
+        "greeting = require('greet').greeting; extra = require('extra'); again = require('greet').greeting;",
+    );
+    state.declare_global("greeting").unwrap();
+    state.declare_global("extra").unwrap();
+    state.declare_global("again").unwrap();
+    state.set_require_paths(&["tests/require"]);
+    state.add_require_path("tests/require/more");
+    assert_eq!(
+        state.require_paths(),
+        [
+            std::path::PathBuf::from("tests/require"),
+            std::path::PathBuf::from("tests/require/more")
+        ]
+    );
+
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<String>("greeting").unwrap(), "hello");
+    assert_eq!(state.get_global::<String>("extra").unwrap(), "extra");
+    assert_eq!(state.get_global::<String>("again").unwrap(), "hello");
+
+    // Modules are only found on the search paths.
+    assert!(state.run_chunk("require('test');").is_err());
+}
+
+/// Test disabling the loading of native code, even after the standard libraries are declared again.
+#[test]
+fn test_disable_require_c() {
+    let mut state = State::default();
+    state.declare_libs();
+    assert!(!state.is_require_c_disabled());
+    state.disable_require_c();
+    assert!(state.is_require_c_disabled());
+    assert_eq!(
+        state.run_chunk("__require_c__('libm.so');"),
+        Err(StateError::Generic)
+    );
+
+    // Loading a module and declaring the libraries again keep it disabled.
+    state.declare_global("greeting").unwrap();
+    state
+        .run_chunk("greeting = require('tests/require/greet').greeting;")
+        .unwrap();
+    assert_eq!(state.get_global::<String>("greeting").unwrap(), "hello");
+    assert_eq!(
+        state.run_chunk("__require_c__('libm.so');"),
+        Err(StateError::Generic)
+    );
+    state.declare_libs();
+    assert_eq!(
+        state.run_chunk("__require_c__('libm.so');"),
+        Err(StateError::Generic)
+    );
+
+    // Disabling doesn't declare `require` for states without it.
+    let mut state = State::default();
+    state.disable_require_c();
+    assert_eq!(
+        state.run_chunk("require('greet');"),
+        Err(StateError::SyntaxError)
+    );
+}
+
+/// Test rendering values anywhere on the stack without changing the stack.
+#[test]
+fn test_to_display_string() {
+    let mut state = State::default();
+    let list = state.eval("[1, 'a', 2.5]").unwrap();
+    state.push_object(&list).unwrap();
+    state.push_int(7);
+    state.push_str("top");
+
+    assert_eq!(state.to_display_string(0).unwrap(), "[1, a, 2.5]");
+    assert_eq!(state.to_display_string(1).unwrap(), "7");
+    assert_eq!(state.to_display_string(2).unwrap(), "top");
+    assert_eq!(state.to_display_string(3), Err(StateError::ValueError));
+
+    // The stack is unchanged.
+    assert_eq!(state.stack_len(), 3);
+    assert_eq!(state.pop_str().unwrap(), "top");
+    assert_eq!(state.pop_int(), 7);
+    assert!(state.is_list());
+}
+
+/// Test concatenating the top values of the stack into one string.
+#[test]
+fn test_concat_top() {
+    let mut state = State::default();
+    state.push_str("below");
+    state.push_int(1);
+    state.push_str("a");
+    state.push_float(2.5);
+    state.push_bool(true);
+
+    state.concat_top(4).unwrap();
+    assert_eq!(state.stack_len(), 2);
+    assert_eq!(state.pop_str().unwrap(), "1a2.5true");
+    assert_eq!(state.concat_top(2), Err(StateError::ValueError));
+
+    state.concat_top(0).unwrap();
+    assert_eq!(state.pop_str().unwrap(), "");
+    assert_eq!(state.pop_str().unwrap(), "below");
+}
+
+// Variadic function which renders its arguments, after an optional fixed separator.
+unsafe extern "C" fn join_vargs(state: *mut YASL_State) -> i32 {
+    let mut state = State::from_memory(state).unwrap();
+    let joined = state
+        .vargs()
+        .map(|arg| arg.to_yasl_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    state.push_str(&joined);
+    1
+}
+unsafe extern "C" fn join_vargs_sep(state: *mut YASL_State) -> i32 {
+    let mut state = State::from_memory(state).unwrap();
+    let args = state
+        .vargs()
+        .map(|arg| arg.to_yasl_string())
+        .collect::<Vec<_>>();
+    state.pop();
+    let sep = state.pop_str().unwrap();
+    state.push_str(&args.join(&sep));
+    1
+}
+
+/// Test reading the variadic arguments of a C function in order.
+#[test]
+fn test_vargs() {
+    let mut state = State::from_source(
+        "all = join(1, 'a', [2.5], undef); none = join(); sep = join_sep(', ', 1, 2, 3);",
+    );
+    state.push_cfunction(join_vargs, -1);
+    state.init_global_slice("join").unwrap();
+    state.push_cfunction(join_vargs_sep, -2);
+    state.init_global_slice("join_sep").unwrap();
+    for name in ["all", "none", "sep"] {
+        state.declare_global(name).unwrap();
+    }
+
+    state.execute().unwrap();
+    assert_eq!(
+        state.get_global::<String>("all").unwrap(),
+        "1 a [2.5] undef"
+    );
+    assert_eq!(state.get_global::<String>("none").unwrap(), "");
+    assert_eq!(state.get_global::<String>("sep").unwrap(), "1, 2, 3");
+}
+
+// Host function which fails for negative inputs.
+unsafe extern "C" fn checked_sqrt(state: *mut YASL_State) -> i32 {
+    run_host_fn(state, |state| {
+        let x = state.pop_float();
+        if x < 0.0 {
+            return Err(ScriptError::new(
+                StateError::ValueError,
+                "ValueError: sqrt expected a non-negative float.",
+            ));
+        }
+        state.push_float(x.sqrt());
+        Ok(1)
+    })
+}
+
+/// Test host functions which return a `Result` instead of a return count.
+#[test]
+fn test_run_host_fn() {
+    let mut state = State::default();
+    state.push_cfunction(checked_sqrt, 1);
+    state.init_global_slice("sqrt").unwrap();
+    assert!(matches!(state.eval("sqrt(16.0)"), Ok(Object::Float(x)) if x == 4.0));
+
+    state.set_printerr_tostr();
+    assert_eq!(state.run_chunk("sqrt(-1.0);"), Err(StateError::ValueError));
+    state.load_printerr();
+    assert_eq!(
+        state.pop_str().unwrap().trim(),
+        "ValueError: sqrt expected a non-negative float. (line 1)"
+    );
+}
+
+/// Test providing globals which a script reads without them being declared.
+#[test]
+fn test_missing_global_handler() {
+    use std::sync::{Arc, Mutex};
+
+    let mut state = State::from_source("result = answer + 1;");
+    state.declare_global("result").unwrap();
+    let requested = Arc::new(Mutex::new(Vec::new()));
+    state.set_missing_global_handler({
+        let requested = Arc::clone(&requested);
+        move |name| {
+            requested.lock().unwrap().push(name.to_owned());
+            match name {
+                "answer" => Some(Object::Int(41)),
+                "half" => Some(Object::Float(0.5)),
+                _ => None,
+            }
+        }
+    });
+
+    state.execute().unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<i64>("result"), Ok(42));
+    assert!(matches!(state.eval("half * 4"), Ok(Object::Float(x)) if x == 2.0));
+
+    // Each global is only requested until it's declared, and unknown globals still fail to compile.
+    assert_eq!(state.eval("typo"), Err(StateError::SyntaxError));
+    assert_eq!(*requested.lock().unwrap(), ["answer", "half", "typo"]);
+}
+
+/// Test receiving the values of `echo` statements instead of them being printed.
+#[test]
+fn test_echo_handler() {
+    use std::sync::{Arc, Mutex};
+
+    let echoed = Arc::new(Mutex::new(Vec::new()));
+    let record = |echoed: &Arc<Mutex<Vec<String>>>| {
+        let echoed = Arc::clone(echoed);
+        move |values: &[Object]| {
+            let values = values
+                .iter()
+                .map(Object::to_yasl_string)
+                .collect::<Vec<_>>();
+            echoed.lock().unwrap().push(values.join(" | "));
+        }
+    };
+
+    let mut state = State::from_source(
+        "echo 1, 'a', [2.5]
+let x = 3
+if x > 2 { echo x; }
+echo \"x is #{x};\"; echo x +
+    1
+let f = fn() { echo 'in f'; }
+f();",
+    );
+    state.set_printout_tostr();
+    state.set_echo_handler(record(&echoed));
+    state.execute().unwrap();
+    assert_eq!(
+        *echoed.lock().unwrap(),
+        ["1 | a | [2.5]", "3", "x is 3;", "4", "in f"]
+    );
+
+    // Nothing is printed, and chunks are rewritten too.
+    state.load_printout();
+    assert_eq!(state.pop_str().unwrap(), "");
+    echoed.lock().unwrap().clear();
+    state.run_chunk("echo 'chunk';").unwrap();
+    assert_eq!(*echoed.lock().unwrap(), ["chunk"]);
+    assert_eq!(
+        state.run_chunk("echo 1;\nlet q = 1 // 0;"),
+        Err(StateError::DivideByZeroError)
+    );
+
+    // Scripts loaded from files are rewritten as well.
+    let echoed = Arc::new(Mutex::new(Vec::new()));
+    let mut state = State::from_file("tests/test.yasl").unwrap();
+    state.push_int(42);
+    state.init_global_slice("answer").unwrap();
+    state.push_cfunction(rust_print, 0);
+    state.init_global_slice("rust_print").unwrap();
+    state.set_echo_handler(record(&echoed));
+    state.execute().unwrap();
+    assert_eq!(
+        *echoed.lock().unwrap(),
+        ["The variable 'answer' has value 42"]
+    );
+}
+
+/// Test counting executions, errors, and host function calls.
+#[test]
+fn test_metrics() {
+    let mut state = State::from_source("let x = sqrt(4.0);");
+    state.push_cfunction(checked_sqrt, 1);
+    state.init_global_slice("sqrt").unwrap();
+
+    // Nothing is counted until metrics are enabled.
+    state.execute().unwrap();
+    assert_eq!(state.metrics(), Default::default());
+    state.enable_metrics();
+
+    state.execute().unwrap();
+    state.set_printerr_tostr();
+    assert_eq!(state.run_chunk("sqrt(-1.0);"), Err(StateError::ValueError));
+    assert_eq!(
+        state.run_chunk("let y = 1 // 0;"),
+        Err(StateError::DivideByZeroError)
+    );
+    assert_eq!(
+        state.run_chunk("sqrt(1.0); sqrt(9.0);"),
+        Ok(StateSuccess::Generic)
+    );
+
+    let metrics = state.metrics();
+    assert_eq!(metrics.executions, 4);
+    assert_eq!(metrics.host_calls, 4);
+    assert_eq!(metrics.error_count(), 2);
+    assert_eq!(metrics.errors[&StateError::ValueError], 1);
+    assert_eq!(metrics.errors[&StateError::DivideByZeroError], 1);
+    assert!(metrics.vm_time > std::time::Duration::ZERO);
+
+    state.reset_metrics();
+    assert_eq!(state.metrics().executions, 0);
+}
+
+/// Test pushing interned strings, which are kept by the state between pushes.
+#[test]
+fn test_push_interned() {
+    let mut state = State::default();
+    state.push_list();
+    for _ in 0..3 {
+        state.push_interned("name");
+        state.list_push().unwrap();
+        state.push_interned("id");
+        state.list_push().unwrap();
+    }
+    let list = state.pop_object(Some(Type::List)).unwrap();
+    assert_eq!(list.to_yasl_string(), "[name, id, name, id, name, id]");
+    assert_eq!(state.stack_len(), 0);
+
+    // Strings which were already pushed outlive clearing them.
+    state.push_interned("name");
+    state.clear_interned();
+    assert_eq!(state.pop_str().unwrap(), "name");
+    state.push_interned("id");
+    assert_eq!(state.pop_str().unwrap(), "id");
+}
+
+/// Test pushing strings which live as long as the program.
+#[test]
+fn test_push_str_static() {
+    let mut state = State::default();
+    state.push_str_static("borrowed\0");
+    state.push_str_static("copied");
+    assert_eq!(state.stack_len(), 2);
+    assert_eq!(state.pop_str().unwrap(), "copied");
+    assert_eq!(state.pop_str().unwrap(), "borrowed");
+}
+
+/// Test pushing and popping paths.
+#[test]
+fn test_push_path() {
+    use std::path::{Path, PathBuf};
+
+    let mut state = State::default();
+    state.push_path(Path::new("scripts/main.yasl"));
+    assert_eq!(state.peek_str().unwrap(), "scripts/main.yasl");
+    assert_eq!(state.pop_path(), Ok(PathBuf::from("scripts/main.yasl")));
+
+    // Paths which aren't valid UTF-8 keep their bytes on Unix.
+    #[cfg(unix)]
+    {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let path = Path::new(OsStr::from_bytes(b"data/\xff.bin"));
+        state.push_path(path);
+        assert_eq!(state.pop_path(), Ok(path.to_path_buf()));
+    }
+
+    state.push_int(1);
+    assert_eq!(state.pop_path(), Err(StateError::TypeError));
+    assert_eq!(state.pop_int(), 1);
+}
+
+// Function which borrows the state it is called with, and returns its argument plus one.
+unsafe extern "C" fn increment(mut state: *mut YASL_State) -> i32 {
+    let mut state = StateRef::new(&mut state).unwrap();
+    let n = state.pop_int();
+    state.push_int(n + 1);
+    1
+}
+
+/// Test borrowing the state in a callback.
+#[test]
+fn test_state_ref() {
+    let mut state = State::from_source("x = increment(41);");
+    state.push_cfunction(increment, 1);
+    state.init_global_slice("increment").unwrap();
+    state.declare_global("x").unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<i64>("x"), Ok(42));
+
+    // The state is still usable after the borrowed states are dropped.
+    assert_eq!(state.eval("increment(1)").unwrap(), Object::Int(2));
+    assert!(StateRef::new(&mut std::ptr::null_mut()).is_none());
+}
+
+yaslapi::new_cfn! {
+    /// Split a string at the given index, with typed parameters.
+    SPLIT_AT(state, (s: String, at: Option<i64>)) => {
+        let at = usize::try_from(at.unwrap_or(0)).unwrap_or(0).min(s.len());
+        let (left, right) = s.split_at(at);
+        Ok((left.to_owned(), right.to_owned()))
+    }
+}
+
+/// Test functions with typed parameters, which check their arguments and push their results.
+#[test]
+fn test_typed_cfn() {
+    assert_eq!(SPLIT_AT.args, 2);
+    let mut state = State::from_source("a, b = split_at('yasl', 2); c, d = split_at('api');");
+    state.push_cfunction(SPLIT_AT.cfn, SPLIT_AT.args as i32);
+    state.init_global_slice("split_at").unwrap();
+    for name in ["a", "b", "c", "d"] {
+        state.declare_global(name).unwrap();
+    }
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<String>("a").unwrap(), "ya");
+    assert_eq!(state.get_global::<String>("b").unwrap(), "sl");
+    assert_eq!(state.get_global::<String>("c").unwrap(), "");
+    assert_eq!(state.get_global::<String>("d").unwrap(), "api");
+
+    // Arguments of the wrong type raise a type error.
+    state.set_printerr_tostr();
+    assert_eq!(
+        state.run_chunk("split_at('yasl', 'x');"),
+        Err(StateError::TypeError)
+    );
+    state.load_printerr();
+    assert!(state
+        .pop_str()
+        .unwrap()
+        .contains("split_at expected arg in position 1 to be of type int, got arg of type str"));
+}
+
+/// Test recording and rendering the documentation of registered functions.
+#[test]
+fn test_api_docs() {
+    let mut state = State::from_source("a, b = split_at('yasl', 1);");
+    state.init_function("split_at", &SPLIT_AT).unwrap();
+    let increment = YaslCFn {
+        cfn: increment,
+        args: 1,
+        doc: "",
+        params: &[],
+    };
+    state.init_function("increment", &increment).unwrap();
+    assert_eq!(
+        state.init_function("bad name", &SPLIT_AT),
+        Err(InvalidIdentifier::InvalidChar {
+            index: 3,
+            character: ' '
+        })
+    );
+    state.declare_global("a").unwrap();
+    state.declare_global("b").unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<String>("b").unwrap(), "asl");
+
+    let docs = state.api_docs();
+    assert_eq!(docs.len(), 2);
+    assert_eq!(docs[0].signature(), "split_at(s: str, at: int)");
+    assert_eq!(
+        docs[0].doc,
+        "Split a string at the given index, with typed parameters."
+    );
+    assert_eq!(docs[1].signature(), "increment(arg0)");
+    assert_eq!(
+        yaslapi::docs::render_markdown(&docs),
+        "### `split_at(s: str, at: int)`\n\nSplit a string at the given index, with typed parameters.\n\n### `increment(arg0)`\n"
+    );
+}
+
+// Function which checks its arguments before returning the length of its string argument.
+unsafe extern "C" fn checked_len(state: *mut YASL_State) -> i32 {
+    run_host_fn(state, |state| {
+        yaslapi::expect_args!(state, "checked_len", [List, Str, Fn?])?;
+        state.pop();
+        let len = state.pop_str().unwrap_or_default().len();
+        state.pop();
+        state.push_int(len as i64);
+        Ok(1)
+    })
+}
+
+/// Test checking the types of a host function's arguments in one place.
+#[test]
+fn test_expect_args() {
+    let mut state =
+        State::from_source("x = checked_len([], 'four', fn() {}) + checked_len([], 'ab');");
+    state.push_cfunction(checked_len, 3);
+    state.init_global_slice("checked_len").unwrap();
+    state.declare_global("x").unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.get_global::<i64>("x"), Ok(6));
+
+    state.set_printerr_tostr();
+    assert_eq!(
+        state.run_chunk("checked_len([], 1);"),
+        Err(StateError::TypeError)
+    );
+    assert_eq!(
+        state.run_chunk("checked_len({}, '');"),
+        Err(StateError::TypeError)
+    );
+    assert_eq!(
+        state.run_chunk("checked_len([]);"),
+        Err(StateError::TypeError)
+    );
+    state.load_printerr();
+    let errors = state.pop_str().unwrap();
+    assert!(errors
+        .contains("checked_len expected arg in position 1 to be of type str, got arg of type int"));
+    assert!(errors.contains(
+        "checked_len expected arg in position 0 to be of type list, got arg of type table"
+    ));
+    assert!(errors.contains(
+        "checked_len expected arg in position 1 to be of type str, got arg of type undef"
+    ));
+}
+
+yaslapi::new_cfn! {
+    /// Pad a string to a width, with a default width and padding.
+    PAD(state, (s: String, width: i64 = 8, fill: String = "-".to_owned())) => {
+        let width = usize::try_from(width).unwrap_or(0);
+        let mut padded = s;
+        while padded.len() < width {
+            padded.push_str(&fill);
+        }
+        Ok(padded)
+    }
+}
+
+/// Test typed parameters with defaults, which are used for omitted and `undef` arguments.
+#[test]
+fn test_typed_cfn_defaults() {
+    let mut state = State::default();
+    state.init_function("pad", &PAD).unwrap();
+    assert_eq!(PAD.args, 3);
+    assert_eq!(
+        state.eval("pad('ab')").unwrap(),
+        Object::Str("ab------".to_owned())
+    );
+    assert_eq!(
+        state.eval("pad('ab', 4)").unwrap(),
+        Object::Str("ab--".to_owned())
+    );
+    assert_eq!(
+        state.eval("pad('ab', undef, '.')").unwrap(),
+        Object::Str("ab......".to_owned())
+    );
+    assert_eq!(
+        state.api_docs()[0].signature(),
+        "pad(s: str, width: int, fill: str)"
+    );
+
+    state.set_printerr_tostr();
+    assert_eq!(state.eval("pad('ab', '4')"), Err(StateError::TypeError));
+}
+
+// Function which formats its string argument with keyword arguments.
+unsafe extern "C" fn format_kwargs(state: *mut YASL_State) -> i32 {
+    run_host_fn(state, |state| {
+        let mut kwargs = yaslapi::kwargs::KwArgs::pop(state, "format")?;
+        let width = kwargs.get_or::<i64>("width", 0)?;
+        let upper = kwargs.get::<bool>("upper")?.unwrap_or_default();
+        let prefix = kwargs.require::<String>("prefix")?;
+        kwargs.deny_unknown()?;
+
+        let s = state.pop_str().unwrap_or_default();
+        let s = if upper { s.to_uppercase() } else { s };
+        state.push_str(&format!("{prefix}{s:>width$}", width = width as usize));
+        Ok(1)
+    })
+}
+
+/// Test reading keyword arguments from a trailing table.
+#[test]
+fn test_kwargs() {
+    let mut state = State::default();
+    state.push_cfunction(format_kwargs, 2);
+    state.init_global_slice("format").unwrap();
+    assert_eq!(
+        state
+            .eval("format('ab', { 'prefix': '>', 'width': 4, 'upper': true })")
+            .unwrap(),
+        Object::Str(">  AB".to_owned())
+    );
+    assert_eq!(
+        state
+            .eval("format('ab', { 'prefix': '', 'upper': undef })")
+            .unwrap(),
+        Object::Str("ab".to_owned())
+    );
+
+    state.set_printerr_tostr();
+    assert_eq!(state.eval("format('ab')"), Err(StateError::ValueError));
+    assert_eq!(
+        state.eval("format('ab', { 'prefix': '', 'width': '4' })"),
+        Err(StateError::TypeError)
+    );
+    assert_eq!(
+        state.eval("format('ab', { 'prefix': '', 'widht': 4 })"),
+        Err(StateError::ValueError)
+    );
+    assert_eq!(state.eval("format('ab', 4)"), Err(StateError::TypeError));
+    state.load_printerr();
+    let errors = state.pop_str().unwrap();
+    assert!(errors.contains("ValueError: format expected keyword argument prefix."));
+    assert!(errors.contains(
+        "TypeError: format expected keyword argument width to be of type int, got arg of type str."
+    ));
+    assert!(errors.contains("ValueError: format got unknown keyword argument widht."));
+    assert!(errors.contains(
+        "TypeError: format expected keyword arguments of type table, got arg of type int."
+    ));
+}
+
+/// Test converting the crate's errors into `yaslapi::Error` with `?`.
+#[test]
+fn test_unified_error() {
+    fn run(source: &str) -> Result<i64, yaslapi::Error> {
+        let mut state = State::from_source(source);
+        validate_identifier("result")?;
+        state.declare_global("result")?;
+        state.execute()?;
+        let value = state.pop_global_slice("result", None)?;
+        Ok(i64::try_from(value)?)
+    }
+
+    assert_eq!(run("result = 6 * 7;").unwrap(), 42);
+    assert!(matches!(
+        run("result = 'text';"),
+        Err(yaslapi::Error::UnexpectedType(Type::Str))
+    ));
+    assert!(matches!(
+        run("result = ;"),
+        Err(yaslapi::Error::State(StateError::SyntaxError))
+    ));
+    let error: yaslapi::Error = std::ffi::CString::new("a\0b").unwrap_err().into();
+    assert!(matches!(error, yaslapi::Error::Nul(_)));
+    assert_eq!(
+        yaslapi::Error::from(StateError::DivideByZeroError).to_string(),
+        "division by zero"
+    );
+}
+
+#[test]
+fn test_unknown_codes() {
+    assert_eq!(StateError::from(999), StateError::Unknown(999));
+    assert_eq!(i32::from(StateError::Unknown(999)), 999);
+    let code = i32::from(StateError::TypeError);
+    assert_eq!(StateError::from(code), StateError::TypeError);
+
+    assert_eq!(Type::from(999), Type::Unknown(999));
+    assert_eq!(Type::Unknown(999).name(), "unknown");
+    assert_eq!(Type::from(i32::from(Type::Table)), Type::Table);
+}
+
+#[test]
+fn test_pop_number() {
+    let mut state = State::default();
+    state.push_int(3);
+    state.push_float(0.5);
+    state.push_str("4");
+
+    assert_eq!(state.pop_number(), Err(StateError::TypeError));
+    assert_eq!(state.stack_len(), 3);
+    state.pop();
+
+    assert_eq!(state.peek_number(), Ok(0.5));
+    assert_eq!(state.pop_number(), Ok(0.5));
+    assert_eq!(state.pop_number(), Ok(3.0));
+    assert_eq!(state.stack_len(), 0);
+}
+
+#[test]
+fn test_strict_peek() {
+    let mut state = State::default();
+    state.push_str("false");
+    assert_eq!(state.peek_bool_strict(), None);
+    assert_eq!(state.peek_int_strict(), None);
+    assert_eq!(state.peek_float_strict(), None);
+
+    state.push_bool(false);
+    assert_eq!(state.peek_bool_strict(), Some(false));
+    state.push_int(0);
+    assert_eq!(state.peek_int_strict(), Some(0));
+    assert_eq!(state.peek_float_strict(), None);
+    state.push_float(0.0);
+    assert_eq!(state.peek_float_strict(), Some(0.0));
+}
+
+#[test]
+fn test_peek_str_len() {
+    let mut state = State::default();
+    state.push_str("hello");
+    state.push_int(1);
+    assert_eq!(state.peek_str_len(), None);
+    assert_eq!(state.peek_n_str_len(0), Some(5));
+    assert_eq!(state.peek_n_str_len(1), None);
+    assert_eq!(state.peek_n_str_len(2), None);
+    assert_eq!(state.stack_len(), 2);
+
+    state.pop();
+    state.push_str("");
+    assert_eq!(state.peek_str_len(), Some(0));
+    assert_eq!(state.pop_str().as_deref(), Some(""));
+    assert_eq!(state.pop_str().as_deref(), Some("hello"));
+}
+
+// C-style function to declare a global named after its argument from a borrowed state.
+unsafe extern "C" fn declare_from_callback(state: *mut YASL_State) -> i32 {
+    let mut state = State::from_memory(state).unwrap();
+    let n = state.pop_int();
+    state.push_int(n);
+    state
+        .init_global_slice(&format!("from_callback_{n}"))
+        .unwrap();
+    0
+}
+
+/// Test that borrowed states on different threads can declare globals at the same time.
+#[test]
+fn test_borrowed_names_across_threads() {
+    let threads = (0..8)
+        .map(|n| {
+            std::thread::spawn(move || {
+                let mut state = State::from_source(&format!("declare({n});"));
+                state.push_cfunction(declare_from_callback, 1);
+                state.init_global_slice("declare").unwrap();
+                state.execute().unwrap();
+                state
+                    .load_global_slice(&format!("from_callback_{n}"))
+                    .unwrap();
+                state.pop_int()
+            })
+        })
+        .collect::<Vec<_>>();
+    for (n, thread) in threads.into_iter().enumerate() {
+        assert_eq!(thread.join().unwrap(), n as i64);
+    }
+}
@@ -0,0 +1,78 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "csv")]
+
+use yaslapi::{aux::Object, modules::csv, State, StateError};
+
+/// Test parsing CSV text into tables and writing tables back as CSV.
+#[test]
+fn test_csv_module() {
+    let mut state = State::from_source(
+        r#"
+        rows = csv.parse('name,count\napple,3\n"pear, green",\n');
+        names = [rows[0].name, rows[1].name, rows[1].count];
+        text = csv.stringify(rows);
+        ordered = csv.stringify([{ .b: 1.5, .a: true }, { .a: 'x' }], ['b', 'a']);
+        "#,
+    );
+    for name in ["rows", "names", "text", "ordered"] {
+        state.declare_global(name).unwrap();
+    }
+    csv::declare(&mut state);
+    state.execute().expect("Failed to execute the script.");
+
+    assert_eq!(
+        state.pop_global_slice("names", None),
+        Ok(Object::List(
+            ["apple", "pear, green", ""]
+                .map(|s| Object::Str(s.to_owned()))
+                .to_vec()
+        ))
+    );
+    assert_eq!(
+        state.pop_global_slice("text", None),
+        Ok(Object::Str(
+            "count,name\n3,apple\n,\"pear, green\"\n".to_owned()
+        ))
+    );
+    assert_eq!(
+        state.pop_global_slice("ordered", None),
+        Ok(Object::Str("b,a\n1.5,true\n,x\n".to_owned()))
+    );
+
+    for invalid in [
+        "parse('a,b\\n1,2,3\\n')",
+        "stringify([1])",
+        "stringify([{ .a: [] }])",
+    ] {
+        let mut state = State::from_source(&format!("csv.{invalid};"));
+        csv::declare(&mut state);
+        assert!(
+            matches!(
+                state.execute(),
+                Err(StateError::ValueError | StateError::TypeError)
+            ),
+            "{invalid}"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "bitflags")]
+
+use bitflags::bitflags;
+use yaslapi::{convert::FromYasl, flags::FlagSet, State, StateError};
+
+bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Access: u8 {
+        const READ = 0b001;
+        const WRITE = 0b010;
+        const EXECUTE = 0b100;
+    }
+}
+
+/// Test declaring flags for a script and reading the combined flags back.
+#[test]
+fn test_declare_flags() {
+    let mut state = State::from_source(
+        "
+        rw = Access.combine(Access.READ, Access.WRITE);
+        x = Access.EXECUTE;
+        none = Access.combine();
+        ",
+    );
+    state.declare_flags::<Access>("Access").unwrap();
+    for name in ["rw", "x", "none"] {
+        state.declare_global(name).unwrap();
+    }
+    assert!(state.execute().is_ok());
+
+    state.load_global_slice("rw").unwrap();
+    assert_eq!(
+        FlagSet::<Access>::pop_from(&mut state),
+        Ok(FlagSet(Access::READ | Access::WRITE))
+    );
+    state.load_global_slice("x").unwrap();
+    assert_eq!(
+        FlagSet::<Access>::pop_from(&mut state),
+        Ok(FlagSet(Access::EXECUTE))
+    );
+    state.load_global_slice("none").unwrap();
+    assert_eq!(
+        FlagSet::<Access>::pop_from(&mut state),
+        Ok(FlagSet(Access::empty()))
+    );
+}
+
+/// Test that unknown bits are rejected, both by `combine` and when popping.
+#[test]
+fn test_unknown_flags() {
+    let mut state = State::from_source("Access.combine(Access.READ, 8);");
+    state.declare_flags::<Access>("Access").unwrap();
+    assert_eq!(state.execute(), Err(StateError::ValueError));
+
+    let mut state = State::default();
+    state.push_int(8);
+    assert_eq!(
+        FlagSet::<Access>::pop_from(&mut state),
+        Err(StateError::ValueError)
+    );
+    assert_eq!(state.stack_len(), 1);
+    state.pop();
+    state.push_str("read");
+    assert_eq!(
+        FlagSet::<Access>::pop_from(&mut state),
+        Err(StateError::TypeError)
+    );
+}
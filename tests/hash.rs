@@ -0,0 +1,73 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "hash")]
+
+use yaslapi::{aux::Object, modules::hash, State, StateError};
+
+/// Test hashing strings, including a string with a zero byte.
+#[test]
+fn test_hash_module() {
+    let mut state = State::from_source(
+        r"
+        sha256 = hash.sha256('hello');
+        sha1 = hash.sha1('hello');
+        md5 = hash.md5('hello');
+        crc32 = hash.crc32('hello');
+        binary = hash.md5('a\0b');
+        ",
+    );
+    let expected = [
+        (
+            "sha256",
+            Object::Str(
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_owned(),
+            ),
+        ),
+        (
+            "sha1",
+            Object::Str("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d".to_owned()),
+        ),
+        (
+            "md5",
+            Object::Str("5d41402abc4b2a76b9719d911017c592".to_owned()),
+        ),
+        ("crc32", Object::Int(0x3610_a686)),
+        (
+            "binary",
+            Object::Str("70350f6027bce3713f6b76473084309b".to_owned()),
+        ),
+    ];
+    for (name, _) in &expected {
+        state.declare_global(name).unwrap();
+    }
+    hash::declare(&mut state);
+    state.execute().expect("Failed to execute the script.");
+
+    for (name, value) in expected {
+        assert_eq!(state.pop_global_slice(name, None), Ok(value), "{name}");
+    }
+
+    let mut state = State::from_source("hash.sha1(1);");
+    hash::declare(&mut state);
+    assert_eq!(state.execute(), Err(StateError::TypeError));
+}
@@ -0,0 +1,65 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::thread;
+
+use yaslapi::{aux::Object, host::ScriptHost, State, StateError};
+
+/// Test calling script functions on a host thread from several threads and an async task.
+#[test]
+fn test_script_host() {
+    let mut state = State::from_source("count = 0; add = fn(a, b) { count += 1; return a + b; };");
+    state.declare_global("count").unwrap();
+    state.declare_global("add").unwrap();
+    state
+        .execute()
+        .expect("Failed to execute the state machine");
+
+    let host = ScriptHost::new(state);
+    thread::scope(|scope| {
+        for i in 0..4 {
+            let handle = host.handle();
+            scope.spawn(move || {
+                let result = handle
+                    .eval(format!("add({i}, 1)"))
+                    .wait()
+                    .expect("Failed to call the global function");
+                assert_eq!(result, Object::Int(i + 1));
+            });
+        }
+    });
+
+    let handle = host.handle();
+    assert_eq!(handle.eval("count * 10").wait(), Ok(Object::Int(40)));
+
+    // Replies are also futures.
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Failed to build the runtime.");
+    let stack_len = runtime.block_on(handle.with(|state: &mut State| state.stack_len()));
+    assert_eq!(stack_len, Ok(0));
+
+    // The state is handed back when the host stops, and later requests fail.
+    let mut state = host.stop();
+    assert_eq!(state.eval("count"), Ok(Object::Int(4)));
+    assert_eq!(handle.eval("count").wait(), Err(StateError::Generic));
+}
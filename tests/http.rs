@@ -0,0 +1,127 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "http")]
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+    time::Duration,
+};
+
+use yaslapi::{
+    aux::{HashableObject, Object},
+    modules::http::{self, HttpConfig},
+    State, StateError,
+};
+
+/// Answer `n` requests on the listener with their method and body.
+fn serve(listener: TcpListener, n: usize) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for stream in listener.incoming().take(n) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            // Read the request line and headers, then the body if there is one.
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let method = line.split_whitespace().next().unwrap().to_owned();
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let reply = format!("{method} {}", String::from_utf8(body).unwrap());
+            let status = if method == "GET" {
+                "200 OK"
+            } else {
+                "201 Created"
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nX-Test: yes\r\nConnection: close\r\n\r\n{reply}",
+                reply.len()
+            )
+            .unwrap();
+        }
+    })
+}
+
+/// Get the value of a string key of the table.
+fn field(table: &HashMap<HashableObject, Object>, key: &str) -> Object {
+    table[&HashableObject::Str(key.to_owned())].clone()
+}
+
+/// Test sending requests to an allowed host, and rejecting other hosts.
+#[test]
+fn test_http_module() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = serve(listener, 2);
+
+    let mut state = State::from_source(&format!(
+        "
+        got = http.get('http://127.0.0.1:{port}/data');
+        posted = http.post('http://127.0.0.1:{port}/data', 'hello');
+        "
+    ));
+    state.declare_global("got").unwrap();
+    state.declare_global("posted").unwrap();
+    let config = HttpConfig::new()
+        .allow_host("127.0.0.1")
+        .timeout(Duration::from_secs(5));
+    http::declare(&mut state, config.clone());
+    state.execute().expect("Failed to execute the script.");
+    server.join().unwrap();
+
+    let Ok(Object::Table(got)) = state.pop_global_slice("got", None) else {
+        panic!("Expected a response table.");
+    };
+    assert_eq!(field(&got, "status"), Object::Int(200));
+    assert_eq!(field(&got, "body"), Object::Str("GET ".to_owned()));
+    let Object::Table(headers) = field(&got, "headers") else {
+        panic!("Expected a headers table.");
+    };
+    assert_eq!(field(&headers, "x-test"), Object::Str("yes".to_owned()));
+
+    let Ok(Object::Table(posted)) = state.pop_global_slice("posted", None) else {
+        panic!("Expected a response table.");
+    };
+    assert_eq!(field(&posted, "status"), Object::Int(201));
+    assert_eq!(field(&posted, "body"), Object::Str("POST hello".to_owned()));
+
+    // Hosts which weren't allowed are rejected before connecting.
+    let mut state = State::from_source(&format!("http.get('http://localhost:{port}/');"));
+    http::declare(&mut state, config);
+    assert_eq!(state.execute(), Err(StateError::ValueError));
+}
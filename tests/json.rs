@@ -0,0 +1,54 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "json")]
+
+use serde_json::{json, Value};
+use yaslapi::{aux::Object, State, StateError};
+
+/// Test passing JSON to a script and returning the result as JSON.
+#[test]
+fn test_json_round_trip() {
+    let mut state = State::from_source(
+        "summary = fn(data) -> { 'names': data.names, 'total': data.a + data.b };",
+    );
+    state.declare_global("summary").unwrap();
+    state.execute().unwrap();
+
+    let input = json!({ "a": 1, "b": 2.5, "names": ["x", null, true] });
+    let data = Object::try_from(input).unwrap();
+    let result: Object = state.call_global_slice("summary", data).unwrap();
+    assert_eq!(
+        Value::try_from(result),
+        Ok(json!({ "names": ["x", null, true], "total": 3.5 }))
+    );
+
+    // Values which JSON can't represent are errors.
+    assert_eq!(
+        Value::try_from(Object::Float(f64::NAN)),
+        Err(StateError::ValueError)
+    );
+    assert_eq!(
+        Object::try_from(json!(u64::MAX)).map(|_| ()),
+        Err(StateError::ValueError)
+    );
+}
@@ -0,0 +1,76 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use yaslapi::{modules, State};
+
+/// A logger which keeps every record's level, target, and message.
+struct TestLogger(Mutex<Vec<(Level, String, String)>>);
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+    fn log(&self, record: &Record) {
+        self.0.lock().unwrap().push((
+            record.level(),
+            record.target().to_owned(),
+            record.args().to_string(),
+        ));
+    }
+    fn flush(&self) {}
+}
+
+static LOGGER: TestLogger = TestLogger(Mutex::new(Vec::new()));
+
+/// Test that each logging function forwards its message with the script's target.
+#[test]
+fn test_log_module() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let mut state = State::from_source(
+        "
+        log.error('failed');
+        log.warn(1.5);
+        log.info([1, 'a']);
+        log.debug(undef);
+        ",
+    );
+    modules::log::declare(&mut state, "scripts/test.yasl");
+    state.execute().expect("Failed to execute the script.");
+
+    let target = "scripts/test.yasl".to_owned();
+    assert_eq!(
+        *LOGGER.0.lock().unwrap(),
+        [
+            (Level::Error, target.clone(), "failed".to_owned()),
+            (Level::Warn, target.clone(), "1.5".to_owned()),
+            (Level::Info, target.clone(), "[1, a]".to_owned()),
+            (Level::Debug, target, "undef".to_owned()),
+        ]
+    );
+}
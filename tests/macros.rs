@@ -0,0 +1,38 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "macros")]
+
+use yaslapi::{yasl_source, State};
+
+/// Test embedding a script which was checked at compile time, with host globals declared.
+#[test]
+fn test_yasl_source() {
+    const SOURCE: &str = yasl_source!("tests/test.yasl", "answer", "rust_print");
+    assert_eq!(SOURCE, include_str!("test.yasl"));
+
+    // The embedded source compiles the same as it was checked.
+    let mut state = State::from_source(SOURCE);
+    state.declare_global("answer").unwrap();
+    state.declare_global("rust_print").unwrap();
+    assert!(state.compile().is_ok());
+}
@@ -0,0 +1,277 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::time::Duration;
+
+use yaslapi::{aux::Object, modules, State, StateError};
+
+/// Test reading the process environment through the `env` library.
+#[test]
+fn test_env_module() {
+    std::env::set_var("YASLAPI_TEST_ENV", "present");
+
+    let mut state = State::from_source(
+        "
+        value = env.get('YASLAPI_TEST_ENV');
+        missing = env.get('YASLAPI_TEST_ENV_MISSING');
+        in_vars = env.vars()['YASLAPI_TEST_ENV'];
+        cwd = env.cwd();
+        platform = env.platform;
+        arg_count = len env.args;
+        ",
+    );
+    for name in [
+        "value",
+        "missing",
+        "in_vars",
+        "cwd",
+        "platform",
+        "arg_count",
+    ] {
+        state.declare_global(name).unwrap();
+    }
+    modules::env::declare(&mut state);
+    state.execute().expect("Failed to execute the script.");
+
+    let present = Object::Str("present".to_owned());
+    assert_eq!(state.pop_global_slice("value", None), Ok(present.clone()));
+    assert_eq!(state.pop_global_slice("missing", None), Ok(Object::Undef));
+    assert_eq!(state.pop_global_slice("in_vars", None), Ok(present));
+    assert_eq!(
+        state.pop_global_slice("cwd", None),
+        Ok(Object::Str(
+            std::env::current_dir().unwrap().display().to_string()
+        ))
+    );
+    assert_eq!(
+        state.pop_global_slice("platform", None),
+        Ok(Object::Str(std::env::consts::OS.to_owned()))
+    );
+    assert_eq!(
+        state.pop_global_slice("arg_count", None),
+        Ok(Object::Int(std::env::args().count() as i64))
+    );
+
+    // Bad arguments are runtime errors, and the library is only available when declared.
+    let mut state = State::from_source("env.get(1);");
+    modules::env::declare(&mut state);
+    assert_eq!(state.execute(), Err(StateError::TypeError));
+    assert!(State::from_source("env.cwd();").execute().is_err());
+}
+
+/// Test that the `random` library's sequences are reproducible from a seed.
+#[test]
+fn test_random_module() {
+    let script = "
+        ints = [];
+        for i in [0, 1, 2, 3, 4, 5, 6, 7] {
+            ints->push(random.int(-3, 3));
+        }
+        x = random.float();
+        random.seed(7);
+        first = random.int(0, 1000);
+        random.seed(7);
+        again = random.int(0, 1000);
+        same = random.int(5, 5);
+        full = random.int(-9223372036854775807 - 1, 9223372036854775807);
+    ";
+    let run = |seed| {
+        let mut state = State::from_source(script);
+        for name in ["ints", "x", "first", "again", "same", "full"] {
+            state.declare_global(name).unwrap();
+        }
+        modules::random::declare(&mut state, seed);
+        state.execute().expect("Failed to execute the script.");
+        state
+    };
+
+    let mut state = run(42);
+    let Ok(Object::List(ints)) = state.pop_global_slice("ints", None) else {
+        panic!("Expected a list of ints.");
+    };
+    assert!(ints
+        .iter()
+        .all(|i| matches!(i, Object::Int(i) if (-3..=3).contains(i))));
+    let Ok(Object::Float(x)) = state.pop_global_slice("x", None) else {
+        panic!("Expected a float.");
+    };
+    assert!((0.0..1.0).contains(&x));
+    assert_eq!(
+        state.pop_global_slice("first", None),
+        state.pop_global_slice("again", None)
+    );
+    assert_eq!(state.pop_global_slice("same", None), Ok(Object::Int(5)));
+
+    // The same seed gives the same sequence, and a different seed gives a different one.
+    let mut same_seed = run(42);
+    assert_eq!(
+        same_seed.pop_global_slice("ints", None),
+        Ok(Object::List(ints.clone()))
+    );
+    let mut other_seed = run(43);
+    assert_ne!(other_seed.pop_global_slice("x", None), Ok(Object::Float(x)));
+
+    let mut state = State::from_source("random.int(2, 1);");
+    modules::random::declare(&mut state, 0);
+    assert_eq!(state.execute(), Err(StateError::ValueError));
+}
+
+/// Test base64 and hex encoding with the `encoding` library, including strings which aren't valid UTF-8.
+#[test]
+fn test_encoding_module() {
+    let mut state = State::from_source(
+        r"
+        const raw = 'a\0\xff';
+        b64 = [encoding.base64_encode(''), encoding.base64_encode('ab'), encoding.base64_encode('hello')];
+        hex = encoding.hex_encode(raw);
+        decoded = encoding.base64_decode('aGVsbG8=');
+        round_trips = encoding.base64_decode(encoding.base64_encode(raw)) == raw &&
+            encoding.hex_decode('6100FF') == raw;
+        ",
+    );
+    for name in ["b64", "hex", "decoded", "round_trips"] {
+        state.declare_global(name).unwrap();
+    }
+    modules::encoding::declare(&mut state);
+    state.execute().expect("Failed to execute the script.");
+
+    assert_eq!(
+        state.pop_global_slice("b64", None),
+        Ok(Object::List(
+            ["", "YWI=", "aGVsbG8="]
+                .map(|s| Object::Str(s.to_owned()))
+                .to_vec()
+        ))
+    );
+    assert_eq!(
+        state.pop_global_slice("hex", None),
+        Ok(Object::Str("6100ff".to_owned()))
+    );
+    assert_eq!(
+        state.pop_global_slice("decoded", None),
+        Ok(Object::Str("hello".to_owned()))
+    );
+    assert_eq!(
+        state.pop_global_slice("round_trips", None),
+        Ok(Object::Bool(true))
+    );
+
+    for invalid in [
+        "base64_decode('aGVsbG8')",
+        "base64_decode('a=Gk')",
+        "hex_decode('abc')",
+        "hex_decode('zz')",
+    ] {
+        let mut state = State::from_source(&format!("encoding.{invalid};"));
+        modules::encoding::declare(&mut state);
+        assert_eq!(state.execute(), Err(StateError::ValueError), "{invalid}");
+    }
+}
+
+/// Test calling delayed and repeating callbacks through the `timer` library.
+#[test]
+fn test_timer_module() {
+    let mut state = State::from_source(
+        "
+        once = 0;
+        ticks = 0;
+        timer.after(1.5, fn() { once = once + 1; });
+        const repeating = timer.every(1, fn() { ticks = ticks + 1; });
+        timer.after(3, fn() { timer.cancel(repeating); });
+        const cancelled = timer.after(0, fn() { once = -1; });
+        timer.cancel(cancelled);
+        ",
+    );
+    for name in ["once", "ticks"] {
+        state.declare_global(name).unwrap();
+    }
+    let mut scheduler = modules::timer::declare(&mut state, Duration::ZERO);
+    scheduler.execute().expect("Failed to execute the script.");
+
+    // SAFETY: The timers only do arithmetic on integers and cancel timers, which can't fail.
+    let mut tick = |seconds: f64| {
+        assert!(unsafe { scheduler.tick(Duration::from_secs_f64(seconds)) }.is_empty());
+        (
+            scheduler.pop_global_slice("once", None).unwrap(),
+            scheduler.pop_global_slice("ticks", None).unwrap(),
+        )
+    };
+    assert_eq!(tick(0.5), (Object::Int(0), Object::Int(0)));
+    assert_eq!(tick(1.0), (Object::Int(0), Object::Int(1)));
+    assert_eq!(tick(2.0), (Object::Int(1), Object::Int(2)));
+    // The repeating timer is due at 3.0 too, but it was scheduled before the timer which cancels it.
+    assert_eq!(tick(3.0), (Object::Int(1), Object::Int(3)));
+    assert_eq!(tick(10.0), (Object::Int(1), Object::Int(3)));
+
+    // A scheduler can be created again for a state whose library was declared earlier.
+    state.declare_global("late").unwrap();
+    state
+        .run_chunk("late = timer.after(1, fn() { once = 2; });")
+        .expect("Failed to schedule the late timer.");
+    let mut scheduler = modules::timer::Scheduler::new(&mut state);
+    assert!(unsafe { scheduler.tick(Duration::from_secs(11)) }.is_empty());
+    assert_eq!(scheduler.pop_global_slice("once", None), Ok(Object::Int(2)));
+
+    for invalid in ["after(-1, fn() {})", "every(0, fn() {})"] {
+        let mut state = State::from_source(&format!("timer.{invalid};"));
+        modules::timer::declare(&mut state, Duration::ZERO);
+        assert_eq!(state.execute(), Err(StateError::ValueError), "{invalid}");
+    }
+    let mut state = State::from_source("timer.after('soon', fn() {});");
+    modules::timer::declare(&mut state, Duration::ZERO);
+    assert_eq!(state.execute(), Err(StateError::TypeError));
+}
+
+/// Test that timers whose functions are closures over a script's top-level locals can finish, be cancelled,
+/// and be dropped with the state after the script has executed.
+#[test]
+fn test_timer_closures() {
+    let mut state = State::from_source(
+        "
+        const step = 5;
+        total = 0;
+        timer.after(1, fn() { total = total + step; });
+        repeating = timer.every(1, fn() { total = total + step * 10; });
+        ",
+    );
+    for name in ["total", "repeating"] {
+        state.declare_global(name).unwrap();
+    }
+    let mut scheduler = modules::timer::declare(&mut state, Duration::ZERO);
+    scheduler.execute().expect("Failed to execute the script.");
+
+    // SAFETY: The timers only do arithmetic on integers, which can't fail.
+    assert!(unsafe { scheduler.tick(Duration::from_secs(1)) }.is_empty());
+    assert_eq!(
+        scheduler.pop_global_slice("total", None),
+        Ok(Object::Int(55))
+    );
+    scheduler
+        .run_chunk("timer.cancel(repeating);")
+        .expect("Failed to cancel the repeating timer.");
+    assert!(unsafe { scheduler.tick(Duration::from_secs(3)) }.is_empty());
+    assert_eq!(
+        scheduler.pop_global_slice("total", None),
+        Ok(Object::Int(55))
+    );
+    drop(state);
+}
@@ -0,0 +1,63 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "msgpack")]
+
+use yaslapi::{aux::Object, State, StateError};
+
+/// Test persisting script data as MessagePack and loading it into another state.
+#[test]
+fn test_msgpack_round_trip() {
+    let mut state =
+        State::from_source("data = { 'name': 'yasl', 'scores': [1, 2.5, undef], true: -3 };");
+    state.declare_global("data").unwrap();
+    state.execute().unwrap();
+    let bytes = state
+        .pop_global_slice("data", None)
+        .unwrap()
+        .to_msgpack()
+        .unwrap();
+
+    let mut state = State::from_source("ok = data.name == 'yasl' && data.scores[1] == 2.5 && data[true] == -3 && len data.scores == 3;");
+    state.declare_global("ok").unwrap();
+    state
+        .push_object(&Object::from_msgpack(&bytes).unwrap())
+        .unwrap();
+    state.init_global_slice("data").unwrap();
+    state.execute().unwrap();
+    assert!(matches!(
+        state.pop_global_slice("ok", None),
+        Ok(Object::Bool(true))
+    ));
+
+    // Trailing or truncated data is rejected.
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        Object::from_msgpack(&trailing),
+        Err(StateError::ValueError)
+    ));
+    assert!(matches!(
+        Object::from_msgpack(&bytes[..bytes.len() - 1]),
+        Err(StateError::ValueError)
+    ));
+}
@@ -0,0 +1,84 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "parallel")]
+
+use yaslapi::{aux::Object, parallel::ParallelRunner, Error, State, StateError};
+
+/// Test executing a batch of scripts in parallel and collecting their results in order.
+#[test]
+fn test_parallel_runner() {
+    let runner = ParallelRunner::new().setup(|state: &mut State| {
+        state.push_int(10);
+        state.init_global_slice("base").unwrap();
+    });
+
+    let mut scripts = (0..100)
+        .map(|i| format!("result = base + {i};"))
+        .collect::<Vec<_>>();
+    scripts.push("result = ;".to_owned());
+    scripts.push("let unused = 1;".to_owned());
+
+    let results = runner.run(&scripts);
+    assert_eq!(results.len(), 102);
+    for (i, result) in results[..100].iter().enumerate() {
+        assert_eq!(result, &Ok(Object::Int(10 + i as i64)));
+    }
+    assert_eq!(results[100], Err(StateError::SyntaxError));
+    assert_eq!(results[101], Ok(Object::Undef));
+
+    // The result global can be renamed.
+    let runner = ParallelRunner::default().result_global("answer");
+    assert_eq!(
+        runner.run(&["answer = 'yes';"]),
+        [Ok(Object::Str("yes".to_owned()))]
+    );
+}
+
+/// Test compiling a batch of script files in parallel and reporting each one which failed.
+#[test]
+fn test_compile_all() {
+    let dir = std::env::temp_dir().join(format!("yaslapi_compile_all_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.yasl");
+    let broken = dir.join("broken.yasl");
+    let missing = dir.join("missing.yasl");
+    std::fs::write(&good, "result = 6 * 7;").unwrap();
+    std::fs::write(&broken, "result = 6 *;").unwrap();
+
+    let runner = ParallelRunner::new();
+    let mut results = runner.compile_all(&[&good, &broken, &missing]).into_iter();
+    let mut state = results.next().unwrap().unwrap();
+    state.execute().unwrap();
+    assert_eq!(state.pop_global_slice("result", None), Ok(Object::Int(42)));
+
+    let error = results.next().unwrap().err().unwrap();
+    assert_eq!(error.path, broken);
+    assert!(matches!(error.error, Error::State(StateError::SyntaxError)));
+    assert!(error.message.contains("SyntaxError"), "{}", error.message);
+
+    let error = results.next().unwrap().err().unwrap();
+    assert!(matches!(error.error, Error::Load(_)));
+    assert!(error.message.is_empty());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,73 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::thread;
+use yaslapi::{aux::Object, pool::StatePool};
+
+/// Test sharing a pool of pre-initialized states between threads.
+#[test]
+fn test_state_pool() {
+    let pool = StatePool::new(2, |state| {
+        state.declare_libs();
+        state.push_int(3);
+        state.init_global_slice("multiplier").unwrap();
+        state.declare_global("result").unwrap();
+    });
+    assert_eq!(pool.size(), 2);
+
+    thread::scope(|scope| {
+        let handles = (0..8)
+            .map(|i| {
+                let pool = &pool;
+                scope.spawn(move || {
+                    let mut state = pool.get();
+                    state.reset_from_source(&format!("result = multiplier * {i};"));
+                    state.execute().unwrap();
+                    state.load_global_slice("result").unwrap();
+                    state.pop_int()
+                })
+            })
+            .collect::<Vec<_>>();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), 3 * i as i64);
+        }
+    });
+    assert_eq!(pool.available(), 2);
+
+    // States are returned when dropped, and discarded states are replaced by new ones.
+    let mut first = pool.try_get().unwrap();
+    let _second = pool.try_get().unwrap();
+    assert!(pool.try_get().is_none());
+    first.push_int(4);
+    first.set_global_slice("multiplier").unwrap();
+    first.discard();
+    assert_eq!(first.eval("multiplier"), Ok(Object::Int(3)));
+    drop(first);
+    assert_eq!(pool.available(), 1);
+}
+
+/// Test that an empty pool is rejected, since checking out a state would wait forever.
+#[test]
+#[should_panic(expected = "at least one state")]
+fn test_empty_state_pool() {
+    let _ = StatePool::new(0, |_| {});
+}
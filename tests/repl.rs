@@ -0,0 +1,164 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use yaslapi::{
+    aux::Object,
+    repl::{ReplResult, ReplSession},
+    State, StateError,
+};
+
+/// Test that a REPL session keeps globals between inputs and returns expression values.
+#[test]
+fn test_repl_session_values() {
+    let mut session = ReplSession::new();
+
+    // Statements produce `undef`, while declaring globals for later inputs.
+    assert!(matches!(
+        session.feed("let x = 20"),
+        Ok(ReplResult::Value(Object::Undef))
+    ));
+    assert!(matches!(
+        session.feed("x * 2 + 2"),
+        Ok(ReplResult::Value(Object::Int(42)))
+    ));
+    assert!(matches!(
+        session.feed("'a' ~ 'b'"),
+        Ok(ReplResult::Value(Object::Str(s))) if s == "ab"
+    ));
+}
+
+/// Test that unfinished input is distinguished from invalid input.
+#[test]
+fn test_repl_session_incomplete() {
+    let mut session = ReplSession::new();
+
+    // A function definition spanning several lines.
+    assert!(matches!(
+        session.feed("fn add(a, b) {"),
+        Ok(ReplResult::Incomplete)
+    ));
+    assert!(session.is_pending());
+    assert!(matches!(
+        session.feed("    return a + b"),
+        Ok(ReplResult::Incomplete)
+    ));
+    assert!(matches!(session.feed("}"), Ok(ReplResult::Value(_))));
+    assert!(!session.is_pending());
+    assert!(matches!(
+        session.feed("add(1, 2)"),
+        Ok(ReplResult::Value(Object::Int(3)))
+    ));
+
+    // A complete but invalid input is a syntax error.
+    assert!(matches!(
+        session.feed("let = 5"),
+        Err(StateError::SyntaxError)
+    ));
+}
+
+/// Test detecting unfinished input without a session.
+#[test]
+fn test_is_input_incomplete() {
+    // Unclosed blocks, brackets, raw strings, and comments.
+    assert!(State::is_input_incomplete("fn f(a) {"));
+    assert!(State::is_input_incomplete("let l = [1, 2,"));
+    assert!(State::is_input_incomplete("let s = `multi\nline"));
+    assert!(State::is_input_incomplete("/* comment"));
+
+    // Lines ending with an operator or a keyword that needs more code.
+    assert!(State::is_input_incomplete("let x = 1 +"));
+    assert!(State::is_input_incomplete("if x { echo 1 } else"));
+
+    // Complete input, even when it is invalid.
+    assert!(!State::is_input_incomplete("fn f(a) { return a }"));
+    assert!(!State::is_input_incomplete("echo 'brace { in a string'"));
+    assert!(!State::is_input_incomplete("echo \"#{ {1: 2}[1] }\""));
+    assert!(!State::is_input_incomplete("let x = )"));
+}
+
+#[test]
+fn test_completions() {
+    let mut session = ReplSession::new();
+    assert!(matches!(
+        session.feed("let my_counter = 0"),
+        Ok(ReplResult::Value(Object::Undef))
+    ));
+    assert!(session.feed("fn my_func() { return 1; }").is_ok());
+    assert!(session
+        .feed("let nested = { 'inner': { 'leaf': 1, 'lean': 2 } }")
+        .is_ok());
+
+    // Globals from the standard library and from top-level declarations.
+    let state = session.state();
+    assert_eq!(state.completions("my_"), vec!["my_counter", "my_func"]);
+    assert_eq!(state.completions("ma"), vec!["math"]);
+
+    // Keys of tables, including nested tables.
+    assert!(state
+        .completions("math.sq")
+        .contains(&"math.sqrt".to_owned()));
+    assert_eq!(
+        state.completions("nested.inner.le"),
+        vec!["nested.inner.leaf", "nested.inner.lean"]
+    );
+    assert!(state.completions("nested.missing.").is_empty());
+    assert!(state.completions("my_counter.").is_empty());
+
+    // The stack is left balanced, so evaluation still works.
+    assert!(matches!(
+        session.feed("my_func()"),
+        Ok(ReplResult::Value(Object::Int(1)))
+    ));
+}
+
+#[test]
+fn test_snapshot_globals() {
+    let mut session = ReplSession::new();
+    assert!(session.feed("let x = 1").is_ok());
+    assert!(session.feed("let items = [1, {'a': 2}]").is_ok());
+    assert!(session.feed("fn f() { return x; }").is_ok());
+    let snapshot = session.state().snapshot_globals();
+
+    // Plain data is captured, while functions and libraries are skipped.
+    assert!(matches!(snapshot.get("x"), Some(Object::Int(1))));
+    assert!(snapshot.get("f").is_none());
+    assert!(snapshot.get("math").is_none());
+
+    assert!(session.feed("x = 5").is_ok());
+    assert!(session.feed("items->push(3)").is_ok());
+    assert!(session.feed("let y = 2").is_ok());
+    session
+        .state()
+        .restore_globals(&snapshot)
+        .expect("Failed to restore the globals");
+
+    // Values are rolled back, new globals are cleared, and functions still work.
+    let mut value = |expr| match session.feed(expr) {
+        Ok(ReplResult::Value(value)) => value.to_yasl_string(),
+        _ => panic!("Failed to evaluate {expr}"),
+    };
+    assert_eq!(value("x"), "1");
+    assert_eq!(value("items"), "[1, {a: 2}]");
+    assert_eq!(value("y"), "undef");
+    assert_eq!(value("f()"), "1");
+    assert_eq!(value("math.sqrt(4.0)"), "2.0");
+}
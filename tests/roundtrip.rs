@@ -0,0 +1,71 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+
+use yaslapi::{
+    aux::{HashableObject, Object},
+    roundtrip::assert_roundtrip,
+};
+
+#[test]
+fn scalars_round_trip() {
+    for object in [
+        Object::Undef,
+        Object::Bool(true),
+        Object::Int(i64::MIN),
+        Object::Int(i64::MAX),
+        Object::Float(f64::NAN),
+        Object::Float(-0.0),
+        Object::Float(f64::NEG_INFINITY),
+        Object::Str("line\nbreak ✓".to_owned()),
+    ] {
+        assert_roundtrip(&object);
+    }
+}
+
+#[test]
+fn nested_containers_round_trip() {
+    let key = |o: Object| HashableObject::try_from(o).unwrap();
+    assert_roundtrip(&Object::List(vec![
+        Object::Table(HashMap::from([
+            (key(Object::Int(1)), Object::Str("int".to_owned())),
+            (key(Object::Float(1.0)), Object::Str("float".to_owned())),
+            (key(Object::Float(-0.0)), Object::List(vec![])),
+        ])),
+        Object::List(vec![Object::Undef, Object::Float(f64::NAN)]),
+    ]));
+}
+
+#[test]
+#[should_panic(expected = "round trip changed the object")]
+fn truncated_string_is_caught() {
+    assert_roundtrip(&Object::Str("before\0after".to_owned()));
+}
+
+#[cfg(feature = "proptest")]
+proptest::proptest! {
+    #[test]
+    fn generated_objects_round_trip(object in yaslapi::roundtrip::any_object()) {
+        assert_roundtrip(&object);
+    }
+}
@@ -0,0 +1,79 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "sqlite")]
+
+use std::collections::HashMap;
+
+use yaslapi::{
+    aux::{HashableObject, Object},
+    modules::sqlite,
+    State, StateError,
+};
+
+/// Create a row table from its non-null columns.
+fn row<const N: usize>(columns: [(&str, Object); N]) -> Object {
+    Object::Table(
+        columns
+            .into_iter()
+            .map(|(k, v)| (HashableObject::Str(k.to_owned()), v))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+/// Test creating, filling, and querying an in-memory database.
+#[test]
+fn test_sqlite_module() {
+    let mut state = State::from_source(
+        "
+        const db = sqlite.open(':memory:');
+        db->execute('CREATE TABLE items (name TEXT, count INTEGER, price REAL)');
+        inserted = db->execute('INSERT INTO items VALUES (?, ?, ?), (?, ?, ?)', ['apple', 3, 0.5, 'pear', undef, 1.0]);
+        rows = db->query('SELECT * FROM items WHERE price >= ? ORDER BY name', [0.0]);
+        ",
+    );
+    state.declare_global("inserted").unwrap();
+    state.declare_global("rows").unwrap();
+    sqlite::declare(&mut state);
+    state.execute().expect("Failed to execute the script.");
+
+    assert_eq!(state.pop_global_slice("inserted", None), Ok(Object::Int(2)));
+    assert_eq!(
+        state.pop_global_slice("rows", None),
+        Ok(Object::List(vec![
+            row([
+                ("name", Object::Str("apple".to_owned())),
+                ("count", Object::Int(3)),
+                ("price", Object::Float(0.5)),
+            ]),
+            row([
+                ("name", Object::Str("pear".to_owned())),
+                ("price", Object::Float(1.0)),
+            ]),
+        ]))
+    );
+
+    // Invalid SQL is a runtime error.
+    let mut state = State::from_source("sqlite.open(':memory:')->query('SELECT * FROM missing');");
+    sqlite::declare(&mut state);
+    assert_eq!(state.execute(), Err(StateError::Generic));
+}
@@ -0,0 +1,89 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+
+use yaslapi::{
+    aux::Object,
+    template::{Template, TemplateError},
+    StateError,
+};
+
+/// Test rendering interpolations and blocks against the given globals.
+#[test]
+fn test_render_template() {
+    let template = Template::parse(
+        "Report for #{name}:\n#{% for n in scores { %}#{% if n >= 50 { %}* #{n} passed\n#{% } %}#{% } %}Braces: #{ {'a': 1}['a'] }, #{'}'}",
+    )
+    .unwrap();
+    let globals = HashMap::from([
+        ("name".to_owned(), Object::Str("class".to_owned())),
+        (
+            "scores".to_owned(),
+            Object::List(vec![Object::Int(70), Object::Int(20), Object::Int(50)]),
+        ),
+    ]);
+    assert_eq!(
+        template.render(&globals).unwrap(),
+        "Report for class:\n* 70 passed\n* 50 passed\nBraces: 1, }"
+    );
+
+    // A template can be rendered again with other globals.
+    let globals = HashMap::from([
+        ("name".to_owned(), Object::Str("nobody".to_owned())),
+        ("scores".to_owned(), Object::List(Vec::new())),
+    ]);
+    assert_eq!(
+        template.render(&globals).unwrap(),
+        "Report for nobody:\nBraces: 1, }"
+    );
+
+    let plain = Template::parse("No tags, 'quotes' and \\ slashes.").unwrap();
+    assert_eq!(
+        plain.render(&HashMap::new()).unwrap(),
+        "No tags, 'quotes' and \\ slashes."
+    );
+}
+
+/// Test the errors from parsing and rendering templates.
+#[test]
+fn test_template_errors() {
+    assert_eq!(
+        Template::parse("abc #{x").unwrap_err(),
+        TemplateError::Unclosed(4)
+    );
+    assert_eq!(
+        Template::parse("#{% if x {").unwrap_err(),
+        TemplateError::Unclosed(0)
+    );
+
+    let template = Template::parse("#{missing}").unwrap();
+    assert_eq!(
+        template.render(&HashMap::new()),
+        Err(TemplateError::Script(StateError::SyntaxError))
+    );
+    let globals = HashMap::from([("not valid".to_owned(), Object::Int(1))]);
+    assert_eq!(
+        template.render(&globals),
+        Err(TemplateError::InvalidGlobal("not valid".to_owned()))
+    );
+}
@@ -0,0 +1,94 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::path::Path;
+
+use yaslapi::{
+    testing::{discover, TestFailure, TestRunner},
+    State, StateError,
+};
+
+yaslapi::new_cfn! {
+    /// Double an int, as an example of a host function under test.
+    DOUBLE(state) 1 => {
+        let n = state.pop_int();
+        state.push_int(2 * n);
+        1
+    }
+}
+
+/// Create a runner which registers `double` in each state.
+fn runner() -> TestRunner {
+    TestRunner::new().setup(|state: &mut State| {
+        state.push_cfunction(DOUBLE.cfn, 1);
+        state.init_global_slice("double").unwrap();
+    })
+}
+
+/// Test running a directory of passing scripts, as a `#[test]` would.
+#[test]
+fn test_passing_scripts() {
+    runner().assert_dir("tests/scripts/pass");
+}
+
+/// Test that failing scripts are reported with their path, line, and message.
+#[test]
+fn test_failing_scripts() {
+    let dir = Path::new("tests/scripts/fail");
+    assert_eq!(
+        discover(dir).unwrap(),
+        [
+            dir.join("function.yasl"),
+            dir.join("mismatch.yasl"),
+            dir.join("sandbox.yasl")
+        ]
+    );
+
+    let failures = runner().run_dir(dir).unwrap();
+    // Functions are rejected rather than compared as `undef`, which would pass.
+    assert_eq!(
+        failures[0],
+        TestFailure {
+            path: dir.join("function.yasl"),
+            line: Some(3),
+            error: StateError::TypeError,
+            message: "TypeError: expect can't compare functions.".to_owned(),
+        }
+    );
+    assert_eq!(
+        failures[1],
+        TestFailure {
+            path: dir.join("mismatch.yasl"),
+            line: Some(3),
+            error: StateError::AssertError,
+            message: "AssertError: expected 4, got 3.".to_owned(),
+        }
+    );
+    assert_eq!(
+        failures[1].to_string(),
+        "tests/scripts/fail/mismatch.yasl:3: AssertError: expected 4, got 3."
+    );
+
+    // Scripts can't access libraries outside of the sandbox.
+    assert_eq!(failures[2].error, StateError::SyntaxError);
+    assert_eq!(failures.len(), 3);
+}
@@ -0,0 +1,50 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "toml")]
+
+use toml::Value;
+use yaslapi::{aux::Object, State, StateError};
+
+/// Test passing a TOML configuration to a script and converting the result back.
+#[test]
+fn test_toml_round_trip() {
+    let mut state =
+        State::from_source("scale = fn(config) -> { 'size': config.size * config.window.scale };");
+    state.declare_global("scale").unwrap();
+    state.execute().unwrap();
+
+    let config: Value = toml::from_str("size = 4\n[window]\nscale = 1.5\n").unwrap();
+    let result: Object = state
+        .call_global_slice("scale", Object::from(config))
+        .unwrap();
+    assert_eq!(
+        <Value as TryFrom<Object>>::try_from(result),
+        Ok(toml::from_str("size = 6.0").unwrap())
+    );
+
+    // TOML has no null value.
+    assert_eq!(
+        <Value as TryFrom<Object>>::try_from(Object::Undef),
+        Err(StateError::TypeError)
+    );
+}
@@ -0,0 +1,54 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use yaslapi::{aux::Object, typestate::SourceState, StateError};
+
+/// Test moving a script through each stage, with globals only readable after execution.
+#[test]
+fn test_typestate_stages() {
+    let mut source = SourceState::from_source("x = x * 2;");
+    source
+        .init_global("x", &Object::Int(21))
+        .expect("Failed to initialize the global");
+
+    let compiled = source.compile().expect("Failed to compile");
+    let mut state = compiled.execute().expect("Failed to execute");
+    state
+        .load_global_slice("x")
+        .expect("Failed to load the global");
+    assert_eq!(state.pop_int(), 42);
+}
+
+/// Test that a failed compile hands back the state with the error.
+#[test]
+fn test_typestate_syntax_error() {
+    let source = SourceState::from_source("let x = ;");
+    let Err(failed) = source.compile() else {
+        panic!("Invalid syntax should not compile");
+    };
+    let (source, error) = *failed;
+    assert_eq!(error, StateError::SyntaxError);
+
+    // The state is still usable through the dynamic API.
+    let mut state = source.into_inner();
+    assert!(state.eval("1 + 1").is_ok());
+}
@@ -0,0 +1,85 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "watch")]
+
+use std::{
+    cell::RefCell,
+    fs,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use yaslapi::{watch::ScriptWatcher, State};
+
+/// Poll until the watcher replaces the state, or a few seconds pass.
+fn poll_until_reload(watcher: &mut ScriptWatcher, state: &mut State) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        if watcher.poll(state) {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+#[test]
+fn test_watcher_reloads_script() {
+    let dir = std::env::temp_dir().join(format!("yaslapi-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("game.yasl");
+    fs::write(&path, "speed = 1; score = 0;").unwrap();
+
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let mut watcher = ScriptWatcher::new(&path)
+        .unwrap()
+        .setup(|state| {
+            state.declare_global("speed").unwrap();
+            state.declare_global("score").unwrap();
+        })
+        .keep_global("score")
+        .on_error({
+            let errors = Rc::clone(&errors);
+            move |message| errors.borrow_mut().push(message.to_owned())
+        });
+
+    let mut state = watcher.load().unwrap();
+    assert_eq!(state.get_global::<i64>("speed"), Ok(1));
+    state.push_int(10);
+    state.set_global_slice("score").unwrap();
+
+    fs::write(&path, "speed = 2; score = 0;").unwrap();
+    assert!(poll_until_reload(&mut watcher, &mut state));
+    assert_eq!(state.get_global::<i64>("speed"), Ok(2));
+    assert_eq!(state.get_global::<i64>("score"), Ok(10));
+    assert!(errors.borrow().is_empty());
+
+    // A broken edit is reported, and the previous state stays in use.
+    fs::write(&path, "speed = ;").unwrap();
+    assert!(!poll_until_reload(&mut watcher, &mut state));
+    assert!(!errors.borrow().is_empty());
+    assert_eq!(state.get_global::<i64>("speed"), Ok(2));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
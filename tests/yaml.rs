@@ -0,0 +1,45 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![cfg(feature = "yaml")]
+
+use serde_yaml::Value;
+use yaslapi::{aux::Object, State};
+
+/// Test passing a YAML configuration to a script and converting the result back.
+#[test]
+fn test_yaml_round_trip() {
+    let mut state = State::from_source(
+        "first = fn(config) -> { 'name': config.names[0], 1: config.missing == undef };",
+    );
+    state.declare_global("first").unwrap();
+    state.execute().unwrap();
+
+    let config: Value = serde_yaml::from_str("names: [alice, bob]\nmissing: ~\n").unwrap();
+    let result: Object = state
+        .call_global_slice("first", Object::try_from(config).unwrap())
+        .unwrap();
+    assert_eq!(
+        Value::try_from(result),
+        Ok(serde_yaml::from_str("name: alice\n1: true\n").unwrap())
+    );
+}
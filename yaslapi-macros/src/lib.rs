@@ -0,0 +1,122 @@
+// MIT License
+
+// Copyright (c) 2023 Ryan Andersen
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! # yaslapi-macros
+//! Procedural macros for [yaslapi](https://crates.io/crates/yaslapi). Use them through the `macros` feature of `yaslapi`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use std::{
+    ffi::{CStr, CString},
+    path::PathBuf,
+};
+use syn::{parse::Parser, punctuated::Punctuated, LitStr, Token};
+
+/// Embed a YASL script as a `&'static str`, checking that it compiles at Rust compile time.
+///
+/// The first argument is the path of the script, relative to the crate's `Cargo.toml`.
+/// The script is compiled with the standard libraries declared. Any further arguments
+/// name globals which the host declares before running the script, so that referencing them
+/// is not reported as an undeclared variable.
+///
+/// ```ignore
+/// let source: &'static str = yasl_source!("scripts/init.yasl", "quit");
+/// let mut state = yaslapi::State::from_source(source);
+/// ```
+#[proc_macro]
+pub fn yasl_source(input: TokenStream) -> TokenStream {
+    let args = match Punctuated::<LitStr, Token![,]>::parse_terminated.parse(input) {
+        Ok(args) if !args.is_empty() => args,
+        Ok(_) => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected the path of a YASL script",
+            )
+            .to_compile_error()
+            .into()
+        }
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut args = args.into_iter();
+    let path_lit = args.next().unwrap();
+    let globals = args.map(|lit| lit.value()).collect::<Vec<_>>();
+
+    // Resolve the script relative to the crate being compiled, as `include_str!` would for the crate root.
+    let mut path = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    path.push(path_lit.value());
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => {
+            let message = format!("could not read `{}`: {e}", path.display());
+            return syn::Error::new(path_lit.span(), message)
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    if let Err(message) = check_syntax(&source, &globals) {
+        let message = format!("`{}` does not compile: {message}", path_lit.value());
+        return syn::Error::new(path_lit.span(), message)
+            .to_compile_error()
+            .into();
+    }
+
+    // Use `include_str!` so that the crate is rebuilt whenever the script changes.
+    let path = path.to_string_lossy();
+    quote!(include_str!(#path)).into()
+}
+
+/// Compile the source with a new YASL state, returning the compiler's error output on failure.
+fn check_syntax(source: &str, globals: &[String]) -> Result<(), String> {
+    unsafe {
+        let state = yaslapi_sys::YASL_newstate_bb(source.as_ptr().cast(), source.len());
+        if state.is_null() {
+            return Err("unable to allocate a YASL state".to_owned());
+        }
+        yaslapi_sys::YASLX_decllibs(state);
+        for name in globals {
+            // YASL copies the name of declared globals, so the C-string may be dropped afterwards.
+            let Ok(name) = CString::new(name.as_str()) else {
+                yaslapi_sys::YASL_delstate(state);
+                return Err(format!("invalid global name {name:?}"));
+            };
+            yaslapi_sys::YASL_declglobal(state, name.as_ptr());
+        }
+
+        // Capture error messages instead of printing them to the build output.
+        yaslapi_sys::YASL_setprinterr_tostr(state);
+        let result = if yaslapi_sys::YASL_compile(state) == 0 {
+            Ok(())
+        } else {
+            yaslapi_sys::YASL_loadprinterr(state);
+            let ptr = yaslapi_sys::YASL_popcstr(state);
+            Err(if ptr.is_null() {
+                "syntax error".to_owned()
+            } else {
+                let message = CString::from_raw(ptr);
+                CStr::to_string_lossy(&message).trim().to_owned()
+            })
+        };
+        yaslapi_sys::YASL_delstate(state);
+        result
+    }
+}